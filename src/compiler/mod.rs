@@ -0,0 +1,390 @@
+#![allow(clippy::transmute_ptr_to_ptr)] // Silence gdnative clippy warnings
+
+use gdnative::{
+    godot_error, godot_print, godot_warn, godot_wrap_method_inner,
+    godot_wrap_method_parameter_count, init::ClassBuilder, methods, Dictionary, GodotString,
+    NativeClass, Node, Object, PackedScene, Resource, Variant, VariantArray,
+};
+use std::path::{Path, PathBuf};
+
+use crate::qodot_map::export::json::JsonValue;
+
+// Batch-compiles every .map file in a directory using a configured QodotMap scene as a
+// template, then quits with a non-zero exit code if any map failed. Intended to be driven
+// from a headless `godot --script` run.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[user_data(gdnative::user_data::RwLockData<QodotCompiler>)]
+#[register_with(register_qodot_compiler)]
+pub struct QodotCompiler {
+    template: Variant,
+    input_directory: GodotString,
+    output_directory: GodotString,
+    format: GodotString,
+    quit_when_done: bool,
+
+    queue: Vec<(GodotString, GodotString)>,
+    current_map: Variant,
+    results: Vec<CompileResult>,
+}
+
+struct CompileResult {
+    map: String,
+    output: String,
+    success: bool,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+fn register_qodot_compiler(builder: &ClassBuilder<QodotCompiler>) {
+    builder
+        .add_property::<Option<Resource>>("template")
+        .with_default(None)
+        .with_getter(QodotCompiler::get_template)
+        .with_setter(QodotCompiler::set_template)
+        .done();
+
+    builder
+        .add_property::<GodotString>("input_directory")
+        .with_default("res://maps".into())
+        .with_ref_getter(QodotCompiler::get_input_directory)
+        .with_setter(QodotCompiler::set_input_directory)
+        .done();
+
+    builder
+        .add_property::<GodotString>("output_directory")
+        .with_default("res://maps/compiled".into())
+        .with_ref_getter(QodotCompiler::get_output_directory)
+        .with_setter(QodotCompiler::set_output_directory)
+        .done();
+
+    builder
+        .add_property::<GodotString>("format")
+        .with_default("tscn".into())
+        .with_ref_getter(QodotCompiler::get_format)
+        .with_setter(QodotCompiler::set_format)
+        .done();
+
+    builder
+        .add_property::<bool>("quit_when_done")
+        .with_default(true)
+        .with_getter(QodotCompiler::get_quit_when_done)
+        .with_setter(QodotCompiler::set_quit_when_done)
+        .done();
+}
+
+#[methods]
+impl QodotCompiler {
+    fn _init(_owner: Node) -> Self {
+        QodotCompiler {
+            template: Variant::new(),
+            input_directory: "res://maps".into(),
+            output_directory: "res://maps/compiled".into(),
+            format: "tscn".into(),
+            quit_when_done: true,
+            queue: Vec::new(),
+            current_map: Variant::new(),
+            results: Vec::new(),
+        }
+    }
+
+    // Getters
+    fn get_template(&self, _owner: Node) -> Option<Resource> {
+        self.template.try_to_object()
+    }
+
+    fn get_input_directory(&self, _owner: Node) -> &GodotString {
+        &self.input_directory
+    }
+
+    fn get_output_directory(&self, _owner: Node) -> &GodotString {
+        &self.output_directory
+    }
+
+    fn get_format(&self, _owner: Node) -> &GodotString {
+        &self.format
+    }
+
+    fn get_quit_when_done(&self, _owner: Node) -> bool {
+        self.quit_when_done
+    }
+
+    // Setters
+    fn set_template(&mut self, _owner: Node, new_template: Option<Resource>) {
+        self.template = match new_template {
+            Some(new_template) => Variant::from_object(&new_template),
+            None => Variant::new(),
+        }
+    }
+
+    fn set_input_directory(&mut self, _owner: Node, new_input_directory: GodotString) {
+        self.input_directory = new_input_directory
+    }
+
+    fn set_output_directory(&mut self, _owner: Node, new_output_directory: GodotString) {
+        self.output_directory = new_output_directory
+    }
+
+    fn set_format(&mut self, _owner: Node, new_format: GodotString) {
+        self.format = new_format
+    }
+
+    fn set_quit_when_done(&mut self, _owner: Node, new_quit_when_done: bool) {
+        self.quit_when_done = new_quit_when_done
+    }
+
+    // Queues every map under the input directory and starts compiling them in turn
+    #[export]
+    fn compile_all(&mut self, mut owner: Node) -> bool {
+        if !self.queue.is_empty() || !self.current_map.is_nil() {
+            godot_error!("A compile is already in progress");
+            return false;
+        }
+
+        let input_directory = trim_directory(&self.input_directory);
+        let output_directory = trim_directory(&self.output_directory);
+        let format = self.format.to_string();
+
+        let project_settings = gdnative::ProjectSettings::godot_singleton();
+        let input_root = PathBuf::from(
+            project_settings
+                .globalize_path(input_directory.as_str().into())
+                .to_string(),
+        );
+
+        let mut map_files: Vec<PathBuf> = Vec::new();
+        if let Err(err) = find_map_files(&input_root, &mut map_files) {
+            godot_error!("Failed to list {}: {}", input_directory, err);
+            return false;
+        }
+        map_files.sort();
+
+        // Outputs mirror the input directory structure
+        self.results.clear();
+        self.queue = map_files
+            .iter()
+            .filter_map(|map_file| map_file.strip_prefix(&input_root).ok())
+            .map(|relative| {
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                let output = Path::new(&relative).with_extension(&format);
+                (
+                    format!("{}/{}", input_directory, relative).into(),
+                    format!("{}/{}", output_directory, output.to_string_lossy()).into(),
+                )
+            })
+            .collect();
+        self.queue.reverse();
+
+        godot_print!(
+            "Compiling {} maps from {}",
+            self.queue.len(),
+            input_directory
+        );
+        unsafe {
+            owner.call_deferred("compile_next".into(), &[]);
+        }
+        true
+    }
+
+    #[export]
+    fn compile_next(&mut self, mut owner: Node) {
+        let (map_file, output_path) = match self.queue.pop() {
+            Some(next) => next,
+            None => {
+                self.finish(owner);
+                return;
+            }
+        };
+
+        godot_print!("Compiling {}", map_file.to_string());
+        if let Err(err) = self.start_compile(owner, &map_file, &output_path) {
+            godot_error!("{}", err);
+            self.results.push(CompileResult {
+                map: map_file.to_string(),
+                output: output_path.to_string(),
+                success: false,
+                errors: vec![err],
+                warnings: Vec::new(),
+            });
+            self.free_current_map(owner);
+            unsafe {
+                owner.call_deferred("compile_next".into(), &[]);
+            }
+        }
+    }
+
+    #[export]
+    fn map_compiled(&mut self, mut owner: Node, report: Dictionary) {
+        let strings = |key: &str| {
+            let array = report.get(&Variant::from_str(key)).to_string_array();
+            (0..array.len())
+                .map(|i| array.get(i).to_string())
+                .collect::<Vec<String>>()
+        };
+
+        self.results.push(CompileResult {
+            map: report.get(&Variant::from_str("map")).to_string(),
+            output: report.get(&Variant::from_str("output")).to_string(),
+            success: report.get(&Variant::from_str("success")).to_bool(),
+            errors: strings("errors"),
+            warnings: strings("warnings"),
+        });
+
+        self.free_current_map(owner);
+        unsafe {
+            owner.call_deferred("compile_next".into(), &[]);
+        }
+    }
+
+    fn start_compile(
+        &mut self,
+        mut owner: Node,
+        map_file: &GodotString,
+        output_path: &GodotString,
+    ) -> Result<(), String> {
+        let template = self
+            .template
+            .try_to_object::<PackedScene>()
+            .ok_or_else(|| "No template scene".to_string())?;
+
+        let mut map = unsafe { template.instance(0) }
+            .ok_or_else(|| "Failed to instance template scene".to_string())?;
+
+        self.current_map = Variant::from_object(&map);
+
+        if !unsafe { map.has_method("compile".into()) } {
+            return Err("Template scene root is not a QodotMap".into());
+        }
+
+        // Output directories are created up front, as Godot won't save into missing ones
+        let global_output_path = gdnative::ProjectSettings::godot_singleton()
+            .globalize_path(output_path.clone())
+            .to_string();
+        if let Some(parent) = Path::new(&global_output_path).parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|err| format!("Failed to create {:?}: {}", parent, err))?;
+        }
+
+        unsafe {
+            map.set("map_type".into(), Variant::from_i64(1));
+            map.set("map_file".into(), Variant::from_godot_string(map_file));
+            owner.add_child(Some(map), true);
+
+            map.connect(
+                "compiled".into(),
+                owner.cast::<Object>(),
+                "map_compiled".into(),
+                VariantArray::new(),
+                0,
+            )
+            .map_err(|err| format!("Failed to connect compiled signal: {:?}", err))?;
+
+            let started = map.call("compile".into(), &[Variant::from_godot_string(output_path)]);
+            if !started.to_bool() {
+                return Err("Failed to start compile".into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn free_current_map(&mut self, _owner: Node) {
+        if let Some(mut map) = self.current_map.try_to_object::<Node>() {
+            unsafe {
+                map.queue_free();
+            }
+        }
+        self.current_map = Variant::new();
+    }
+
+    fn finish(&mut self, owner: Node) {
+        let failed = self.results.iter().filter(|result| !result.success).count();
+        let warnings: usize = self
+            .results
+            .iter()
+            .map(|result| result.warnings.len())
+            .sum();
+
+        for result in self.results.iter().filter(|result| !result.success) {
+            for error in &result.errors {
+                godot_error!("{}: {}", result.map, error);
+            }
+        }
+
+        godot_print!(
+            "Compiled {} maps: {} failed, {} warnings",
+            self.results.len(),
+            failed,
+            warnings
+        );
+
+        let report_path = format!(
+            "{}/compile_report.json",
+            trim_directory(&self.output_directory)
+        );
+        let report_path = gdnative::ProjectSettings::godot_singleton()
+            .globalize_path(report_path.into())
+            .to_string();
+        if let Err(err) = std::fs::write(&report_path, self.report_json()) {
+            godot_warn!("Failed to write {}: {}", report_path, err);
+        }
+
+        if self.quit_when_done {
+            if let Some(mut tree) = unsafe { owner.get_tree() } {
+                unsafe {
+                    tree.quit(if failed > 0 { 1 } else { 0 });
+                }
+            }
+        }
+    }
+
+    fn report_json(&self) -> String {
+        let to_array = |strings: &[String]| {
+            JsonValue::Array(strings.iter().cloned().map(JsonValue::String).collect())
+        };
+
+        let maps = self
+            .results
+            .iter()
+            .map(|result| {
+                JsonValue::object()
+                    .with("map", JsonValue::String(result.map.clone()))
+                    .with("output", JsonValue::String(result.output.clone()))
+                    .with("success", JsonValue::Bool(result.success))
+                    .with("errors", to_array(&result.errors))
+                    .with("warnings", to_array(&result.warnings))
+            })
+            .collect();
+
+        let report = JsonValue::object()
+            .with(
+                "success",
+                JsonValue::Bool(self.results.iter().all(|result| result.success)),
+            )
+            .with("maps", JsonValue::Array(maps));
+
+        let mut out = String::new();
+        report.write_pretty(&mut out, 0);
+        out.push('\n');
+        out
+    }
+}
+
+fn trim_directory(directory: &GodotString) -> String {
+    directory.to_string().trim_end_matches('/').to_string()
+}
+
+fn find_map_files(directory: &Path, map_files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_map_files(&path, map_files)?;
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("map"))
+        {
+            map_files.push(path);
+        }
+    }
+    Ok(())
+}
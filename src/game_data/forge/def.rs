@@ -0,0 +1,173 @@
+use std::fmt::Write;
+
+use super::fgd::FgdEntity;
+use super::Metadata;
+use quarchitect::game_data::forge::{ChoiceData, Property as QuarchitectProperty, PropertyData};
+
+#[cfg(test)]
+mod tests;
+
+// Quake's default point entity bounds, used when a class declares no size
+const DEFAULT_MINS: &str = "-8 -8 -8";
+const DEFAULT_MAXS: &str = "8 8 8";
+
+// Writes Doom 3 style entityDef declarations, as read by DarkRadiant.
+// Entities are expected to have been flattened beforehand.
+pub fn write(entities: &[FgdEntity]) -> String {
+    let mut out = String::new();
+
+    for entity in entities {
+        let class_type: i64 = entity.definition.class_type.into();
+        if class_type == 0 {
+            continue;
+        }
+
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        write_entity(&mut out, entity, class_type == 2);
+    }
+
+    out
+}
+
+fn write_entity(out: &mut String, entity: &FgdEntity, solid: bool) {
+    let definition = &entity.definition;
+
+    writeln!(out, "entityDef {}\n{{", definition.class_name).unwrap();
+
+    let mut size: Option<(String, String)> = None;
+    for metadata in &entity.metadata {
+        match metadata {
+            Metadata::Color(color) => key_value(
+                out,
+                "editor_color",
+                &format!("{} {} {}", color.r, color.g, color.b),
+            ),
+            Metadata::Size(min, max) => {
+                size = Some((
+                    format!("{} {} {}", min.x(), min.y(), min.z()),
+                    format!("{} {} {}", max.x(), max.y(), max.z()),
+                ))
+            }
+            // Expressions are TrenchBroom-specific, so only plain paths carry over
+            Metadata::Model(model) | Metadata::Studio(model)
+                if !model.is_empty() && !model.starts_with('{') =>
+            {
+                key_value(out, "model", model)
+            }
+            _ => (),
+        }
+    }
+
+    // Brush entities take their bounds from their brushes, signalled by "?"
+    match (solid, size) {
+        (true, _) => {
+            key_value(out, "editor_mins", "?");
+            key_value(out, "editor_maxs", "?");
+        }
+        (false, Some((mins, maxs))) => {
+            key_value(out, "editor_mins", &mins);
+            key_value(out, "editor_maxs", &maxs);
+        }
+        (false, None) => {
+            key_value(out, "editor_mins", DEFAULT_MINS);
+            key_value(out, "editor_maxs", DEFAULT_MAXS);
+        }
+    }
+
+    if !definition.description.is_empty() {
+        key_value(out, "editor_usage", &definition.description);
+    }
+
+    for property in &definition.properties {
+        write_property(out, property);
+    }
+
+    out.push_str("}\n");
+}
+
+fn write_property(out: &mut String, property: &QuarchitectProperty) {
+    let (editor_type, default, values) = match &property.data {
+        PropertyData::Integer(value) => ("int", Some(value.to_string()), None),
+        PropertyData::Float(value) => ("float", Some(value.to_string()), None),
+        PropertyData::Vector3(value) => (
+            "vector",
+            Some(format!("{} {} {}", value.x(), value.y(), value.z())),
+            None,
+        ),
+        PropertyData::String(value) => ("var", Some(value.clone()), None),
+        PropertyData::Color(value) => (
+            "color",
+            Some(format!("{} {} {}", value.r, value.g, value.b)),
+            None,
+        ),
+        // The format has no enumerations, so the options are listed in the description
+        PropertyData::Choices(choices, default) => (
+            "var",
+            choices
+                .get(*default as usize)
+                .map(|choice| choice_value(&choice.value)),
+            Some(
+                choices
+                    .iter()
+                    .map(|choice| format!("{} = {}", choice_value(&choice.value), choice.name))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+        ),
+        PropertyData::Flags(flags, default) => (
+            "int",
+            Some(default.to_string()),
+            Some(
+                flags
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, flag)| !flag.is_empty())
+                    .map(|(bit, flag)| format!("{} = {}", 1 << bit, flag))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+        ),
+        PropertyData::TargetSource => ("var", None, None),
+        PropertyData::TargetDestination => ("var", None, None),
+    };
+
+    let description = [
+        property.short_description.as_str(),
+        property.long_description.as_str(),
+        values.as_ref().map(String::as_str).unwrap_or_default(),
+    ]
+    .iter()
+    .filter(|part| !part.is_empty())
+    .cloned()
+    .collect::<Vec<&str>>()
+    .join(". ");
+
+    key_value(
+        out,
+        &format!("editor_{} {}", editor_type, property.name),
+        &description,
+    );
+
+    if let Some(default) = default {
+        key_value(out, &property.name, &default);
+    }
+}
+
+fn key_value(out: &mut String, key: &str, value: &str) {
+    writeln!(out, "\t{}\t{}", quote(key), quote(value)).unwrap();
+}
+
+fn choice_value(value: &ChoiceData) -> String {
+    match value {
+        ChoiceData::Integer(value) => value.to_string(),
+        ChoiceData::Float(value) => value.to_string(),
+        ChoiceData::String(value) => value.clone(),
+    }
+}
+
+// Decl strings have no escape sequences, so embedded quotes are replaced
+fn quote(string: &str) -> String {
+    format!("\"{}\"", string.replace('"', "'"))
+}
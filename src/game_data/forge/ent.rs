@@ -0,0 +1,189 @@
+use std::fmt::Write;
+
+use super::fgd::FgdEntity;
+use super::Metadata;
+use quarchitect::game_data::forge::{ChoiceData, Property as QuarchitectProperty, PropertyData};
+
+#[cfg(test)]
+mod tests;
+
+// Quake's default point entity bounds, used when a class declares no size
+const DEFAULT_BOX: &str = "-8 -8 -8 8 8 8";
+
+// Writes NetRadiant's XML .ent format. The format has no inheritance,
+// so entities are expected to have been flattened beforehand.
+pub fn write(entities: &[FgdEntity]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\"?>\n<classes>\n");
+
+    // Choice lists are declared up front and referenced by name as property types
+    for entity in entities.iter().filter(|entity| is_placeable(entity)) {
+        for property in &entity.definition.properties {
+            if let PropertyData::Choices(choices, _) = &property.data {
+                writeln!(
+                    out,
+                    "<list name=\"{}\">",
+                    list_name(&entity.definition.class_name, &property.name)
+                )
+                .unwrap();
+                for choice in choices {
+                    writeln!(
+                        out,
+                        "\t<item name=\"{}\" value=\"{}\"/>",
+                        escape(&choice.name),
+                        escape(&choice_value(&choice.value))
+                    )
+                    .unwrap();
+                }
+                out.push_str("</list>\n");
+            }
+        }
+    }
+
+    for entity in entities.iter().filter(|entity| is_placeable(entity)) {
+        write_entity(&mut out, entity);
+    }
+
+    out.push_str("</classes>\n");
+    out
+}
+
+fn is_placeable(entity: &FgdEntity) -> bool {
+    let class_type: i64 = entity.definition.class_type.into();
+    class_type != 0
+}
+
+fn write_entity(out: &mut String, entity: &FgdEntity) {
+    let definition = &entity.definition;
+    let class_type: i64 = definition.class_type.into();
+    let element = if class_type == 2 { "group" } else { "point" };
+
+    write!(
+        out,
+        "<{} name=\"{}\"",
+        element,
+        escape(&definition.class_name)
+    )
+    .unwrap();
+
+    let mut has_box = false;
+    for metadata in &entity.metadata {
+        match metadata {
+            Metadata::Color(color) => {
+                write!(out, " color=\"{} {} {}\"", color.r, color.g, color.b).unwrap()
+            }
+            Metadata::Size(min, max) if element == "point" => {
+                write!(
+                    out,
+                    " box=\"{} {} {} {} {} {}\"",
+                    min.x(),
+                    min.y(),
+                    min.z(),
+                    max.x(),
+                    max.y(),
+                    max.z()
+                )
+                .unwrap();
+                has_box = true;
+            }
+            // Expressions are TrenchBroom-specific, so only plain paths carry over
+            Metadata::Model(model) | Metadata::Studio(model)
+                if !model.is_empty() && !model.starts_with('{') =>
+            {
+                write!(out, " model=\"{}\"", escape(model)).unwrap()
+            }
+            _ => (),
+        }
+    }
+
+    if element == "point" && !has_box {
+        write!(out, " box=\"{}\"", DEFAULT_BOX).unwrap();
+    }
+    out.push_str(">\n");
+
+    if !definition.description.is_empty() {
+        writeln!(out, "{}", escape(&definition.description)).unwrap();
+    }
+
+    for property in &definition.properties {
+        write_property(out, &definition.class_name, property);
+    }
+
+    writeln!(out, "</{}>", element).unwrap();
+}
+
+fn write_property(out: &mut String, class_name: &str, property: &QuarchitectProperty) {
+    let (element, value) = match &property.data {
+        PropertyData::Integer(value) => ("integer".to_string(), Some(value.to_string())),
+        PropertyData::Float(value) => ("real".to_string(), Some(value.to_string())),
+        PropertyData::Vector3(value) => (
+            "vector3".to_string(),
+            Some(format!("{} {} {}", value.x(), value.y(), value.z())),
+        ),
+        PropertyData::String(value) => ("string".to_string(), Some(value.clone())),
+        PropertyData::Color(value) => (
+            "color".to_string(),
+            Some(format!("{} {} {}", value.r, value.g, value.b)),
+        ),
+        PropertyData::Choices(choices, default) => (
+            list_name(class_name, &property.name),
+            choices
+                .get(*default as usize)
+                .map(|choice| choice_value(&choice.value)),
+        ),
+        PropertyData::Flags(flags, _) => {
+            for (bit, flag) in flags.iter().enumerate() {
+                if flag.is_empty() {
+                    continue;
+                }
+
+                writeln!(
+                    out,
+                    "\t<flag key=\"{}\" name=\"{}\" bit=\"{}\"/>",
+                    escape(&flag.to_uppercase().replace(' ', "_")),
+                    escape(flag),
+                    bit
+                )
+                .unwrap();
+            }
+            return;
+        }
+        PropertyData::TargetSource => ("targetname".to_string(), None),
+        PropertyData::TargetDestination => ("target".to_string(), None),
+    };
+
+    write!(
+        out,
+        "\t<{} key=\"{}\" name=\"{}\"",
+        element,
+        escape(&property.name),
+        escape(&property.short_description)
+    )
+    .unwrap();
+
+    if let Some(value) = value {
+        write!(out, " value=\"{}\"", escape(&value)).unwrap();
+    }
+
+    writeln!(out, ">{}</{}>", escape(&property.long_description), element).unwrap();
+}
+
+fn list_name(class_name: &str, property_name: &str) -> String {
+    format!("{}_{}", class_name, property_name)
+}
+
+fn choice_value(value: &ChoiceData) -> String {
+    match value {
+        ChoiceData::Integer(value) => value.to_string(),
+        ChoiceData::Float(value) => value.to_string(),
+        ChoiceData::String(value) => value.clone(),
+    }
+}
+
+fn escape(string: &str) -> String {
+    string
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,171 @@
+use super::write;
+use crate::game_data::forge::fgd::FgdEntity;
+use crate::game_data::forge::Metadata;
+use quarchitect::game_data::forge::{
+    Choice as QuarchitectChoice, ChoiceData, Entity as QuarchitectForgeEntity,
+    Property as QuarchitectProperty, PropertyData,
+};
+
+const ALL_TYPES: &str = include_str!("golden/all_types.ent");
+
+fn entity(
+    class_type: i64,
+    class_name: &str,
+    description: &str,
+    properties: Vec<QuarchitectProperty>,
+    metadata: Vec<Metadata>,
+) -> FgdEntity {
+    let mut entity = QuarchitectForgeEntity::default();
+    entity.class_type = class_type.into();
+    entity.class_name = class_name.into();
+    entity.description = description.into();
+    entity.properties = properties;
+    FgdEntity::new(entity, metadata)
+}
+
+fn property(name: &str, short_description: &str, data: PropertyData) -> QuarchitectProperty {
+    let mut property = QuarchitectProperty::default();
+    property.name = name.into();
+    property.short_description = short_description.into();
+    property.data = data;
+    property
+}
+
+fn choice(name: &str, value: ChoiceData) -> QuarchitectChoice {
+    let mut choice = QuarchitectChoice::default();
+    choice.name = name.into();
+    choice.value = value;
+    choice
+}
+
+// Already flattened, as the writer expects
+fn all_types() -> Vec<FgdEntity> {
+    vec![
+        entity(
+            0,
+            "Targetname",
+            "Targetable",
+            vec![property("targetname", "Name", PropertyData::TargetSource)],
+            Vec::new(),
+        ),
+        entity(
+            1,
+            "item_armor",
+            "Armor <pickup>",
+            vec![
+                property("targetname", "Name", PropertyData::TargetSource),
+                property("target", "Target", PropertyData::TargetDestination),
+                QuarchitectProperty {
+                    long_description: "Armor points given".into(),
+                    ..property("amount", "Amount", PropertyData::Integer(100))
+                },
+                property("speed", "Speed", PropertyData::Float(1.5)),
+                property(
+                    "offset",
+                    "Offset",
+                    PropertyData::Vector3(quarchitect::Vector3::new(0.0, 0.0, 8.0)),
+                ),
+                property(
+                    "message",
+                    "Message",
+                    PropertyData::String("\"Fish\" & chips".into()),
+                ),
+                property(
+                    "tint",
+                    "Tint",
+                    PropertyData::Color(quarchitect::Color::new(1.0, 0.5, 0.0)),
+                ),
+                property(
+                    "style",
+                    "Style",
+                    PropertyData::Choices(
+                        vec![
+                            choice("Normal", ChoiceData::Integer(0)),
+                            choice("Fancy", ChoiceData::Integer(1)),
+                        ],
+                        1,
+                    ),
+                ),
+                property(
+                    "spawnflags",
+                    "",
+                    PropertyData::Flags(
+                        vec![
+                            "Suspended".into(),
+                            String::new(),
+                            "Not in Deathmatch".into(),
+                        ],
+                        4,
+                    ),
+                ),
+            ],
+            vec![
+                Metadata::Color(quarchitect::Color::new(1.0, 0.0, 1.0)),
+                Metadata::Size(
+                    quarchitect::Vector3::new(-16.0, -16.0, 0.0),
+                    quarchitect::Vector3::new(16.0, 16.0, 32.0),
+                ),
+                Metadata::Model("progs/armor.mdl".into()),
+                Metadata::IconSprite("sprites/armor.png".into()),
+            ],
+        ),
+        entity(
+            1,
+            "monster_test",
+            "",
+            vec![property(
+                "mode",
+                "Mode",
+                PropertyData::Choices(
+                    vec![
+                        choice("Walk", ChoiceData::String("walk".into())),
+                        choice("Run", ChoiceData::String("run".into())),
+                    ],
+                    0,
+                ),
+            )],
+            vec![
+                Metadata::Model(r#"{{ spawnflags & 1 -> "progs/a.mdl", "progs/b.mdl" }}"#.into()),
+                Metadata::Studio(String::new()),
+            ],
+        ),
+        entity(
+            2,
+            "func_wall",
+            "Wall",
+            vec![property(
+                "rendercolor",
+                "Render color",
+                PropertyData::Color(quarchitect::Color::new(1.0, 0.25, 0.0)),
+            )],
+            vec![
+                Metadata::Color(quarchitect::Color::new(0.0, 0.5, 0.8)),
+                Metadata::Size(
+                    quarchitect::Vector3::new(-8.0, -8.0, -8.0),
+                    quarchitect::Vector3::new(8.0, 8.0, 8.0),
+                ),
+            ],
+        ),
+    ]
+}
+
+#[test]
+fn writes_golden_ent() {
+    assert_eq!(write(&all_types()), ALL_TYPES);
+}
+
+#[test]
+fn choice_lists_are_declared_before_classes() {
+    let ent = write(&all_types());
+
+    let list = ent.find("<list name=\"monster_test_mode\">").unwrap();
+    let class = ent.find("<point name=\"item_armor\"").unwrap();
+    assert!(list < class);
+}
+
+#[test]
+fn base_classes_are_left_out() {
+    let ent = write(&all_types()[..1]);
+
+    assert_eq!(ent, "<?xml version=\"1.0\"?>\n<classes>\n</classes>\n");
+}
@@ -0,0 +1,59 @@
+mod parse;
+mod write;
+
+#[cfg(test)]
+mod tests;
+
+use super::Metadata;
+use quarchitect::game_data::forge::Entity as QuarchitectForgeEntity;
+
+pub use parse::parse;
+pub use write::write;
+
+// Contents of a parsed FGD file, prior to conversion into ForgeGameData resources
+#[derive(Debug, Default)]
+pub struct FgdFile {
+    pub includes: Vec<String>,
+    pub entities: Vec<FgdEntity>,
+    pub warnings: Vec<String>,
+}
+
+// A class definition alongside the full list of helpers written for it, including
+// those quarchitect has no representation for
+#[derive(Debug, Clone)]
+pub struct FgdEntity {
+    pub definition: QuarchitectForgeEntity,
+    pub metadata: Vec<Metadata>,
+    // Colour properties declared as color255, which quarchitect stores like any other colour
+    pub color255: Vec<String>,
+}
+
+impl FgdEntity {
+    pub fn new(definition: QuarchitectForgeEntity, metadata: Vec<Metadata>) -> Self {
+        FgdEntity {
+            definition: QuarchitectForgeEntity {
+                metadata: metadata.iter().flat_map(Metadata::to_quarchitect).collect(),
+                ..definition
+            },
+            metadata,
+            color255: Vec::new(),
+        }
+    }
+}
+
+impl From<QuarchitectForgeEntity> for FgdEntity {
+    fn from(definition: QuarchitectForgeEntity) -> Self {
+        let metadata = definition.metadata.iter().cloned().map(Metadata::from).collect();
+        FgdEntity::new(definition, metadata)
+    }
+}
+
+pub fn parse_file(path: &str) -> Result<FgdFile, String> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => return Err(format!("Failed to read {}: {}", path, err)),
+    };
+
+    // Stock FGDs frequently contain Latin-1 descriptions
+    parse(&String::from_utf8_lossy(&bytes))
+}
@@ -0,0 +1,647 @@
+use super::{FgdEntity, FgdFile, Metadata};
+use quarchitect::game_data::forge::{
+    Choice as QuarchitectChoice, ChoiceData, Entity as QuarchitectForgeEntity,
+    Property as QuarchitectProperty, PropertyData,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Directive(String),
+    Word(String),
+    Quoted(String),
+    // A brace-delimited TrenchBroom model expression, kept verbatim
+    Expression(String),
+    Symbol(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Directive(directive) => write!(f, "'{}'", directive),
+            Token::Word(word) => write!(f, "'{}'", word),
+            Token::Quoted(quoted) => write!(f, "\"{}\"", quoted),
+            Token::Expression(expression) => write!(f, "'{}'", expression),
+            Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+        }
+    }
+}
+
+const SYMBOLS: &[char] = &['(', ')', '[', ']', '=', ':', ',', '+'];
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens: Vec<(Token, usize)> = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => (),
+            '/' if chars.peek() == Some(&'/') => {
+                while let Some(c) = chars.peek() {
+                    if *c == '\n' {
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+            '"' => {
+                let start_line = line;
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            quoted.push(c);
+                        }
+                        None => return Err(format!("Line {}: Unterminated string", start_line)),
+                    }
+                }
+                tokens.push((Token::Quoted(quoted), start_line));
+            }
+            '{' => {
+                let start_line = line;
+                let mut expression = c.to_string();
+                let mut depth = 1;
+                let mut in_quotes = false;
+                while depth > 0 {
+                    let c = match chars.next() {
+                        Some(c) => c,
+                        None => return Err(format!("Line {}: Unterminated expression", start_line)),
+                    };
+
+                    match c {
+                        '\n' => line += 1,
+                        '"' => in_quotes = !in_quotes,
+                        '{' if !in_quotes => depth += 1,
+                        '}' if !in_quotes => depth -= 1,
+                        _ => (),
+                    }
+                    expression.push(c);
+                }
+                tokens.push((Token::Expression(expression), start_line));
+            }
+            c if SYMBOLS.contains(&c) => tokens.push((Token::Symbol(c), line)),
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.peek() {
+                    if c.is_whitespace() || SYMBOLS.contains(c) || *c == '"' {
+                        break;
+                    }
+                    word.push(*c);
+                    chars.next();
+                }
+
+                if word.starts_with('@') {
+                    tokens.push((Token::Directive(word), line));
+                } else {
+                    tokens.push((Token::Word(word), line));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    warnings: Vec<String>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens
+            .get(self.position + offset)
+            .map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        match self.tokens.get(self.position) {
+            Some((_, line)) => *line,
+            None => self.tokens.last().map(|(_, line)| *line).unwrap_or(1),
+        }
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        match self.tokens.get(self.position) {
+            Some((token, _)) => {
+                self.position += 1;
+                Ok(token.clone())
+            }
+            None => Err(format!("Line {}: Unexpected end of file", self.line())),
+        }
+    }
+
+    fn is_symbol(&self, symbol: char) -> bool {
+        self.peek() == Some(&Token::Symbol(symbol))
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Symbol(c) if c == symbol => Ok(()),
+            token => Err(format!(
+                "Line {}: Expected '{}', found {}",
+                line, symbol, token
+            )),
+        }
+    }
+
+    fn expect_word(&mut self) -> Result<String, String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Word(word) => Ok(word),
+            token => Err(format!("Line {}: Expected a name, found {}", line, token)),
+        }
+    }
+
+    fn expect_quoted(&mut self) -> Result<String, String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Quoted(quoted) => Ok(quoted),
+            token => Err(format!("Line {}: Expected a string, found {}", line, token)),
+        }
+    }
+
+    // Reads a string, joining "a" + "b" continuations
+    fn expect_string(&mut self) -> Result<String, String> {
+        let mut string = self.expect_quoted()?;
+        while self.is_symbol('+') {
+            self.next()?;
+            string += &self.expect_quoted()?;
+        }
+        Ok(string)
+    }
+
+    // Reads an optional bare or quoted value
+    fn value(&mut self) -> Result<Option<String>, String> {
+        match self.peek() {
+            Some(Token::Quoted(_)) => Ok(Some(self.expect_string()?)),
+            // A word followed by '(' starts the next property rather than being a default
+            Some(Token::Word(_)) if self.peek_at(1) != Some(&Token::Symbol('(')) => {
+                Ok(Some(self.expect_word()?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn warn(&mut self, line: usize, warning: String) {
+        self.warnings.push(format!("Line {}: {}", line, warning));
+    }
+
+    fn parse_file(&mut self) -> Result<FgdFile, String> {
+        let mut fgd = FgdFile::default();
+
+        while self.peek().is_some() {
+            let line = self.line();
+            let directive = match self.next()? {
+                Token::Directive(directive) => directive,
+                token => {
+                    return Err(format!(
+                        "Line {}: Expected a class directive, found {}",
+                        line, token
+                    ))
+                }
+            };
+
+            match directive.to_lowercase().as_str() {
+                "@include" => fgd.includes.push(self.expect_string()?),
+                "@baseclass" => fgd.entities.push(self.parse_class(0)?),
+                "@pointclass" => fgd.entities.push(self.parse_class(1)?),
+                "@solidclass" => fgd.entities.push(self.parse_class(2)?),
+                "@npcclass" | "@keyframeclass" | "@moveclass" | "@filterclass" => {
+                    self.warn(line, format!("{} imported as a point class", directive));
+                    fgd.entities.push(self.parse_class(1)?)
+                }
+                "@mapsize" => {
+                    self.warn(line, "@mapsize ignored".into());
+                    self.parse_arguments()?;
+                }
+                _ => return Err(format!("Line {}: Unknown directive '{}'", line, directive)),
+            }
+        }
+
+        fgd.warnings = self.warnings.clone();
+        Ok(fgd)
+    }
+
+    // Reads a parenthesized, comma-separated argument list
+    fn parse_arguments(&mut self) -> Result<Vec<Vec<String>>, String> {
+        self.expect_symbol('(')?;
+
+        let mut arguments: Vec<Vec<String>> = vec![Vec::new()];
+        loop {
+            let line = self.line();
+            match self.next()? {
+                Token::Symbol(')') => break,
+                Token::Symbol(',') => arguments.push(Vec::new()),
+                Token::Word(word) | Token::Quoted(word) | Token::Expression(word) => {
+                    arguments.last_mut().unwrap().push(word)
+                }
+                token => {
+                    return Err(format!(
+                        "Line {}: Unexpected {} in argument list",
+                        line, token
+                    ))
+                }
+            }
+        }
+
+        arguments.retain(|argument| !argument.is_empty());
+        Ok(arguments)
+    }
+
+    fn parse_class(&mut self, class_type: i64) -> Result<FgdEntity, String> {
+        let mut metadata: Vec<Metadata> = Vec::new();
+
+        while !self.is_symbol('=') {
+            let line = self.line();
+            let helper = self.expect_word()?;
+            let arguments = self.parse_arguments()?;
+
+            match helper.to_lowercase().as_str() {
+                "base" => metadata.push(Metadata::Base(
+                    arguments
+                        .into_iter()
+                        .flat_map(|argument| argument.into_iter())
+                        .collect(),
+                )),
+                "color" => {
+                    let color = parse_numbers(&arguments.concat(), line)?;
+                    if color.len() != 3 {
+                        return Err(format!("Line {}: color() expects three components", line));
+                    }
+                    metadata.push(Metadata::Color(quarchitect::Color::new(
+                        color[0] / 255.0,
+                        color[1] / 255.0,
+                        color[2] / 255.0,
+                    )));
+                }
+                "size" => {
+                    let vectors = arguments
+                        .iter()
+                        .map(|argument| parse_vector3(argument, line))
+                        .collect::<Result<Vec<quarchitect::Vector3>, String>>()?;
+
+                    match vectors.as_slice() {
+                        [min, max] => metadata.push(Metadata::Size(*min, *max)),
+                        [extents] => {
+                            metadata.push(Metadata::Size(*extents * -0.5, *extents * 0.5))
+                        }
+                        _ => {
+                            return Err(format!("Line {}: size() expects one or two vectors", line))
+                        }
+                    }
+                }
+                "model" => metadata.push(Metadata::Model(single_argument(arguments))),
+                "studio" => metadata.push(Metadata::Studio(single_argument(arguments))),
+                "iconsprite" => metadata.push(Metadata::IconSprite(single_argument(arguments))),
+                "decal" => metadata.push(Metadata::Decal),
+                _ => self.warn(line, format!("Unsupported helper '{}' ignored", helper)),
+            }
+        }
+
+        self.expect_symbol('=')?;
+
+        let mut entity = QuarchitectForgeEntity::default();
+        entity.class_type = class_type.into();
+        entity.class_name = self.expect_word()?;
+
+        if self.is_symbol(':') {
+            self.next()?;
+            entity.description = self.expect_string()?;
+        }
+
+        let mut color255: Vec<String> = Vec::new();
+
+        self.expect_symbol('[')?;
+        while !self.is_symbol(']') {
+            if let Some(property) = self.parse_property(&entity.class_name, &mut color255)? {
+                entity.properties.push(property);
+            }
+        }
+        self.expect_symbol(']')?;
+
+        Ok(FgdEntity {
+            color255,
+            ..FgdEntity::new(entity, metadata)
+        })
+    }
+
+    fn parse_property(
+        &mut self,
+        class_name: &str,
+        color255: &mut Vec<String>,
+    ) -> Result<Option<QuarchitectProperty>, String> {
+        let line = self.line();
+        let name = self.expect_word()?;
+
+        // Skip Source-style I/O declarations, which have no Quake equivalent
+        let lower_name = name.to_lowercase();
+        if (lower_name == "input" || lower_name == "output") && !self.is_symbol('(') {
+            self.expect_word()?;
+            self.parse_arguments()?;
+            if self.is_symbol(':') {
+                self.next()?;
+                self.expect_string()?;
+            }
+            self.warn(line, format!("{} on '{}' ignored", lower_name, class_name));
+            return Ok(None);
+        }
+
+        self.expect_symbol('(')?;
+        let property_type = self.expect_word()?.to_lowercase();
+        self.expect_symbol(')')?;
+
+        while let Some(Token::Word(modifier)) = self.peek() {
+            match modifier.to_lowercase().as_str() {
+                "readonly" | "report" => {
+                    self.next()?;
+                }
+                _ => break,
+            }
+        }
+
+        let mut property = QuarchitectProperty::default();
+        property.name = name;
+
+        let mut default: Option<String> = None;
+        if self.is_symbol(':') {
+            self.next()?;
+            if let Some(Token::Quoted(_)) = self.peek() {
+                property.short_description = self.expect_string()?;
+            }
+
+            if self.is_symbol(':') {
+                self.next()?;
+                default = self.value()?;
+
+                if self.is_symbol(':') {
+                    self.next()?;
+                    if let Some(Token::Quoted(_)) = self.peek() {
+                        property.long_description = self.expect_string()?;
+                    }
+                }
+            }
+        }
+
+        property.data = match property_type.as_str() {
+            "integer" => PropertyData::Integer(match &default {
+                Some(default) => parse_default(default, line, &mut self.warnings),
+                None => 0,
+            }),
+            "float" => PropertyData::Float(match &default {
+                Some(default) => parse_default(default, line, &mut self.warnings),
+                None => 0.0,
+            }),
+            "color255" | "color1" => {
+                let scale = if property_type == "color255" {
+                    color255.push(property.name.clone());
+                    255.0
+                } else {
+                    1.0
+                };
+
+                let color = match &default {
+                    Some(default) => {
+                        parse_numbers(&split_words(default), line).unwrap_or_else(|_| Vec::new())
+                    }
+                    None => Vec::new(),
+                };
+
+                PropertyData::Color(if color.len() >= 3 {
+                    quarchitect::Color::new(color[0] / scale, color[1] / scale, color[2] / scale)
+                } else {
+                    quarchitect::Color::new(1.0, 1.0, 1.0)
+                })
+            }
+            "vector" | "origin" => PropertyData::Vector3(match &default {
+                Some(default) => parse_vector3(&split_words(default), line)
+                    .unwrap_or_else(|_| quarchitect::Vector3::default()),
+                None => quarchitect::Vector3::default(),
+            }),
+            "target_source" => PropertyData::TargetSource,
+            "target_destination" => PropertyData::TargetDestination,
+            "choices" => self.parse_choices(default, line)?,
+            "flags" => self.parse_flags()?,
+            _ => {
+                if !STRING_TYPES.contains(&property_type.as_str()) {
+                    self.warn(
+                        line,
+                        format!(
+                            "Unknown property type '{}' on '{}' imported as string",
+                            property_type, class_name
+                        ),
+                    );
+                }
+                PropertyData::String(default.unwrap_or_default())
+            }
+        };
+
+        Ok(Some(property))
+    }
+
+    fn parse_choices(
+        &mut self,
+        default: Option<String>,
+        line: usize,
+    ) -> Result<PropertyData, String> {
+        self.expect_symbol('=')?;
+        self.expect_symbol('[')?;
+
+        let mut choices: Vec<QuarchitectChoice> = Vec::new();
+        let mut default_index: Option<usize> = None;
+
+        while !self.is_symbol(']') {
+            let value = self.next()?;
+            self.expect_symbol(':')?;
+
+            let mut choice = QuarchitectChoice::default();
+            choice.name = self.expect_string()?;
+
+            let value_string = match &value {
+                Token::Word(word) => {
+                    choice.value = if let Ok(integer) = word.parse::<i32>() {
+                        ChoiceData::Integer(integer)
+                    } else if let Ok(float) = word.parse::<f32>() {
+                        ChoiceData::Float(float)
+                    } else {
+                        ChoiceData::String(word.clone())
+                    };
+                    word.clone()
+                }
+                Token::Quoted(quoted) => {
+                    choice.value = ChoiceData::String(quoted.clone());
+                    quoted.clone()
+                }
+                token => {
+                    return Err(format!(
+                        "Line {}: Expected a choice value, found {}",
+                        self.line(),
+                        token
+                    ))
+                }
+            };
+
+            if default.as_ref().map(|default| default.trim()) == Some(value_string.trim()) {
+                default_index = Some(choices.len());
+            }
+
+            choices.push(choice);
+        }
+        self.expect_symbol(']')?;
+
+        if let (Some(default), None) = (&default, default_index) {
+            self.warn(
+                line,
+                format!("Default '{}' does not match any choice", default),
+            );
+        }
+
+        Ok(PropertyData::Choices(
+            choices,
+            default_index.unwrap_or(0) as i32,
+        ))
+    }
+
+    fn parse_flags(&mut self) -> Result<PropertyData, String> {
+        self.expect_symbol('=')?;
+        self.expect_symbol('[')?;
+
+        // Flags are stored by bit position, so gaps in the FGD become unnamed entries
+        let mut flags: Vec<String> = Vec::new();
+        let mut default: i32 = 0;
+
+        while !self.is_symbol(']') {
+            let line = self.line();
+            let value = match self.next()? {
+                Token::Word(word) | Token::Quoted(word) => word,
+                token => {
+                    return Err(format!(
+                        "Line {}: Expected a flag value, found {}",
+                        line, token
+                    ))
+                }
+            };
+            self.expect_symbol(':')?;
+            let name = self.expect_string()?;
+
+            let mut enabled = false;
+            if self.is_symbol(':') {
+                self.next()?;
+                if let Some(flag_default) = self.value()? {
+                    enabled = flag_default.trim() != "0";
+                }
+            }
+
+            let value: u32 = match value.trim().parse() {
+                Ok(value) => value,
+                Err(_) => {
+                    self.warn(line, format!("Invalid flag value '{}' ignored", value));
+                    continue;
+                }
+            };
+
+            if !value.is_power_of_two() || value.trailing_zeros() >= 31 {
+                self.warn(line, format!("Flag value {} is not a valid bit", value));
+                continue;
+            }
+
+            let bit = value.trailing_zeros() as usize;
+            if flags.len() <= bit {
+                flags.resize(bit + 1, String::new());
+            }
+            flags[bit] = name;
+
+            if enabled {
+                default |= value as i32;
+            }
+        }
+        self.expect_symbol(']')?;
+
+        Ok(PropertyData::Flags(flags, default))
+    }
+}
+
+// Property types with no dedicated representation that are stored as plain strings
+const STRING_TYPES: &[&str] = &[
+    "string",
+    "studio",
+    "sprite",
+    "sound",
+    "decal",
+    "material",
+    "model",
+    "scene",
+    "angle",
+    "target_name_or_class",
+    "sidelist",
+    "npcclass",
+    "filterclass",
+    "pointentityclass",
+];
+
+fn single_argument(arguments: Vec<Vec<String>>) -> String {
+    arguments.concat().join(" ")
+}
+
+fn split_words(string: &str) -> Vec<String> {
+    string.split_whitespace().map(String::from).collect()
+}
+
+fn parse_numbers(words: &[String], line: usize) -> Result<Vec<f32>, String> {
+    words
+        .iter()
+        .map(|word| {
+            word.trim()
+                .parse::<f32>()
+                .map_err(|_| format!("Line {}: Expected a number, found '{}'", line, word))
+        })
+        .collect()
+}
+
+fn parse_vector3(words: &[String], line: usize) -> Result<quarchitect::Vector3, String> {
+    let numbers = parse_numbers(words, line)?;
+    if numbers.len() != 3 {
+        return Err(format!("Line {}: Expected three components", line));
+    }
+    Ok(quarchitect::Vector3::new(
+        numbers[0], numbers[1], numbers[2],
+    ))
+}
+
+fn parse_default<T: std::str::FromStr + Default>(
+    default: &str,
+    line: usize,
+    warnings: &mut Vec<String>,
+) -> T {
+    match default.trim().parse::<T>() {
+        Ok(value) => value,
+        Err(_) => {
+            warnings.push(format!(
+                "Line {}: Invalid default '{}' ignored",
+                line, default
+            ));
+            T::default()
+        }
+    }
+}
+
+pub fn parse(source: &str) -> Result<FgdFile, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+        warnings: Vec::new(),
+    };
+
+    parser.parse_file()
+}
@@ -0,0 +1,415 @@
+use super::{parse, write, FgdEntity, FgdFile, Metadata};
+use quarchitect::game_data::forge::{
+    Choice as QuarchitectChoice, ChoiceData, Entity as QuarchitectForgeEntity,
+    Property as QuarchitectProperty, PropertyData,
+};
+
+const ALL_TYPES: &str = include_str!("golden/all_types.fgd");
+
+fn entity(
+    class_type: i64,
+    class_name: &str,
+    description: &str,
+    properties: Vec<QuarchitectProperty>,
+    metadata: Vec<Metadata>,
+) -> FgdEntity {
+    let mut entity = QuarchitectForgeEntity::default();
+    entity.class_type = class_type.into();
+    entity.class_name = class_name.into();
+    entity.description = description.into();
+    entity.properties = properties;
+    FgdEntity::new(entity, metadata)
+}
+
+fn property(
+    name: &str,
+    short_description: &str,
+    long_description: &str,
+    data: PropertyData,
+) -> QuarchitectProperty {
+    let mut property = QuarchitectProperty::default();
+    property.name = name.into();
+    property.short_description = short_description.into();
+    property.long_description = long_description.into();
+    property.data = data;
+    property
+}
+
+fn choice(name: &str, value: ChoiceData) -> QuarchitectChoice {
+    let mut choice = QuarchitectChoice::default();
+    choice.name = name.into();
+    choice.value = value;
+    choice
+}
+
+// Covers every property type and metadata helper the writer supports
+fn all_types() -> FgdFile {
+    FgdFile {
+        includes: vec!["base.fgd".into()],
+        entities: vec![
+            entity(
+                0,
+                "Targetname",
+                "Targetable",
+                vec![
+                    property("targetname", "Name", "", PropertyData::TargetSource),
+                    property("target", "Target", "", PropertyData::TargetDestination),
+                ],
+                vec![
+                    Metadata::Color(quarchitect::Color::new(1.0, 0.0, 1.0)),
+                    Metadata::Size(
+                        quarchitect::Vector3::new(-8.0, -8.0, -8.0),
+                        quarchitect::Vector3::new(8.0, 8.0, 8.0),
+                    ),
+                ],
+            ),
+            entity(
+                1,
+                "item_armor",
+                "Armor pickup",
+                vec![
+                    property(
+                        "amount",
+                        "Amount",
+                        "Armor points given",
+                        PropertyData::Integer(100),
+                    ),
+                    property("speed", "Speed", "", PropertyData::Float(1.5)),
+                    property(
+                        "offset",
+                        "Offset",
+                        "",
+                        PropertyData::Vector3(quarchitect::Vector3::new(0.0, 0.0, 8.0)),
+                    ),
+                    property(
+                        "message",
+                        "Message",
+                        "",
+                        PropertyData::String("Hello".into()),
+                    ),
+                    property(
+                        "tint",
+                        "Tint",
+                        "",
+                        PropertyData::Color(quarchitect::Color::new(1.0, 0.5, 0.0)),
+                    ),
+                    property(
+                        "style",
+                        "Style",
+                        "",
+                        PropertyData::Choices(
+                            vec![
+                                choice("Normal", ChoiceData::Integer(0)),
+                                choice("Fancy", ChoiceData::Integer(1)),
+                            ],
+                            1,
+                        ),
+                    ),
+                    property(
+                        "spawnflags",
+                        "",
+                        "",
+                        PropertyData::Flags(
+                            vec!["Suspended".into(), String::new(), "Silent".into()],
+                            4,
+                        ),
+                    ),
+                ],
+                vec![
+                    Metadata::Base(vec!["Targetname".into()]),
+                    Metadata::Model("progs/armor.mdl".into()),
+                    Metadata::IconSprite("sprites/armor.png".into()),
+                ],
+            ),
+            entity(
+                1,
+                "monster_test",
+                "",
+                vec![property(
+                    "mode",
+                    "Mode",
+                    "",
+                    PropertyData::Choices(
+                        vec![
+                            choice("Walk", ChoiceData::String("walk".into())),
+                            choice("Run", ChoiceData::String("run".into())),
+                        ],
+                        0,
+                    ),
+                )],
+                vec![
+                    Metadata::Model(
+                        r#"{{ spawnflags & 1 -> "progs/a.mdl", "progs/b.mdl" }}"#.into(),
+                    ),
+                    Metadata::Studio(String::new()),
+                ],
+            ),
+            entity(
+                1,
+                "infodecal",
+                "",
+                vec![property(
+                    "texture",
+                    "Texture",
+                    "",
+                    PropertyData::String(String::new()),
+                )],
+                vec![Metadata::Decal],
+            ),
+            FgdEntity {
+                color255: vec!["rendercolor".into()],
+                ..entity(
+                    2,
+                    "func_wall",
+                    "Wall",
+                    vec![property(
+                        "rendercolor",
+                        "Render color",
+                        "",
+                        PropertyData::Color(quarchitect::Color::new(1.0, 128.0 / 255.0, 0.0)),
+                    )],
+                    Vec::new(),
+                )
+            },
+        ],
+        warnings: Vec::new(),
+    }
+}
+
+fn assert_vector_eq(actual: &quarchitect::Vector3, expected: &quarchitect::Vector3) {
+    assert_eq!(
+        (actual.x(), actual.y(), actual.z()),
+        (expected.x(), expected.y(), expected.z())
+    );
+}
+
+// Colors pass through 0-255 components, so they only survive to within a step
+fn assert_color_eq(actual: &quarchitect::Color, expected: &quarchitect::Color) {
+    for (actual, expected) in &[
+        (actual.r, expected.r),
+        (actual.g, expected.g),
+        (actual.b, expected.b),
+    ] {
+        assert!(
+            (actual - expected).abs() <= 1.0 / 255.0,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+}
+
+fn assert_choice_eq(actual: &QuarchitectChoice, expected: &QuarchitectChoice) {
+    assert_eq!(actual.name, expected.name);
+    match (&actual.value, &expected.value) {
+        (ChoiceData::Integer(actual), ChoiceData::Integer(expected)) => {
+            assert_eq!(actual, expected)
+        }
+        (ChoiceData::Float(actual), ChoiceData::Float(expected)) => assert_eq!(actual, expected),
+        (ChoiceData::String(actual), ChoiceData::String(expected)) => {
+            assert_eq!(actual, expected)
+        }
+        (actual, expected) => panic!("{:?} != {:?}", actual, expected),
+    }
+}
+
+fn assert_property_eq(actual: &QuarchitectProperty, expected: &QuarchitectProperty) {
+    assert_eq!(actual.name, expected.name);
+    assert_eq!(actual.short_description, expected.short_description);
+    assert_eq!(actual.long_description, expected.long_description);
+
+    match (&actual.data, &expected.data) {
+        (PropertyData::Integer(actual), PropertyData::Integer(expected)) => {
+            assert_eq!(actual, expected)
+        }
+        (PropertyData::Float(actual), PropertyData::Float(expected)) => {
+            assert_eq!(actual, expected)
+        }
+        (PropertyData::Vector3(actual), PropertyData::Vector3(expected)) => {
+            assert_vector_eq(actual, expected)
+        }
+        (PropertyData::String(actual), PropertyData::String(expected)) => {
+            assert_eq!(actual, expected)
+        }
+        (PropertyData::Color(actual), PropertyData::Color(expected)) => {
+            assert_color_eq(actual, expected)
+        }
+        (
+            PropertyData::Choices(actual, actual_default),
+            PropertyData::Choices(expected, expected_default),
+        ) => {
+            assert_eq!(actual.len(), expected.len());
+            for (actual, expected) in actual.iter().zip(expected) {
+                assert_choice_eq(actual, expected);
+            }
+            assert_eq!(actual_default, expected_default);
+        }
+        (
+            PropertyData::Flags(actual, actual_default),
+            PropertyData::Flags(expected, expected_default),
+        ) => {
+            assert_eq!(actual, expected);
+            assert_eq!(actual_default, expected_default);
+        }
+        (PropertyData::TargetSource, PropertyData::TargetSource) => (),
+        (PropertyData::TargetDestination, PropertyData::TargetDestination) => (),
+        (actual, expected) => panic!("{:?} != {:?}", actual, expected),
+    }
+}
+
+fn assert_metadata_list_eq(actual: &[Metadata], expected: &[Metadata]) {
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(expected) {
+        assert_metadata_eq(actual, expected);
+    }
+}
+
+fn assert_metadata_eq(actual: &Metadata, expected: &Metadata) {
+    match (actual, expected) {
+        (Metadata::Base(actual), Metadata::Base(expected)) => assert_eq!(actual, expected),
+        (Metadata::Color(actual), Metadata::Color(expected)) => assert_color_eq(actual, expected),
+        (Metadata::Size(actual_min, actual_max), Metadata::Size(expected_min, expected_max)) => {
+            assert_vector_eq(actual_min, expected_min);
+            assert_vector_eq(actual_max, expected_max);
+        }
+        (Metadata::Model(actual), Metadata::Model(expected)) => assert_eq!(actual, expected),
+        (Metadata::Studio(actual), Metadata::Studio(expected)) => assert_eq!(actual, expected),
+        (Metadata::IconSprite(actual), Metadata::IconSprite(expected)) => {
+            assert_eq!(actual, expected)
+        }
+        (Metadata::Decal, Metadata::Decal) => (),
+        (actual, expected) => panic!("{:?} != {:?}", actual, expected),
+    }
+}
+
+// Quarchitect's types have no PartialEq, so definitions are compared field by field
+fn assert_entity_eq(actual: &FgdEntity, expected: &FgdEntity) {
+    let (actual_definition, expected_definition) = (&actual.definition, &expected.definition);
+    assert_eq!(actual_definition.class_name, expected_definition.class_name);
+
+    let class_types: (i64, i64) = (
+        actual_definition.class_type.into(),
+        expected_definition.class_type.into(),
+    );
+    assert_eq!(class_types.0, class_types.1);
+    assert_eq!(
+        actual_definition.description,
+        expected_definition.description
+    );
+
+    assert_eq!(
+        actual_definition.properties.len(),
+        expected_definition.properties.len()
+    );
+    for (actual, expected) in actual_definition
+        .properties
+        .iter()
+        .zip(&expected_definition.properties)
+    {
+        assert_property_eq(actual, expected);
+    }
+
+    assert_metadata_list_eq(&actual.metadata, &expected.metadata);
+    assert_eq!(actual.color255, expected.color255);
+
+    let quarchitect_metadata = |definition: &QuarchitectForgeEntity| -> Vec<Metadata> {
+        definition
+            .metadata
+            .iter()
+            .cloned()
+            .map(Metadata::from)
+            .collect()
+    };
+    assert_metadata_list_eq(
+        &quarchitect_metadata(actual_definition),
+        &quarchitect_metadata(expected_definition),
+    );
+}
+
+#[test]
+fn writes_golden_fgd() {
+    assert_eq!(write(&all_types()), ALL_TYPES);
+}
+
+#[test]
+fn golden_fgd_round_trips() {
+    let fgd = parse(ALL_TYPES).unwrap();
+
+    assert!(fgd.warnings.is_empty(), "{:?}", fgd.warnings);
+    assert_eq!(write(&fgd), ALL_TYPES);
+}
+
+#[test]
+fn definitions_round_trip() {
+    let expected = all_types();
+    let actual = parse(&write(&expected)).unwrap();
+
+    assert!(actual.warnings.is_empty(), "{:?}", actual.warnings);
+    assert_eq!(actual.includes, expected.includes);
+    assert_eq!(actual.entities.len(), expected.entities.len());
+    for (actual, expected) in actual.entities.iter().zip(&expected.entities) {
+        assert_entity_eq(actual, expected);
+    }
+}
+
+#[test]
+fn parses_golden_fgd_structure() {
+    let fgd = parse(ALL_TYPES).unwrap();
+
+    assert_eq!(fgd.includes, vec!["base.fgd".to_string()]);
+
+    let class_names: Vec<&str> = fgd
+        .entities
+        .iter()
+        .map(|entity| entity.definition.class_name.as_str())
+        .collect();
+    assert_eq!(
+        class_names,
+        vec![
+            "Targetname",
+            "item_armor",
+            "monster_test",
+            "infodecal",
+            "func_wall"
+        ]
+    );
+
+    let class_types: Vec<i64> = fgd
+        .entities
+        .iter()
+        .map(|entity| entity.definition.class_type.into())
+        .collect();
+    assert_eq!(class_types, vec![0, 1, 1, 1, 2]);
+
+    let armor = &fgd.entities[1];
+    assert_eq!(armor.metadata.len(), 3);
+    // Only base() is meaningful to quarchitect
+    assert_eq!(armor.definition.metadata.len(), 1);
+
+    match &armor.definition.properties[5].data {
+        PropertyData::Choices(choices, default) => {
+            assert_eq!(choices.len(), 2);
+            assert_eq!(*default, 1);
+        }
+        _ => panic!("Expected choices"),
+    }
+
+    match &armor.definition.properties[6].data {
+        PropertyData::Flags(flags, default) => {
+            assert_eq!(
+                flags,
+                &vec!["Suspended".to_string(), String::new(), "Silent".to_string()]
+            );
+            assert_eq!(*default, 4);
+        }
+        _ => panic!("Expected flags"),
+    }
+
+    match &fgd.entities[2].metadata[0] {
+        Metadata::Model(model) => assert_eq!(
+            model,
+            r#"{{ spawnflags & 1 -> "progs/a.mdl", "progs/b.mdl" }}"#
+        ),
+        _ => panic!("Expected a model expression"),
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt::Write;
+
+use super::{FgdEntity, FgdFile, Metadata};
+use quarchitect::game_data::forge::{ChoiceData, Property as QuarchitectProperty, PropertyData};
+
+pub fn write(fgd: &FgdFile) -> String {
+    let mut out = String::new();
+
+    for include in &fgd.includes {
+        writeln!(out, "@include {}", quote(include)).unwrap();
+    }
+
+    if !fgd.includes.is_empty() {
+        out.push('\n');
+    }
+
+    for (i, entity) in fgd.entities.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_entity(&mut out, entity);
+    }
+
+    out
+}
+
+fn write_entity(out: &mut String, entity: &FgdEntity) {
+    let definition = &entity.definition;
+
+    let class_type: i64 = definition.class_type.into();
+    out.push_str(match class_type {
+        0 => "@BaseClass",
+        2 => "@SolidClass",
+        _ => "@PointClass",
+    });
+
+    for metadata in &entity.metadata {
+        out.push(' ');
+        write_metadata(out, metadata);
+    }
+
+    write!(out, " = {}", definition.class_name).unwrap();
+    if !definition.description.is_empty() {
+        write!(out, " : {}", quote(&definition.description)).unwrap();
+    }
+    out.push('\n');
+
+    out.push_str("[\n");
+    for property in &definition.properties {
+        let color255 = entity.color255.contains(&property.name);
+        write_property(out, property, color255);
+    }
+    out.push_str("]\n");
+}
+
+fn write_metadata(out: &mut String, metadata: &Metadata) {
+    match metadata {
+        Metadata::Base(base_classes) => write!(out, "base({})", base_classes.join(", ")).unwrap(),
+        Metadata::Color(color) => write!(
+            out,
+            "color({} {} {})",
+            (color.r * 255.0).round(),
+            (color.g * 255.0).round(),
+            (color.b * 255.0).round()
+        )
+        .unwrap(),
+        Metadata::Size(min, max) => write!(
+            out,
+            "size({} {} {}, {} {} {})",
+            min.x(),
+            min.y(),
+            min.z(),
+            max.x(),
+            max.y(),
+            max.z()
+        )
+        .unwrap(),
+        Metadata::Model(model) => write!(out, "model({})", model_argument(model)).unwrap(),
+        Metadata::Studio(model) => write!(out, "studio({})", model_argument(model)).unwrap(),
+        Metadata::IconSprite(sprite) => {
+            write!(out, "iconsprite({})", model_argument(sprite)).unwrap()
+        }
+        Metadata::Decal => out.push_str("decal()"),
+    }
+}
+
+// Expressions are written as-is, while plain paths need quoting
+fn model_argument(model: &str) -> String {
+    let model = model.trim();
+    if model.is_empty() || model.starts_with('{') {
+        model.to_string()
+    } else {
+        quote(model)
+    }
+}
+
+fn write_property(out: &mut String, property: &QuarchitectProperty, color255: bool) {
+    let (property_type, default) = match &property.data {
+        PropertyData::Integer(value) => ("integer", Some(value.to_string())),
+        PropertyData::Float(value) => ("float", Some(quote(&value.to_string()))),
+        PropertyData::Vector3(value) => (
+            "vector",
+            Some(quote(&format!("{} {} {}", value.x(), value.y(), value.z()))),
+        ),
+        PropertyData::String(value) => ("string", Some(quote(value))),
+        PropertyData::Color(value) if color255 => (
+            "color255",
+            Some(quote(&format!(
+                "{} {} {}",
+                (value.r * 255.0).round(),
+                (value.g * 255.0).round(),
+                (value.b * 255.0).round()
+            ))),
+        ),
+        PropertyData::Color(value) => (
+            "color1",
+            Some(quote(&format!("{} {} {}", value.r, value.g, value.b))),
+        ),
+        // Choice defaults are stored as an index, but written as the choice's value
+        PropertyData::Choices(choices, default) => (
+            "choices",
+            choices
+                .get(*default as usize)
+                .map(|choice| choice_value(&choice.value)),
+        ),
+        PropertyData::Flags(_, _) => ("flags", None),
+        PropertyData::TargetSource => ("target_source", None),
+        PropertyData::TargetDestination => ("target_destination", None),
+    };
+
+    write!(out, "\t{}({})", property.name, property_type).unwrap();
+
+    let mut fields: Vec<String> = vec![quote(&property.short_description)];
+    if let Some(default) = default {
+        fields.push(default);
+        if !property.long_description.is_empty() {
+            fields.push(quote(&property.long_description));
+        }
+    }
+
+    // An empty description on its own carries no information
+    if fields.len() == 1 && property.short_description.is_empty() {
+        fields.clear();
+    }
+
+    for field in fields {
+        write!(out, " : {}", field).unwrap();
+    }
+
+    match &property.data {
+        PropertyData::Choices(choices, _) => {
+            out.push_str(" =\n\t[\n");
+            for choice in choices {
+                writeln!(
+                    out,
+                    "\t\t{} : {}",
+                    choice_value(&choice.value),
+                    quote(&choice.name)
+                )
+                .unwrap();
+            }
+            out.push_str("\t]\n");
+        }
+        PropertyData::Flags(flags, default) => {
+            out.push_str(" =\n\t[\n");
+            for (bit, flag) in flags.iter().enumerate() {
+                if flag.is_empty() {
+                    continue;
+                }
+
+                let value = 1 << bit;
+                let enabled = if default & value != 0 { 1 } else { 0 };
+                writeln!(out, "\t\t{} : {} : {}", value, quote(flag), enabled).unwrap();
+            }
+            out.push_str("\t]\n");
+        }
+        _ => out.push('\n'),
+    }
+}
+
+fn choice_value(value: &ChoiceData) -> String {
+    match value {
+        ChoiceData::Integer(value) => value.to_string(),
+        ChoiceData::Float(value) => value.to_string(),
+        ChoiceData::String(value) => quote(value),
+    }
+}
+
+// FGD strings have no escape sequences, so embedded quotes are replaced
+fn quote(string: &str) -> String {
+    format!("\"{}\"", string.replace('"', "'"))
+}
@@ -1,6 +1,6 @@
 use gdnative::{
     godot_error, godot_wrap_method_inner, godot_wrap_method_parameter_count, methods, GodotString,
-    NativeClass, Resource, Variant, VariantArray,
+    Instance, MapMut, NativeClass, Resource, Variant, VariantArray,
 };
 
 use quarchitect::game_data::forge::{Choice as QuarchitectChoice, ChoiceData};
@@ -199,4 +199,15 @@ impl ForgeChoice {
     pub fn inner(&self) -> QuarchitectChoice {
         self.data.clone()
     }
+
+    pub fn from_inner(choice: QuarchitectChoice) -> Resource {
+        let (mut base, script) = Instance::<ForgeChoice>::new().decouple();
+        base.set_name(choice.name.clone().into());
+
+        if let Err(err) = script.map_mut(|script| script.data = choice) {
+            godot_error!("Error writing choice: {:?}", err);
+        }
+
+        base
+    }
 }
@@ -1,10 +1,12 @@
 use gdnative::{
     godot_error,
     init::property::{EnumHint, IntHint},
-    FromVariant, GodotString, Instance, Map, NativeClass, Resource, Variant, VariantArray,
+    FromVariant, GodotString, Instance, Map, MapMut, NativeClass, Resource, Variant,
+    VariantArray,
 };
 
-use super::{ForgeMetadata, ForgeProperty};
+use super::fgd::FgdEntity;
+use super::{ForgeMetadata, ForgeProperty, Metadata};
 use quarchitect::game_data::forge::Entity as QuarchitectForgeEntity;
 
 #[derive(NativeClass)]
@@ -154,23 +156,9 @@ impl ForgeEntity {
     // Business Logic
     pub fn inner(&self) -> QuarchitectForgeEntity {
         let metadata = self
-            .metadata
+            .full_metadata()
             .iter()
-            .flat_map(
-                |metadata| match Instance::<ForgeMetadata>::from_variant(metadata) {
-                    Ok(instance) => match instance.into_script().map(|script| script.inner()) {
-                        Ok(metadata) => Some(metadata),
-                        Err(err) => {
-                            godot_error!("Error reading metadata: {:?}", err);
-                            None
-                        }
-                    },
-                    Err(err) => {
-                        godot_error!("Error reading metadata: {:?}", err);
-                        None
-                    }
-                },
-            )
+            .flat_map(Metadata::to_quarchitect)
             .collect();
 
         let properties = self
@@ -199,4 +187,88 @@ impl ForgeEntity {
             ..self.data.clone()
         }
     }
+
+    pub fn fgd_entity(&self) -> FgdEntity {
+        FgdEntity {
+            color255: self.color255_properties(),
+            ..FgdEntity::new(self.inner(), self.full_metadata())
+        }
+    }
+
+    // Names of this class's own colour properties declared with 0-255 components
+    pub fn color255_properties(&self) -> Vec<String> {
+        self.properties
+            .iter()
+            .flat_map(|property| Instance::<ForgeProperty>::from_variant(property).ok())
+            .flat_map(|instance| {
+                instance
+                    .into_script()
+                    .map(|script| (script.inner().name, script.is_color255()))
+                    .ok()
+            })
+            .filter(|(_, color255)| *color255)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    fn full_metadata(&self) -> Vec<Metadata> {
+        self.metadata
+            .iter()
+            .flat_map(
+                |metadata| match Instance::<ForgeMetadata>::from_variant(metadata) {
+                    Ok(instance) => match instance.into_script().map(|script| script.inner()) {
+                        Ok(metadata) => Some(metadata),
+                        Err(err) => {
+                            godot_error!("Error reading metadata: {:?}", err);
+                            None
+                        }
+                    },
+                    Err(err) => {
+                        godot_error!("Error reading metadata: {:?}", err);
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+
+    pub fn from_inner(entity: QuarchitectForgeEntity) -> Resource {
+        ForgeEntity::from_fgd_entity(entity.into())
+    }
+
+    pub fn from_fgd_entity(fgd_entity: FgdEntity) -> Resource {
+        let entity = fgd_entity.definition;
+
+        let (mut base, script) = Instance::<ForgeEntity>::new().decouple();
+        base.set_name(entity.class_name.clone().into());
+
+        let mut metadata = VariantArray::new();
+        for entity_metadata in fgd_entity.metadata {
+            metadata.push(&Variant::from_object(&ForgeMetadata::from_inner(
+                entity_metadata,
+            )));
+        }
+
+        let mut properties = VariantArray::new();
+        for property in &entity.properties {
+            properties.push(&Variant::from_object(&ForgeProperty::from_inner(
+                property.clone(),
+                fgd_entity.color255.contains(&property.name),
+            )));
+        }
+
+        if let Err(err) = script.map_mut(|script| {
+            script.data = QuarchitectForgeEntity {
+                metadata: Vec::new(),
+                properties: Vec::new(),
+                ..entity
+            };
+            script.metadata = metadata;
+            script.properties = properties;
+        }) {
+            godot_error!("Error writing entity: {:?}", err);
+        }
+
+        base
+    }
 }
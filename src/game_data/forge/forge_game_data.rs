@@ -1,11 +1,12 @@
-use crate::game_data::forge::ForgeEntity;
+use crate::game_data::forge::{fgd, ForgeEntity};
 use gdnative::{
-    godot_error, godot_print, godot_wrap_method_inner, godot_wrap_method_parameter_count, methods,
+    godot_error, godot_print, godot_warn, godot_wrap_method_inner, godot_wrap_method_parameter_count, methods,
     user_data::RwLockData, FromVariant, GodotString, Instance, Map, NativeClass, Resource,
     StringArray, Variant, VariantArray,
 };
 
 use quarchitect::game_data::forge::GameData as QuarchitectForgeGameData;
+use std::collections::{HashMap, HashSet};
 
 #[derive(NativeClass)]
 #[inherit(Resource)]
@@ -46,6 +47,14 @@ fn register_forge_game_data(builder: &gdnative::init::ClassBuilder<ForgeGameData
         .with_setter(ForgeGameData::set_save_as)
         .with_usage(gdnative::init::property::Usage::NOEDITOR)
         .done();
+
+    builder
+        .add_property::<GodotString>("import_from")
+        .with_default(GodotString::new())
+        .with_getter(ForgeGameData::get_import_from)
+        .with_setter(ForgeGameData::set_import_from)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
 }
 
 #[methods]
@@ -71,6 +80,10 @@ impl ForgeGameData {
         &self.save_as
     }
 
+    fn get_import_from(&self, _owner: Resource) -> GodotString {
+        GodotString::new()
+    }
+
     // Setters
     fn set_name(&mut self, mut owner: Resource, new_name: GodotString) {
         let new_name_string = new_name.to_string();
@@ -108,16 +121,27 @@ impl ForgeGameData {
         if self.save_as != new_save_as {
             self.save_as = new_save_as;
         } else if !self.save_as.is_empty() {
-            godot_print!("Save FGD as {:?}", self.save_as);
-            let fgd = self.inner();
+            godot_print!("Save entity definitions as {:?}", self.save_as);
             let path = gdnative::ProjectSettings::godot_singleton().globalize_path(self.save_as.clone());
-            match fgd.save(path.to_string()) {
-                Ok(()) => (),
-                Err(err) => godot_error!("Failed to save FGD to {:?}: {:?}", path, err)
+            if let Err(err) = self.save(&path.to_string()) {
+                godot_error!("Failed to save entity definitions to {:?}: {}", path, err)
             }
         }
     }
 
+    fn set_import_from(&mut self, owner: Resource, new_import_from: GodotString) {
+        if new_import_from.is_empty() {
+            return;
+        }
+
+        godot_print!("Import FGD from {:?}", new_import_from);
+        let path = gdnative::ProjectSettings::godot_singleton().globalize_path(new_import_from);
+        match fgd::parse_file(&path.to_string()) {
+            Ok(fgd) => self.import(owner, fgd),
+            Err(err) => godot_error!("Failed to import FGD from {:?}: {}", path, err),
+        }
+    }
+
     // Overrides
     fn _init(mut owner: Resource) -> Self {
         if owner.get_name().is_empty() {
@@ -154,6 +178,16 @@ impl ForgeGameData {
                 "save_as",
                 gdnative::GlobalConstants::TYPE_STRING,
                 Some(36),
+                Some("*.fgd,*.ent,*.def"),
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "import_from",
+                gdnative::GlobalConstants::TYPE_STRING,
+                Some(gdnative::GlobalConstants::PROPERTY_HINT_GLOBAL_FILE),
                 Some("*.fgd"),
                 None,
             ),
@@ -162,6 +196,29 @@ impl ForgeGameData {
         property_list
     }
 
+    // Warns about definitions that have no spawn mapping in the given QodotGameData
+    #[export]
+    fn validate(&self, _owner: Resource, qodot_game_data: Resource) -> StringArray {
+        let mut warnings = StringArray::new();
+
+        let spawn_classes = match crate::game_data::validation::spawn_classes(&qodot_game_data) {
+            Ok(spawn_classes) => spawn_classes,
+            Err(err) => {
+                godot_error!("Failed to read qodot game data: {}", err);
+                return warnings;
+            }
+        };
+
+        for warning in
+            crate::game_data::validation::cross_reference(&spawn_classes, &self.inner().definitions)
+        {
+            godot_warn!("{}", warning);
+            warnings.push(&warning.into());
+        }
+
+        warnings
+    }
+
     // Business Logic
     pub fn inner(&self) -> QuarchitectForgeGameData {
         let definitions = self
@@ -189,4 +246,102 @@ impl ForgeGameData {
             ..self.data.clone()
         }
     }
+
+    // Colour properties whose map values use 0-255 components, by class, including those
+    // inherited from base classes
+    pub fn color255_properties(&self) -> Result<HashMap<String, HashSet<String>>, String> {
+        let (entities, _) = super::inheritance::flatten_fgd_entities(&self.fgd_file().entities)?;
+
+        Ok(entities
+            .into_iter()
+            .map(|entity| {
+                (
+                    entity.definition.class_name,
+                    entity.color255.into_iter().collect(),
+                )
+            })
+            .collect())
+    }
+
+    // Writes the definitions in the format matching the path's extension
+    fn save(&self, path: &str) -> Result<(), String> {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        let contents = match extension.as_str() {
+            "fgd" => self.fgd(),
+            "ent" | "def" => {
+                let (entities, warnings) =
+                    super::inheritance::flatten_fgd_entities(&self.fgd_file().entities)?;
+                for warning in warnings {
+                    godot_warn!("{}", warning);
+                }
+
+                if extension == "ent" {
+                    super::ent::write(&entities)
+                } else {
+                    super::def::write(&entities)
+                }
+            }
+            _ => return Err(format!("Unsupported definition format '{}'", extension)),
+        };
+
+        std::fs::write(path, contents).map_err(|err| err.to_string())
+    }
+
+    // Serializes the definitions, including editor-only helpers, as FGD source
+    pub fn fgd(&self) -> String {
+        fgd::write(&self.fgd_file())
+    }
+
+    fn fgd_file(&self) -> fgd::FgdFile {
+        let entities = self
+            .entities
+            .iter()
+            .flat_map(
+                |entity| match Instance::<ForgeEntity>::from_variant(entity) {
+                    Ok(instance) => match instance.into_script().map(|script| script.fgd_entity()) {
+                        Ok(entity) => Some(entity),
+                        Err(err) => {
+                            godot_error!("Error reading entity: {:?}", err);
+                            None
+                        }
+                    },
+                    Err(err) => {
+                        godot_error!("Error reading entity: {:?}", err);
+                        None
+                    }
+                },
+            )
+            .collect();
+
+        fgd::FgdFile {
+            includes: self.data.includes.clone(),
+            entities,
+            warnings: Vec::new(),
+        }
+    }
+
+    // Replaces the current definitions with those of a parsed FGD
+    fn import(&mut self, mut owner: Resource, fgd: fgd::FgdFile) {
+        for warning in &fgd.warnings {
+            godot_warn!("FGD import: {}", warning);
+        }
+
+        let mut entities = VariantArray::new();
+        for entity in fgd.entities {
+            entities.push(&Variant::from_object(&ForgeEntity::from_fgd_entity(entity)));
+        }
+
+        godot_print!("Imported {} entities", entities.len());
+
+        self.data.includes = fgd.includes;
+        self.entities = entities;
+
+        unsafe {
+            owner.property_list_changed_notify();
+        }
+    }
 }
@@ -1,16 +1,17 @@
 use gdnative::{
     godot_error, godot_wrap_method_inner, godot_wrap_method_parameter_count, methods, Color,
-    GodotString, NativeClass, Resource, StringArray, Variant, VariantArray, Vector3,
+    GodotString, Instance, MapMut, NativeClass, Resource, StringArray, Variant, VariantArray,
+    Vector3,
 };
 
-use quarchitect::game_data::forge::Metadata as QuarchitectMetadata;
+use super::Metadata;
 
 #[derive(Debug, NativeClass)]
 #[user_data[gdnative::user_data::RwLockData<ForgeMetadata>]]
 #[register_with(register_forge_metadata)]
 #[inherit(Resource)]
 pub struct ForgeMetadata {
-    data: QuarchitectMetadata,
+    data: Metadata,
 }
 
 fn register_forge_metadata(builder: &gdnative::init::ClassBuilder<ForgeMetadata>) {
@@ -53,82 +54,100 @@ fn register_forge_metadata(builder: &gdnative::init::ClassBuilder<ForgeMetadata>
         .with_setter(ForgeMetadata::set_bounding_box_max)
         .with_usage(gdnative::init::property::Usage::NOEDITOR)
         .done();
+
+    builder
+        .add_property::<GodotString>("model")
+        .with_default(GodotString::new())
+        .with_getter(ForgeMetadata::get_model)
+        .with_setter(ForgeMetadata::set_model)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<GodotString>("sprite")
+        .with_default(GodotString::new())
+        .with_getter(ForgeMetadata::get_sprite)
+        .with_setter(ForgeMetadata::set_sprite)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
 }
 
 #[methods]
 impl ForgeMetadata {
     // Getters
     pub fn get_metadata_type(&self, _owner: Resource) -> i32 {
-        match &self.data {
-            QuarchitectMetadata::Base(_) => 0,
-            QuarchitectMetadata::Color(_) => 1,
-            QuarchitectMetadata::Size(_, _) => 2,
-        }
+        (&self.data).into()
     }
 
     pub fn get_base_classes(&self, _owner: Resource) -> StringArray {
         match &self.data {
-            QuarchitectMetadata::Base(base_classes) => {
+            Metadata::Base(base_classes) => {
                 let mut string_array = StringArray::new();
                 for base_class in base_classes {
                     string_array.push(&GodotString::from_str(&base_class));
                 }
                 string_array
             }
-            QuarchitectMetadata::Color(_) => StringArray::new(),
-            QuarchitectMetadata::Size(_, _) => StringArray::new(),
+            _ => StringArray::new(),
         }
     }
 
     pub fn get_color(&self, _owner: Resource) -> Color {
         match &self.data {
-            QuarchitectMetadata::Base(_) => Color::rgb(1.0, 1.0, 1.0),
-            QuarchitectMetadata::Color(color) => Color::rgb(color.r, color.g, color.b),
-            QuarchitectMetadata::Size(_, _) => Color::rgb(1.0, 1.0, 1.0),
+            Metadata::Color(color) => Color::rgb(color.r, color.g, color.b),
+            _ => Color::rgb(1.0, 1.0, 1.0),
         }
     }
 
     pub fn get_bounding_box_min(&self, _owner: Resource) -> Vector3 {
         match &self.data {
-            QuarchitectMetadata::Base(_) => Vector3::default(),
-            QuarchitectMetadata::Color(_) => Vector3::default(),
-            QuarchitectMetadata::Size(min, _) => Vector3::new(min.x(), min.y(), min.z()),
+            Metadata::Size(min, _) => Vector3::new(min.x(), min.y(), min.z()),
+            _ => Vector3::default(),
         }
     }
 
     pub fn get_bounding_box_max(&self, _owner: Resource) -> Vector3 {
         match &self.data {
-            QuarchitectMetadata::Base(_) => Vector3::default(),
-            QuarchitectMetadata::Color(_) => Vector3::default(),
-            QuarchitectMetadata::Size(_, max) => Vector3::new(max.x(), max.y(), max.z()),
+            Metadata::Size(_, max) => Vector3::new(max.x(), max.y(), max.z()),
+            _ => Vector3::default(),
+        }
+    }
+
+    pub fn get_model(&self, _owner: Resource) -> GodotString {
+        match &self.data {
+            Metadata::Model(model) | Metadata::Studio(model) => model.into(),
+            _ => GodotString::new(),
+        }
+    }
+
+    pub fn get_sprite(&self, _owner: Resource) -> GodotString {
+        match &self.data {
+            Metadata::IconSprite(sprite) => sprite.into(),
+            _ => GodotString::new(),
         }
     }
 
     // Setters
     pub fn set_metadata_type(&mut self, mut owner: Resource, new_metadata_type: i32) {
-        let metadata_type: i32 = self.data.clone().into();
+        let metadata_type: i32 = (&self.data).into();
         if metadata_type == new_metadata_type {
             return;
         }
 
-        match new_metadata_type {
-            0 => {
-                self.data = QuarchitectMetadata::Base(Vec::default());
-                owner.set_name("Base".into());
-            }
-            1 => {
-                self.data = QuarchitectMetadata::Color(quarchitect::Color::new(1.0, 1.0, 1.0));
-                owner.set_name("Color".into());
-            }
-            2 => {
-                self.data = QuarchitectMetadata::Size(
-                    quarchitect::Vector3::default(),
-                    quarchitect::Vector3::default(),
-                );
-                owner.set_name("Size".into());
-            }
+        self.data = match new_metadata_type {
+            0 => Metadata::Base(Vec::default()),
+            1 => Metadata::Color(quarchitect::Color::new(1.0, 1.0, 1.0)),
+            2 => Metadata::Size(
+                quarchitect::Vector3::default(),
+                quarchitect::Vector3::default(),
+            ),
+            3 => Metadata::Model(String::new()),
+            4 => Metadata::Studio(String::new()),
+            5 => Metadata::IconSprite(String::new()),
+            6 => Metadata::Decal,
             _ => panic!("Unexpected metadata type"),
-        }
+        };
+        owner.set_name(self.data.name().into());
 
         unsafe {
             owner.property_list_changed_notify();
@@ -140,11 +159,11 @@ impl ForgeMetadata {
         for i in 0..new_base_classes.len() {
             vec.push(new_base_classes.get(i).to_string());
         }
-        self.data = QuarchitectMetadata::Base(vec)
+        self.data = Metadata::Base(vec)
     }
 
     pub fn set_color(&mut self, _owner: Resource, new_color: Color) {
-        self.data = QuarchitectMetadata::Color(quarchitect::Color::new(
+        self.data = Metadata::Color(quarchitect::Color::new(
             new_color.r,
             new_color.g,
             new_color.b,
@@ -153,11 +172,11 @@ impl ForgeMetadata {
 
     pub fn set_bounding_box_min(&mut self, _owner: Resource, new_bounding_box_min: Vector3) {
         let max = match self.data {
-            QuarchitectMetadata::Size(_, max) => max,
+            Metadata::Size(_, max) => max,
             _ => quarchitect::Vector3::default(),
         };
 
-        self.data = QuarchitectMetadata::Size(
+        self.data = Metadata::Size(
             quarchitect::Vector3::new(
                 new_bounding_box_min.x,
                 new_bounding_box_min.y,
@@ -169,11 +188,11 @@ impl ForgeMetadata {
 
     pub fn set_bounding_box_max(&mut self, _owner: Resource, new_bounding_box_max: Vector3) {
         let min = match self.data {
-            QuarchitectMetadata::Size(min, _) => min,
+            Metadata::Size(min, _) => min,
             _ => quarchitect::Vector3::default(),
         };
 
-        self.data = QuarchitectMetadata::Size(
+        self.data = Metadata::Size(
             min,
             quarchitect::Vector3::new(
                 new_bounding_box_max.x,
@@ -183,13 +202,24 @@ impl ForgeMetadata {
         )
     }
 
+    pub fn set_model(&mut self, _owner: Resource, new_model: GodotString) {
+        self.data = match self.data {
+            Metadata::Studio(_) => Metadata::Studio(new_model.to_string()),
+            _ => Metadata::Model(new_model.to_string()),
+        }
+    }
+
+    pub fn set_sprite(&mut self, _owner: Resource, new_sprite: GodotString) {
+        self.data = Metadata::IconSprite(new_sprite.to_string())
+    }
+
     // Overrides
     fn _init(mut owner: Resource) -> Self {
         if owner.get_name().is_empty() {
             owner.set_name("Metadata".into())
         }
 
-        let data = QuarchitectMetadata::base(Vec::new());
+        let data = Metadata::Base(Vec::new());
 
         ForgeMetadata { data }
     }
@@ -213,13 +243,13 @@ impl ForgeMetadata {
                 "metadata_type",
                 gdnative::GlobalConstants::TYPE_INT,
                 Some(gdnative::GlobalConstants::PROPERTY_HINT_ENUM),
-                Some("Base,Color,Size"),
+                Some("Base,Color,Size,Model,Studio,Icon Sprite,Decal"),
                 None,
             ),
         ));
 
         match self.data {
-            QuarchitectMetadata::Base(_) => {
+            Metadata::Base(_) => {
                 property_list.push(&Variant::from_dictionary(
                     &crate::util::build_property_dictionary(
                         "base_classes",
@@ -230,7 +260,7 @@ impl ForgeMetadata {
                     ),
                 ));
             }
-            QuarchitectMetadata::Color(_) => {
+            Metadata::Color(_) => {
                 property_list.push(&Variant::from_dictionary(
                     &crate::util::build_property_dictionary(
                         "color",
@@ -241,7 +271,7 @@ impl ForgeMetadata {
                     ),
                 ));
             }
-            QuarchitectMetadata::Size(_, _) => {
+            Metadata::Size(_, _) => {
                 property_list.push(&Variant::from_dictionary(
                     &crate::util::build_property_dictionary(
                         "bounding_box_min",
@@ -262,13 +292,47 @@ impl ForgeMetadata {
                     ),
                 ));
             }
+            Metadata::Model(_) | Metadata::Studio(_) => {
+                property_list.push(&Variant::from_dictionary(
+                    &crate::util::build_property_dictionary(
+                        "model",
+                        gdnative::GlobalConstants::TYPE_STRING,
+                        None,
+                        None,
+                        None,
+                    ),
+                ));
+            }
+            Metadata::IconSprite(_) => {
+                property_list.push(&Variant::from_dictionary(
+                    &crate::util::build_property_dictionary(
+                        "sprite",
+                        gdnative::GlobalConstants::TYPE_STRING,
+                        None,
+                        None,
+                        None,
+                    ),
+                ));
+            }
+            Metadata::Decal => (),
         }
 
         property_list
     }
 
     // Business Logic
-    pub fn inner(&self) -> QuarchitectMetadata {
+    pub fn inner(&self) -> Metadata {
         self.data.clone()
     }
+
+    pub fn from_inner(metadata: Metadata) -> Resource {
+        let (mut base, script) = Instance::<ForgeMetadata>::new().decouple();
+        base.set_name(metadata.name().into());
+
+        if let Err(err) = script.map_mut(|script| script.data = metadata) {
+            godot_error!("Error writing metadata: {:?}", err);
+        }
+
+        base
+    }
 }
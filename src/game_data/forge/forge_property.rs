@@ -1,6 +1,6 @@
 use gdnative::{
     godot_error, godot_wrap_method_inner, godot_wrap_method_parameter_count, FromVariant,
-    GodotString, Instance, Map, NativeClass, Resource, StringArray, Variant, VariantArray,
+    GodotString, Instance, Map, MapMut, NativeClass, Resource, StringArray, Variant, VariantArray,
 };
 
 use super::ForgeChoice;
@@ -13,6 +13,7 @@ use quarchitect::game_data::forge::{Property as QuarchitectProperty, PropertyDat
 pub struct ForgeProperty {
     data: QuarchitectProperty,
     choices: VariantArray,
+    color_255: bool,
     last_modified_flag: Option<i32>,
 }
 
@@ -89,6 +90,14 @@ fn register_forge_property(builder: &gdnative::init::ClassBuilder<ForgeProperty>
         .with_usage(gdnative::init::property::Usage::NOEDITOR)
         .done();
 
+    builder
+        .add_property::<bool>("color_255")
+        .with_default(false)
+        .with_getter(ForgeProperty::get_color_255)
+        .with_setter(ForgeProperty::set_color_255)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
     builder
         .add_property::<VariantArray>("choices")
         .with_default(VariantArray::default())
@@ -186,6 +195,10 @@ impl ForgeProperty {
         }
     }
 
+    pub fn get_color_255(&self, _owner: Resource) -> bool {
+        self.color_255
+    }
+
     pub fn get_choices(&self, _owner: Resource) -> &VariantArray {
         &self.choices
     }
@@ -288,6 +301,10 @@ impl ForgeProperty {
         ))
     }
 
+    pub fn set_color_255(&mut self, _owner: Resource, new_color_255: bool) {
+        self.color_255 = new_color_255
+    }
+
     pub fn set_choices(&mut self, owner: Resource, new_choices: VariantArray) {
         self.choices.resize(new_choices.len());
         for (i, choice) in new_choices.iter().enumerate() {
@@ -407,11 +424,13 @@ impl ForgeProperty {
 
         let data = QuarchitectProperty::default();
         let choices = VariantArray::default();
+        let color_255 = false;
         let last_modified_flag = None;
 
         ForgeProperty {
             data,
             choices,
+            color_255,
             last_modified_flag,
         }
     }
@@ -525,6 +544,16 @@ impl ForgeProperty {
                         None,
                     ),
                 ));
+
+                property_list.push(&Variant::from_dictionary(
+                    &crate::util::build_property_dictionary(
+                        "color_255",
+                        gdnative::GlobalConstants::TYPE_BOOL,
+                        None,
+                        None,
+                        None,
+                    ),
+                ));
             }
             PropertyData::Choices(_, _) => {
                 property_list.push(&Variant::from_dictionary(
@@ -637,4 +666,34 @@ impl ForgeProperty {
             _ => self.data.clone(),
         }
     }
+
+    // Whether map values are written in 0-255 rather than 0-1 components
+    pub fn is_color255(&self) -> bool {
+        match self.data.data {
+            PropertyData::Color(_) => self.color_255,
+            _ => false,
+        }
+    }
+
+    pub fn from_inner(property: QuarchitectProperty, color_255: bool) -> Resource {
+        let (mut base, script) = Instance::<ForgeProperty>::new().decouple();
+        base.set_name(property.name.clone().into());
+
+        let mut choices = VariantArray::new();
+        if let PropertyData::Choices(property_choices, _) = &property.data {
+            for choice in property_choices {
+                choices.push(&Variant::from_object(&ForgeChoice::from_inner(choice.clone())));
+            }
+        }
+
+        if let Err(err) = script.map_mut(|script| {
+            script.data = property;
+            script.color_255 = color_255;
+            script.set_choices(base.new_ref(), choices);
+        }) {
+            godot_error!("Error writing property: {:?}", err);
+        }
+
+        base
+    }
 }
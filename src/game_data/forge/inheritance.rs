@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use super::fgd::FgdEntity;
+use super::Metadata;
+use quarchitect::game_data::forge::{
+    Entity as QuarchitectForgeEntity, Metadata as QuarchitectMetadata,
+    Property as QuarchitectProperty,
+};
+
+#[cfg(test)]
+mod tests;
+
+// Resolves base() metadata so that every definition carries its full inherited property set.
+// Returns the flattened definitions in their original order, along with any warnings.
+pub fn flatten_definitions(
+    definitions: &[QuarchitectForgeEntity],
+) -> Result<(Vec<QuarchitectForgeEntity>, Vec<String>), String> {
+    let mut resolver = Resolver {
+        definitions: definitions
+            .iter()
+            .map(|definition| (definition.class_name.as_str(), definition))
+            .collect(),
+        resolved: HashMap::new(),
+        stack: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let mut flattened: Vec<QuarchitectForgeEntity> = Vec::new();
+    for definition in definitions {
+        if let Some(entity) = resolver.resolve(&definition.class_name)? {
+            flattened.push(entity);
+        }
+    }
+
+    Ok((flattened, resolver.warnings))
+}
+
+// Flattens full FGD entities for formats without inheritance. Editor-only helpers such as
+// model() are taken from the nearest class in the hierarchy that declares any, and colour
+// ranges from whichever class last declared each property.
+pub fn flatten_fgd_entities(
+    entities: &[FgdEntity],
+) -> Result<(Vec<FgdEntity>, Vec<String>), String> {
+    let definitions: Vec<QuarchitectForgeEntity> = entities
+        .iter()
+        .map(|entity| entity.definition.clone())
+        .collect();
+    let (definitions, warnings) = flatten_definitions(&definitions)?;
+
+    let by_name: HashMap<&str, &FgdEntity> = entities
+        .iter()
+        .map(|entity| (entity.definition.class_name.as_str(), entity))
+        .collect();
+
+    let flattened = definitions
+        .into_iter()
+        .map(|definition| {
+            let mut metadata: Vec<Metadata> = definition
+                .metadata
+                .iter()
+                .cloned()
+                .map(Metadata::from)
+                .collect();
+            metadata.extend(display_metadata(&by_name, &definition.class_name));
+
+            let color255_properties = color255_properties(&by_name, &definition.class_name);
+            let color255 = definition
+                .properties
+                .iter()
+                .filter(|property| color255_properties.get(&property.name) == Some(&true))
+                .map(|property| property.name.clone())
+                .collect();
+
+            FgdEntity {
+                color255,
+                ..FgdEntity::new(definition, metadata)
+            }
+        })
+        .collect();
+
+    Ok((flattened, warnings))
+}
+
+// Cycles have already been rejected by flatten_definitions, so this recursion terminates
+fn display_metadata(by_name: &HashMap<&str, &FgdEntity>, class_name: &str) -> Vec<Metadata> {
+    let entity = match by_name.get(class_name) {
+        Some(entity) => entity,
+        None => return Vec::new(),
+    };
+
+    let own: Vec<Metadata> = entity
+        .metadata
+        .iter()
+        .filter(|metadata| metadata.to_quarchitect().is_none())
+        .cloned()
+        .collect();
+
+    if !own.is_empty() {
+        return own;
+    }
+
+    base_classes(&entity.definition)
+        .iter()
+        .map(|base_class| display_metadata(by_name, base_class))
+        .find(|metadata| !metadata.is_empty())
+        .unwrap_or_default()
+}
+
+// Whether each property in a class's hierarchy is declared color255, overridden in the same
+// order as flatten_definitions merges properties
+fn color255_properties(
+    by_name: &HashMap<&str, &FgdEntity>,
+    class_name: &str,
+) -> HashMap<String, bool> {
+    let entity = match by_name.get(class_name) {
+        Some(entity) => entity,
+        None => return HashMap::new(),
+    };
+
+    let mut color255: HashMap<String, bool> = HashMap::new();
+    for base_class in base_classes(&entity.definition) {
+        color255.extend(color255_properties(by_name, &base_class));
+    }
+
+    for property in &entity.definition.properties {
+        color255.insert(
+            property.name.clone(),
+            entity.color255.contains(&property.name),
+        );
+    }
+
+    color255
+}
+
+struct Resolver<'a> {
+    definitions: HashMap<&'a str, &'a QuarchitectForgeEntity>,
+    resolved: HashMap<String, QuarchitectForgeEntity>,
+    stack: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Resolver<'a> {
+    fn resolve(&mut self, class_name: &str) -> Result<Option<QuarchitectForgeEntity>, String> {
+        if let Some(entity) = self.resolved.get(class_name) {
+            return Ok(Some(entity.clone()));
+        }
+
+        if let Some(position) = self.stack.iter().position(|name| name == class_name) {
+            let mut chain = self.stack[position..].to_vec();
+            chain.push(class_name.to_string());
+            return Err(format!(
+                "Cyclic base class inheritance: {}",
+                chain.join(" -> ")
+            ));
+        }
+
+        let definition = match self.definitions.get(class_name) {
+            Some(definition) => *definition,
+            None => return Ok(None),
+        };
+
+        self.stack.push(class_name.to_string());
+
+        let mut properties: Vec<QuarchitectProperty> = Vec::new();
+        let mut color: Option<QuarchitectMetadata> = None;
+        let mut size: Option<QuarchitectMetadata> = None;
+
+        // Later bases override earlier ones, and the class itself overrides all of its bases
+        for base_class in base_classes(definition) {
+            match self.resolve(&base_class)? {
+                Some(base) => {
+                    merge_properties(&mut properties, base.properties);
+                    merge_metadata(&mut color, &mut size, base.metadata);
+                }
+                None => self.warnings.push(format!(
+                    "'{}' derives from unknown base class '{}'",
+                    class_name, base_class
+                )),
+            }
+        }
+
+        merge_properties(&mut properties, definition.properties.clone());
+        merge_metadata(&mut color, &mut size, definition.metadata.clone());
+
+        self.stack.pop();
+
+        let entity = QuarchitectForgeEntity {
+            metadata: color.into_iter().chain(size.into_iter()).collect(),
+            properties,
+            ..definition.clone()
+        };
+
+        self.resolved.insert(class_name.to_string(), entity.clone());
+        Ok(Some(entity))
+    }
+}
+
+fn base_classes(definition: &QuarchitectForgeEntity) -> Vec<String> {
+    definition
+        .metadata
+        .iter()
+        .flat_map(|metadata| match metadata {
+            QuarchitectMetadata::Base(base_classes) => base_classes.clone(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+// Overridden properties keep their original position so inherited keys stay grouped
+fn merge_properties(
+    properties: &mut Vec<QuarchitectProperty>,
+    overrides: Vec<QuarchitectProperty>,
+) {
+    for property in overrides {
+        match properties
+            .iter_mut()
+            .find(|existing| existing.name == property.name)
+        {
+            Some(existing) => *existing = property,
+            None => properties.push(property),
+        }
+    }
+}
+
+fn merge_metadata(
+    color: &mut Option<QuarchitectMetadata>,
+    size: &mut Option<QuarchitectMetadata>,
+    metadata: Vec<QuarchitectMetadata>,
+) {
+    for metadata in metadata {
+        match metadata {
+            QuarchitectMetadata::Base(_) => (),
+            QuarchitectMetadata::Color(_) => *color = Some(metadata),
+            QuarchitectMetadata::Size(_, _) => *size = Some(metadata),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use super::{flatten_definitions, flatten_fgd_entities};
+use crate::game_data::forge::fgd::FgdEntity;
+use quarchitect::game_data::forge::{
+    Entity as QuarchitectForgeEntity, Metadata as QuarchitectMetadata,
+    Property as QuarchitectProperty, PropertyData,
+};
+
+fn entity(
+    class_name: &str,
+    base_classes: &[&str],
+    properties: &[(&str, i32)],
+) -> QuarchitectForgeEntity {
+    let mut entity = QuarchitectForgeEntity::default();
+    entity.class_name = class_name.into();
+    if !base_classes.is_empty() {
+        entity.metadata = vec![QuarchitectMetadata::Base(
+            base_classes.iter().map(|base| base.to_string()).collect(),
+        )];
+    }
+    entity.properties = properties
+        .iter()
+        .map(|(name, value)| {
+            let mut property = QuarchitectProperty::default();
+            property.name = name.to_string();
+            property.data = PropertyData::Integer(*value);
+            property
+        })
+        .collect();
+    entity
+}
+
+// Flattened properties as (name, value) pairs, in order
+fn properties(entities: &[QuarchitectForgeEntity], class_name: &str) -> Vec<(String, i32)> {
+    entities
+        .iter()
+        .find(|entity| entity.class_name == class_name)
+        .expect("Missing flattened class")
+        .properties
+        .iter()
+        .map(|property| match property.data {
+            PropertyData::Integer(value) => (property.name.clone(), value),
+            _ => panic!("Expected an integer property"),
+        })
+        .collect()
+}
+
+fn pairs(expected: &[(&str, i32)]) -> Vec<(String, i32)> {
+    expected
+        .iter()
+        .map(|(name, value)| (name.to_string(), *value))
+        .collect()
+}
+
+#[test]
+fn overrides_through_multiple_levels() {
+    let (flattened, warnings) = flatten_definitions(&[
+        entity("Base", &[], &[("a", 1), ("b", 1)]),
+        entity("Middle", &["Base"], &[("b", 2), ("c", 2)]),
+        entity("Leaf", &["Middle"], &[("a", 3), ("d", 3)]),
+    ])
+    .unwrap();
+
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert_eq!(
+        properties(&flattened, "Middle"),
+        pairs(&[("a", 1), ("b", 2), ("c", 2)])
+    );
+
+    // a is overridden by the leaf, but keeps the position the base gave it
+    assert_eq!(
+        properties(&flattened, "Leaf"),
+        pairs(&[("a", 3), ("b", 2), ("c", 2), ("d", 3)])
+    );
+}
+
+#[test]
+fn keeps_definition_order() {
+    let (flattened, _) = flatten_definitions(&[
+        entity("Leaf", &["Base"], &[]),
+        entity("Base", &[], &[("a", 1)]),
+    ])
+    .unwrap();
+
+    let class_names: Vec<&str> = flattened
+        .iter()
+        .map(|entity| entity.class_name.as_str())
+        .collect();
+    assert_eq!(class_names, vec!["Leaf", "Base"]);
+    assert!(flattened[0].metadata.is_empty());
+}
+
+#[test]
+fn merges_diamond_inheritance() {
+    let (flattened, warnings) = flatten_definitions(&[
+        entity("Base", &[], &[("shared", 0), ("base", 0)]),
+        entity("Left", &["Base"], &[("shared", 1), ("left", 1)]),
+        entity("Right", &["Base"], &[("shared", 2), ("right", 2)]),
+        entity("Diamond", &["Left", "Right"], &[]),
+    ])
+    .unwrap();
+
+    assert!(warnings.is_empty(), "{:?}", warnings);
+
+    // The later base wins, and the shared base's properties only appear once
+    assert_eq!(
+        properties(&flattened, "Diamond"),
+        pairs(&[("shared", 2), ("base", 0), ("left", 1), ("right", 2)])
+    );
+}
+
+#[test]
+fn rejects_cyclic_inheritance() {
+    let result = flatten_definitions(&[
+        entity("Start", &["First"], &[]),
+        entity("First", &["Second"], &[]),
+        entity("Second", &["First"], &[]),
+    ]);
+
+    assert_eq!(
+        result.err(),
+        Some("Cyclic base class inheritance: First -> Second -> First".to_string())
+    );
+}
+
+#[test]
+fn warns_about_unknown_base_classes() {
+    let (flattened, warnings) =
+        flatten_definitions(&[entity("Leaf", &["Missing"], &[("a", 1)])]).unwrap();
+
+    assert_eq!(
+        warnings,
+        vec!["'Leaf' derives from unknown base class 'Missing'".to_string()]
+    );
+    assert_eq!(properties(&flattened, "Leaf"), pairs(&[("a", 1)]));
+}
+
+#[test]
+fn inherits_color_ranges() {
+    let fgd_entity = |class_name, base_classes, colors: &[(&str, bool)]| {
+        let mut definition = entity(class_name, base_classes, &[]);
+        definition.properties = colors
+            .iter()
+            .map(|(name, _)| {
+                let mut property = QuarchitectProperty::default();
+                property.name = name.to_string();
+                property.data = PropertyData::Color(quarchitect::Color::default());
+                property
+            })
+            .collect();
+
+        FgdEntity {
+            color255: colors
+                .iter()
+                .filter(|(_, color255)| *color255)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            ..FgdEntity::from(definition)
+        }
+    };
+
+    let (flattened, _) = flatten_fgd_entities(&[
+        fgd_entity("Base", &[], &[("rendercolor", true), ("_color", true)]),
+        fgd_entity("Leaf", &["Base"], &[("_color", false), ("tint", true)]),
+    ])
+    .unwrap();
+
+    assert_eq!(flattened[0].color255, vec!["rendercolor", "_color"]);
+    assert_eq!(flattened[1].color255, vec!["rendercolor", "tint"]);
+}
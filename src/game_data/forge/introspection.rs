@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use gdnative::{
+    Dictionary, GlobalConstants, GodotString, PackedScene, ResourceLoader, Script, Variant,
+};
+use quarchitect::game_data::forge::{
+    Choice as QuarchitectChoice, ChoiceData, Property as QuarchitectProperty, PropertyData,
+};
+
+// Builds forge property definitions from the exported variables of a script.
+// Values in `overrides` take precedence over the script's own defaults.
+pub fn script_properties(
+    script_path: &str,
+    overrides: &HashMap<String, Variant>,
+) -> Result<(Vec<QuarchitectProperty>, Vec<String>), String> {
+    let mut script = load_script(script_path)?;
+
+    let mut properties: Vec<QuarchitectProperty> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+
+    for property_info in script.get_script_property_list().iter() {
+        let property_info = property_info.to_dictionary();
+
+        // Only exported variables are meaningful to a level designer
+        let usage = dictionary_int(&property_info, "usage");
+        if usage & GlobalConstants::PROPERTY_USAGE_EDITOR == 0 {
+            continue;
+        }
+
+        let name = dictionary_string(&property_info, "name");
+        let default = match overrides.get(&name) {
+            Some(value) => value.clone(),
+            None => script.get_property_default_value(name.as_str().into()),
+        };
+
+        let data = match property_data(&property_info, &default) {
+            Some(data) => data,
+            None => {
+                warnings.push(format!(
+                    "{}: Property '{}' has no FGD equivalent and was skipped",
+                    script_path, name
+                ));
+                continue;
+            }
+        };
+
+        let mut property = QuarchitectProperty::default();
+        property.short_description = description(&name);
+        property.name = name;
+        property.data = data;
+        properties.push(property);
+    }
+
+    Ok((properties, warnings))
+}
+
+// Finds the script attached to a prefab's root node, along with the values the scene
+// assigns over that script's defaults
+pub fn prefab_script(scene_path: &str) -> Result<(String, HashMap<String, Variant>), String> {
+    let scene = ResourceLoader::godot_singleton()
+        .load(scene_path.into(), "PackedScene".into(), false)
+        .and_then(|resource| resource.cast::<PackedScene>())
+        .ok_or_else(|| format!("Failed to load scene {}", scene_path))?;
+
+    let state = scene
+        .get_state()
+        .ok_or_else(|| format!("Failed to read scene state for {}", scene_path))?;
+
+    let mut script_path: Option<String> = None;
+    let mut overrides: HashMap<String, Variant> = HashMap::new();
+
+    for i in 0..state.get_node_property_count(0) {
+        let name = state.get_node_property_name(0, i).to_string();
+        let value = state.get_node_property_value(0, i);
+
+        if name == "script" {
+            script_path = value
+                .try_to_object::<Script>()
+                .map(|script| script.get_path().to_string());
+        } else {
+            overrides.insert(name, value);
+        }
+    }
+
+    match script_path {
+        Some(script_path) => Ok((script_path, overrides)),
+        None => Err(format!("Root node of {} has no script", scene_path)),
+    }
+}
+
+fn load_script(script_path: &str) -> Result<Script, String> {
+    ResourceLoader::godot_singleton()
+        .load(script_path.into(), "Script".into(), false)
+        .and_then(|resource| resource.cast::<Script>())
+        .ok_or_else(|| format!("Failed to load script {}", script_path))
+}
+
+fn property_data(property_info: &Dictionary, default: &Variant) -> Option<PropertyData> {
+    let property_type = dictionary_int(property_info, "type");
+    let hint = dictionary_int(property_info, "hint");
+    let hint_string = dictionary_string(property_info, "hint_string");
+
+    match property_type {
+        GlobalConstants::TYPE_BOOL => Some(PropertyData::Choices(
+            vec![
+                choice("No", ChoiceData::Integer(0)),
+                choice("Yes", ChoiceData::Integer(1)),
+            ],
+            if default.to_bool() { 1 } else { 0 },
+        )),
+        GlobalConstants::TYPE_INT => match hint {
+            GlobalConstants::PROPERTY_HINT_ENUM => {
+                let choices = enum_choices(&hint_string);
+                let default = default.to_i64() as i32;
+                let default_index = choices
+                    .iter()
+                    .position(|choice| match choice.value {
+                        ChoiceData::Integer(value) => value == default,
+                        _ => false,
+                    })
+                    .unwrap_or(0);
+                Some(PropertyData::Choices(choices, default_index as i32))
+            }
+            GlobalConstants::PROPERTY_HINT_FLAGS => Some(PropertyData::Flags(
+                hint_string
+                    .split(',')
+                    .map(|flag| flag.trim().to_string())
+                    .collect(),
+                default.to_i64() as i32,
+            )),
+            _ => Some(PropertyData::Integer(default.to_i64() as i32)),
+        },
+        GlobalConstants::TYPE_REAL => Some(PropertyData::Float(default.to_f64() as f32)),
+        GlobalConstants::TYPE_STRING => match hint {
+            GlobalConstants::PROPERTY_HINT_ENUM => {
+                let choices: Vec<QuarchitectChoice> = hint_string
+                    .split(',')
+                    .map(|name| choice(name.trim(), ChoiceData::String(name.trim().to_string())))
+                    .collect();
+                let default = default.to_string();
+                let default_index = choices
+                    .iter()
+                    .position(|choice| choice.name == default)
+                    .unwrap_or(0);
+                Some(PropertyData::Choices(choices, default_index as i32))
+            }
+            _ => Some(PropertyData::String(default.to_string())),
+        },
+        GlobalConstants::TYPE_VECTOR3 => {
+            let vector = default.to_vector3();
+            Some(PropertyData::Vector3(quarchitect::Vector3::new(
+                vector.x, vector.y, vector.z,
+            )))
+        }
+        GlobalConstants::TYPE_COLOR => {
+            let color = default.to_color();
+            Some(PropertyData::Color(quarchitect::Color::new(
+                color.r, color.g, color.b,
+            )))
+        }
+        _ => None,
+    }
+}
+
+// Enum hints are either "A,B,C" or "A:4,B:8,C:16"
+fn enum_choices(hint_string: &str) -> Vec<QuarchitectChoice> {
+    let mut next_value = 0;
+    hint_string
+        .split(',')
+        .map(|entry| {
+            let mut parts = entry.splitn(2, ':');
+            let name = parts.next().unwrap_or_default().trim();
+            if let Some(value) = parts
+                .next()
+                .and_then(|value| value.trim().parse::<i32>().ok())
+            {
+                next_value = value;
+            }
+
+            let choice = choice(name, ChoiceData::Integer(next_value));
+            next_value += 1;
+            choice
+        })
+        .collect()
+}
+
+fn choice(name: &str, value: ChoiceData) -> QuarchitectChoice {
+    let mut choice = QuarchitectChoice::default();
+    choice.name = name.to_string();
+    choice.value = value;
+    choice
+}
+
+// "move_speed" -> "Move Speed"
+fn description(name: &str) -> String {
+    GodotString::from_str(name).capitalize().to_string()
+}
+
+fn dictionary_int(dictionary: &Dictionary, key: &str) -> i64 {
+    dictionary.get(&Variant::from_str(key)).to_i64()
+}
+
+fn dictionary_string(dictionary: &Dictionary, key: &str) -> String {
+    dictionary.get(&Variant::from_str(key)).to_string()
+}
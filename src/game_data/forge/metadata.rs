@@ -0,0 +1,62 @@
+use quarchitect::game_data::forge::Metadata as QuarchitectMetadata;
+
+// Class helpers attached to a definition. Quarchitect only understands base, color and size;
+// the remaining kinds only change how editors display the entity.
+#[derive(Debug, Clone)]
+pub enum Metadata {
+    Base(Vec<String>),
+    Color(quarchitect::Color),
+    Size(quarchitect::Vector3, quarchitect::Vector3),
+    // Either a model path or a TrenchBroom expression, e.g. {{ spawnflags & 1 -> "a.mdl", "b.mdl" }}
+    Model(String),
+    Studio(String),
+    IconSprite(String),
+    Decal,
+}
+
+impl Metadata {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metadata::Base(_) => "Base",
+            Metadata::Color(_) => "Color",
+            Metadata::Size(_, _) => "Size",
+            Metadata::Model(_) => "Model",
+            Metadata::Studio(_) => "Studio",
+            Metadata::IconSprite(_) => "Icon Sprite",
+            Metadata::Decal => "Decal",
+        }
+    }
+
+    pub fn to_quarchitect(&self) -> Option<QuarchitectMetadata> {
+        match self {
+            Metadata::Base(base_classes) => Some(QuarchitectMetadata::Base(base_classes.clone())),
+            Metadata::Color(color) => Some(QuarchitectMetadata::Color(*color)),
+            Metadata::Size(min, max) => Some(QuarchitectMetadata::Size(*min, *max)),
+            _ => None,
+        }
+    }
+}
+
+impl From<QuarchitectMetadata> for Metadata {
+    fn from(metadata: QuarchitectMetadata) -> Self {
+        match metadata {
+            QuarchitectMetadata::Base(base_classes) => Metadata::Base(base_classes),
+            QuarchitectMetadata::Color(color) => Metadata::Color(color),
+            QuarchitectMetadata::Size(min, max) => Metadata::Size(min, max),
+        }
+    }
+}
+
+impl Into<i32> for &Metadata {
+    fn into(self) -> i32 {
+        match self {
+            Metadata::Base(_) => 0,
+            Metadata::Color(_) => 1,
+            Metadata::Size(_, _) => 2,
+            Metadata::Model(_) => 3,
+            Metadata::Studio(_) => 4,
+            Metadata::IconSprite(_) => 5,
+            Metadata::Decal => 6,
+        }
+    }
+}
@@ -3,9 +3,18 @@ mod forge_game_data;
 mod forge_metadata;
 mod forge_property;
 mod forge_choice;
+mod def;
+mod ent;
+mod fgd;
+mod inheritance;
+mod introspection;
+mod metadata;
 
 pub use forge_entity::ForgeEntity;
 pub use forge_game_data::ForgeGameData;
 pub use forge_metadata::ForgeMetadata;
 pub use forge_property::ForgeProperty;
 pub use forge_choice::ForgeChoice;
+pub use inheritance::flatten_definitions;
+pub use introspection::{prefab_script, script_properties};
+pub use metadata::Metadata;
@@ -4,6 +4,7 @@ mod qodot_entity;
 mod qodot_game_data;
 mod qodot_worldspawn_layer;
 mod qodot_material_data;
+mod validation;
 pub mod forge;
 
 pub use brush_data::BrushData;
@@ -13,4 +14,5 @@ pub use qodot_entity::QodotEntity;
 pub use qodot_game_data::QodotGameData;
 pub use qodot_worldspawn_layer::QodotWorldspawnLayer;
 pub use qodot_material_data::QodotMaterialData;
-pub use qodot_material_data::DefaultMaterialType;
\ No newline at end of file
+pub use qodot_material_data::DefaultMaterialType;
+pub use validation::validate;
\ No newline at end of file
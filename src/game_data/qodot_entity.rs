@@ -1,10 +1,14 @@
 use gdnative::{
-    godot_error, godot_wrap_method_inner, godot_wrap_method_parameter_count, methods, FromVariant,
-    GodotString, Instance, NativeClass, Resource, Variant, VariantArray,
+    godot_error, godot_print, godot_warn, godot_wrap_method_inner,
+    godot_wrap_method_parameter_count, methods, FromVariant, GodotString, Instance, Map,
+    NativeClass, Resource, ResourceSaver, Variant, VariantArray,
 };
+use std::collections::HashMap;
 
+use crate::game_data::forge::ForgeEntity;
 use crate::game_data::BrushData;
 use crate::game_data::PointData;
+use quarchitect::game_data::forge::Entity as QuarchitectForgeEntity;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum EntityType {
@@ -77,6 +81,14 @@ fn register_qodot_entity(builder: &gdnative::init::ClassBuilder<QodotEntity>) {
         .with_setter(QodotEntity::set_brush_data)
         .with_usage(gdnative::init::PropertyUsage::NOEDITOR)
         .done();
+
+    builder
+        .add_property::<GodotString>("save_forge_entity")
+        .with_default(GodotString::new())
+        .with_getter(QodotEntity::get_save_forge_entity)
+        .with_setter(QodotEntity::set_save_forge_entity)
+        .with_usage(gdnative::init::PropertyUsage::NOEDITOR)
+        .done();
 }
 
 #[methods]
@@ -98,6 +110,10 @@ impl QodotEntity {
         self.brush_data.try_to_object()
     }
 
+    pub fn get_save_forge_entity(&self, _owner: Resource) -> GodotString {
+        GodotString::new()
+    }
+
     // Setters
     pub fn set_classname(&mut self, mut owner: Resource, new_classname: GodotString) {
         if self.classname != new_classname {
@@ -142,6 +158,87 @@ impl QodotEntity {
         }
     }
 
+    pub fn set_save_forge_entity(&mut self, _owner: Resource, path: GodotString) {
+        if path.is_empty() {
+            return;
+        }
+
+        let forge_entity = match self.forge_entity() {
+            Ok(forge_entity) => ForgeEntity::from_inner(forge_entity),
+            Err(err) => {
+                godot_error!("Failed to generate forge entity: {}", err);
+                return;
+            }
+        };
+
+        match ResourceSaver::godot_singleton().save(path.clone(), Some(forge_entity), 0) {
+            Ok(_) => godot_print!("Saved forge entity to {}", path.to_string()),
+            Err(err) => godot_error!("Failed to save {}: {:?}", path.to_string(), err),
+        }
+    }
+
+    // Generates a forge entity whose properties mirror the exported variables of the
+    // component script, or of the prefab scene's root script when no component is set
+    #[export]
+    pub fn generate_forge_entity(&self, _owner: Resource) -> Option<Resource> {
+        match self.forge_entity() {
+            Ok(forge_entity) => Some(ForgeEntity::from_inner(forge_entity)),
+            Err(err) => {
+                godot_error!("Failed to generate forge entity: {}", err);
+                None
+            }
+        }
+    }
+
+    fn forge_entity(&self) -> Result<QuarchitectForgeEntity, String> {
+        let point_data = Instance::<PointData>::from_variant(&self.point_data)
+            .map_err(|err| format!("Failed to read entity point data: {:?}", err))?;
+
+        let (point_data_base, point_data_script) = point_data.decouple();
+        let (spawn_type, component_type) = point_data_script
+            .map(|point_data_script: &PointData| {
+                let data = point_data_script.get_data(point_data_base);
+                (data.entity_type.clone(), data.component_type.clone())
+            })
+            .map_err(|err| format!("Error reading point data: {:?}", err))?;
+
+        let (script_path, overrides) = match (component_type, spawn_type) {
+            (quarchitect::game_data::ComponentType::Script(script_path), _)
+                if !script_path.is_empty() =>
+            {
+                (script_path, HashMap::new())
+            }
+            (_, quarchitect::game_data::EntityType::Prefab(prefab_scene))
+                if !prefab_scene.is_empty() =>
+            {
+                crate::game_data::forge::prefab_script(&prefab_scene)?
+            }
+            _ => {
+                return Err(format!(
+                    "{} has no component script or prefab scene",
+                    self.classname.to_string()
+                ))
+            }
+        };
+
+        let (properties, warnings) =
+            crate::game_data::forge::script_properties(&script_path, &overrides)?;
+        for warning in warnings {
+            godot_warn!("{}", warning);
+        }
+
+        let class_type: i64 = match self.entity_type {
+            EntityType::Brush => 2,
+            _ => 1,
+        };
+
+        let mut forge_entity = QuarchitectForgeEntity::default();
+        forge_entity.class_type = class_type.into();
+        forge_entity.class_name = self.classname.to_string();
+        forge_entity.properties = properties;
+        Ok(forge_entity)
+    }
+
     // Overrides
     fn _init(mut owner: Resource) -> Self {
         if owner.get_name().is_empty() {
@@ -217,6 +314,16 @@ impl QodotEntity {
             ));
         }
 
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "save_forge_entity",
+                gdnative::GlobalConstants::TYPE_STRING,
+                Some(gdnative::GlobalConstants::PROPERTY_HINT_FILE),
+                Some("*.tres,*.res"),
+                None,
+            ),
+        ));
+
         property_list
     }
 }
@@ -2,8 +2,8 @@ use super::{BrushData, EntityType, PointData};
 use crate::game_data::qodot_entity::QodotEntity;
 use crate::game_data::qodot_worldspawn_layer::QodotWorldspawnLayer;
 use gdnative::{
-    godot_error, user_data::RwLockData, FromVariant, Instance, Map, NativeClass, Resource, Variant,
-    VariantArray, GodotString,
+    godot_error, godot_warn, user_data::RwLockData, FromVariant, Instance, Map, NativeClass,
+    Resource, Variant, VariantArray, GodotString, StringArray,
 };
 
 #[derive(NativeClass)]
@@ -87,6 +87,51 @@ impl QodotGameData {
         }
     }
 
+    // Warns about classnames that have no matching definition in the given ForgeGameData
+    #[export]
+    pub fn validate(&self, _owner: Resource, forge_game_data: Resource) -> StringArray {
+        let mut warnings = StringArray::new();
+
+        let definitions = match super::validation::definitions(&forge_game_data) {
+            Ok(definitions) => definitions,
+            Err(err) => {
+                godot_error!("Failed to read forge game data: {}", err);
+                return warnings;
+            }
+        };
+
+        for warning in super::validation::cross_reference(&self.spawn_classes(), &definitions) {
+            godot_warn!("{}", warning);
+            warnings.push(&warning.into());
+        }
+
+        warnings
+    }
+
+    pub fn spawn_classes(&self) -> Vec<(String, EntityType)> {
+        self.entities
+            .iter()
+            .flat_map(|entity| {
+                let entity = match Instance::<QodotEntity>::from_variant(entity) {
+                    Ok(entity) => entity,
+                    Err(err) => {
+                        godot_error!("Failed to load entity data: {:?}", err);
+                        return None;
+                    }
+                };
+
+                let entity: RwLockData<QodotEntity> = entity.into_script();
+                match entity.map(|entity| (entity.classname.to_string(), entity.entity_type)) {
+                    Ok(spawn_class) => Some(spawn_class),
+                    Err(err) => {
+                        godot_error!("Failed to read entity classname: {:?}", err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
     pub fn to_quarchitect_game_data(&self) -> quarchitect::game_data::GameData {
         quarchitect::game_data::GameData {
             entities: self
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use gdnative::{
+    user_data::{LocalCellData, RwLockData},
+    FromVariant, Instance, Map, Resource, Variant,
+};
+use quarchitect::game_data::forge::Entity as QuarchitectForgeEntity;
+
+use super::forge::ForgeGameData;
+use super::{EntityType, QodotGameData};
+
+#[cfg(test)]
+mod tests;
+
+// Spawned by the map builder itself rather than through game data
+const BUILTIN_CLASSES: &[&str] = &["worldspawn"];
+
+const CLASS_TYPE_BASE: i64 = 0;
+const CLASS_TYPE_POINT: i64 = 1;
+const CLASS_TYPE_SOLID: i64 = 2;
+
+pub fn validate(
+    qodot_game_data: &Resource,
+    forge_game_data: &Resource,
+) -> Result<Vec<String>, String> {
+    Ok(cross_reference(
+        &spawn_classes(qodot_game_data)?,
+        &definitions(forge_game_data)?,
+    ))
+}
+
+pub fn spawn_classes(qodot_game_data: &Resource) -> Result<Vec<(String, EntityType)>, String> {
+    let qodot_game_data =
+        Instance::<QodotGameData>::from_variant(&Variant::from_object(qodot_game_data))
+            .map_err(|err| err.to_string())?;
+    let qodot_game_data: LocalCellData<QodotGameData> = qodot_game_data.into_script();
+    qodot_game_data
+        .map(QodotGameData::spawn_classes)
+        .map_err(|err| format!("{:?}", err))
+}
+
+pub fn definitions(forge_game_data: &Resource) -> Result<Vec<QuarchitectForgeEntity>, String> {
+    let forge_game_data =
+        Instance::<ForgeGameData>::from_variant(&Variant::from_object(forge_game_data))
+            .map_err(|err| err.to_string())?;
+    let forge_game_data: RwLockData<ForgeGameData> = forge_game_data.into_script();
+    forge_game_data
+        .map(|forge_game_data| forge_game_data.inner().definitions)
+        .map_err(|err| format!("{:?}", err))
+}
+
+// Cross-references spawn behaviour against editor definitions, returning a warning for
+// every classname that is missing from either side or disagrees on brush / point type
+pub fn cross_reference(
+    spawn_classes: &[(String, EntityType)],
+    definitions: &[QuarchitectForgeEntity],
+) -> Vec<String> {
+    let mut warnings: Vec<String> = Vec::new();
+
+    // Base classes only exist to be inherited from, and are never placed in a map
+    let definitions: HashMap<&str, i64> = definitions
+        .iter()
+        .map(|definition| {
+            let class_type: i64 = definition.class_type.into();
+            (definition.class_name.as_str(), class_type)
+        })
+        .filter(|(_, class_type)| *class_type != CLASS_TYPE_BASE)
+        .collect();
+
+    for (classname, entity_type) in spawn_classes {
+        if BUILTIN_CLASSES.contains(&classname.as_str()) {
+            continue;
+        }
+
+        match definitions.get(classname.as_str()) {
+            None => warnings.push(format!(
+                "'{}' has a spawn mapping in QodotGameData but no ForgeGameData definition",
+                classname
+            )),
+            Some(&class_type) => {
+                let expected = match entity_type {
+                    EntityType::Brush => CLASS_TYPE_SOLID,
+                    EntityType::Point | EntityType::Placeholder => CLASS_TYPE_POINT,
+                };
+
+                if class_type != expected {
+                    warnings.push(format!(
+                        "'{}' is a {} entity in QodotGameData but a {} class in ForgeGameData",
+                        classname,
+                        if expected == CLASS_TYPE_SOLID {
+                            "brush"
+                        } else {
+                            "point"
+                        },
+                        if class_type == CLASS_TYPE_SOLID {
+                            "solid"
+                        } else {
+                            "point"
+                        },
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut orphans: Vec<&&str> = definitions
+        .keys()
+        .filter(|class_name| !BUILTIN_CLASSES.contains(class_name))
+        .filter(|class_name| {
+            !spawn_classes
+                .iter()
+                .any(|(classname, _)| classname == **class_name)
+        })
+        .collect();
+    orphans.sort();
+
+    for class_name in orphans {
+        warnings.push(format!(
+            "'{}' is defined in ForgeGameData but has no QodotGameData spawn mapping",
+            class_name
+        ));
+    }
+
+    warnings
+}
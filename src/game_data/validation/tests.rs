@@ -0,0 +1,94 @@
+use super::cross_reference;
+use crate::game_data::EntityType;
+use quarchitect::game_data::forge::Entity as QuarchitectForgeEntity;
+
+fn definition(class_type: i64, class_name: &str) -> QuarchitectForgeEntity {
+    let mut definition = QuarchitectForgeEntity::default();
+    definition.class_type = class_type.into();
+    definition.class_name = class_name.into();
+    definition
+}
+
+fn spawn_classes(classes: &[(&str, EntityType)]) -> Vec<(String, EntityType)> {
+    classes
+        .iter()
+        .map(|(classname, entity_type)| (classname.to_string(), *entity_type))
+        .collect()
+}
+
+#[test]
+fn matching_game_data_has_no_warnings() {
+    let warnings = cross_reference(
+        &spawn_classes(&[
+            ("func_door", EntityType::Brush),
+            ("light", EntityType::Point),
+            ("info_notnull", EntityType::Placeholder),
+        ]),
+        &[
+            definition(0, "Targetname"),
+            definition(2, "func_door"),
+            definition(1, "light"),
+            definition(1, "info_notnull"),
+        ],
+    );
+
+    assert!(warnings.is_empty(), "{:?}", warnings);
+}
+
+#[test]
+fn warns_about_missing_definitions() {
+    let warnings = cross_reference(
+        &spawn_classes(&[("func_door", EntityType::Brush)]),
+        &[definition(0, "func_door")],
+    );
+
+    // Base classes can't be placed, so they don't count as definitions
+    assert_eq!(
+        warnings,
+        vec!["'func_door' has a spawn mapping in QodotGameData but no ForgeGameData definition"]
+    );
+}
+
+#[test]
+fn warns_about_orphaned_definitions_in_order() {
+    let warnings = cross_reference(
+        &[],
+        &[
+            definition(1, "monster_zombie"),
+            definition(2, "func_door"),
+            definition(2, "worldspawn"),
+        ],
+    );
+
+    assert_eq!(
+        warnings,
+        vec![
+            "'func_door' is defined in ForgeGameData but has no QodotGameData spawn mapping",
+            "'monster_zombie' is defined in ForgeGameData but has no QodotGameData spawn mapping",
+        ]
+    );
+}
+
+#[test]
+fn warns_about_type_mismatches() {
+    let warnings = cross_reference(
+        &spawn_classes(&[
+            ("func_door", EntityType::Point),
+            ("light", EntityType::Brush),
+            ("worldspawn", EntityType::Point),
+        ]),
+        &[
+            definition(2, "func_door"),
+            definition(1, "light"),
+            definition(2, "worldspawn"),
+        ],
+    );
+
+    assert_eq!(
+        warnings,
+        vec![
+            "'func_door' is a point entity in QodotGameData but a solid class in ForgeGameData",
+            "'light' is a brush entity in QodotGameData but a point class in ForgeGameData",
+        ]
+    );
+}
@@ -6,12 +6,14 @@ use gdnative::{
 
 pub mod util;
 
+mod compiler;
 mod game_data;
 mod map;
 mod qodot_map;
 mod texture_loader;
 mod wad;
 
+use compiler::QodotCompiler;
 use game_data::{
     forge::{ForgeChoice, ForgeEntity, ForgeGameData, ForgeMetadata, ForgeProperty},
     BrushData, PointData, QodotEntity, QodotGameData, QodotMaterialData, QodotWorldspawnLayer,
@@ -45,6 +47,7 @@ fn init(handle: gdnative::init::InitHandle) {
 
     handle.add_tool_class::<QodotMap>();
     handle.add_tool_class::<QodotBuildWorker>();
+    handle.add_tool_class::<QodotCompiler>();
 }
 
 // macros that create the entry-points of the dynamic library.
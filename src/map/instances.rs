@@ -0,0 +1,811 @@
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod tests;
+
+type Vector = [f64; 3];
+
+// A map with its instances expanded, and every instance file it was built from
+pub struct ExpandedMap {
+    pub source: String,
+    pub instance_paths: Vec<PathBuf>,
+}
+
+// Entities and brushes, kept close enough to the source to be written back out
+#[derive(Default)]
+struct MapEntity {
+    properties: Vec<(String, String)>,
+    brushes: Vec<Brush>,
+}
+
+#[derive(Default)]
+struct Brush {
+    lines: Vec<BrushLine>,
+}
+
+enum BrushLine {
+    Face(Face),
+    // Anything else, like patch definitions, is passed through untransformed
+    Raw(String),
+}
+
+struct Face {
+    points: [Vector; 3],
+    texture: String,
+    alignment: Alignment,
+}
+
+enum Alignment {
+    // Offsets, rotation and scale, plus any trailing surface flags
+    Standard(String),
+    Valve {
+        u: [f64; 4],
+        v: [f64; 4],
+        rest: String,
+    },
+}
+
+impl MapEntity {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn set(&mut self, key: &str, value: String) {
+        match self.properties.iter_mut().find(|(k, _)| k == key) {
+            Some(property) => property.1 = value,
+            None => self.properties.push((key.to_string(), value)),
+        }
+    }
+
+    fn classname(&self) -> &str {
+        self.get("classname").unwrap_or_default()
+    }
+
+    // TrenchBroom stores layers and groups as func_groups, but their brushes are world geometry
+    fn is_world(&self) -> bool {
+        self.classname() == "worldspawn"
+            || (self.classname() == "func_group" && self.get("_tb_type").is_some())
+    }
+}
+
+// Builds misc_external_map and func_instance entities into the map. ericw-tools'
+// misc_external_map brings in the referenced map's world brushes, either merged into
+// worldspawn or as an entity of _external_map_classname. Valve's func_instance brings in
+// every entity, substituting $variables from its replace keys and fixing up entity names.
+// Relative instance paths are resolved from base_path, the file the map was authored as.
+// Returns None if the map has no instances, so it can be built as it is.
+pub fn expand_instances(map_path: &Path, base_path: &Path) -> Result<Option<ExpandedMap>, String> {
+    let source = std::fs::read_to_string(map_path)
+        .map_err(|err| format!("Failed to read {:?}: {}", map_path, err))?;
+
+    if !source.contains("misc_external_map") && !source.contains("func_instance") {
+        return Ok(None);
+    }
+
+    let (header, entities) =
+        parse_map(&source).map_err(|err| format!("{:?}: {}", map_path, err))?;
+
+    let canonical = map_path
+        .canonicalize()
+        .map_err(|err| format!("Failed to resolve {:?}: {}", map_path, err))?;
+
+    let mut expansion = Expansion::default();
+    expansion.stack.push(canonical);
+
+    // The authored file may not exist yet, if the resource holding the map is unsaved
+    if let Ok(canonical_base) = base_path.canonicalize() {
+        if !expansion.stack.contains(&canonical_base) {
+            expansion.stack.push(canonical_base);
+        }
+    }
+
+    let entities = expand_entities(entities, base_path, &mut expansion)?;
+
+    if expansion.instance_paths.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(ExpandedMap {
+        source: write_map(&header, &entities),
+        instance_paths: expansion.instance_paths,
+    }))
+}
+
+// State shared by every level of one map's expansion
+#[derive(Default)]
+struct Expansion {
+    // Files currently being expanded, outermost first, to catch instances that include themselves
+    stack: Vec<PathBuf>,
+    instance_paths: Vec<PathBuf>,
+    // Unnamed func_instances so far, numbering the names generated for them
+    auto_names: usize,
+}
+
+fn expand_entities(
+    entities: Vec<MapEntity>,
+    map_path: &Path,
+    expansion: &mut Expansion,
+) -> Result<Vec<MapEntity>, String> {
+    let mut expanded: Vec<MapEntity> = Vec::new();
+    let mut world_brushes: Vec<Brush> = Vec::new();
+
+    for entity in entities {
+        let classname = entity.classname().to_string();
+        match classname.as_str() {
+            "misc_external_map" => {
+                let file = entity
+                    .get("_external_map")
+                    .ok_or_else(|| "misc_external_map has no _external_map key".to_string())?;
+                let instance = load_instance(file, map_path, &[], expansion)?;
+
+                let angles = match entity.get("_external_map_angles") {
+                    Some(angles) => parse_vector(angles),
+                    None => entity
+                        .get("_external_map_angle")
+                        .and_then(parse_number)
+                        .map(|yaw| [0.0, yaw, 0.0]),
+                };
+                let scale = entity.get("_external_map_scale").and_then(|scale| {
+                    parse_vector(scale).or_else(|| parse_number(scale).map(|s| [s, s, s]))
+                });
+                let transform =
+                    Transform::new(entity.get("origin").and_then(parse_vector), angles, scale);
+
+                let brushes = instance
+                    .into_iter()
+                    .filter(|instance_entity| instance_entity.is_world())
+                    .filter(|instance_entity| !is_omitted_layer(instance_entity))
+                    .flat_map(|instance_entity| instance_entity.brushes)
+                    .map(|brush| transform.brush(brush));
+
+                match entity.get("_external_map_classname") {
+                    Some(classname) if !classname.is_empty() && classname != "worldspawn" => {
+                        let properties = entity
+                            .properties
+                            .iter()
+                            .filter(|(key, _)| !key.starts_with("_external_map") && key != "origin")
+                            .map(|(key, value)| match key.as_str() {
+                                "classname" => (key.clone(), classname.to_string()),
+                                _ => (key.clone(), value.clone()),
+                            })
+                            .collect();
+
+                        expanded.push(MapEntity {
+                            properties,
+                            brushes: brushes.collect(),
+                        });
+                    }
+                    _ => world_brushes.extend(brushes),
+                }
+            }
+            "func_instance" => {
+                let file = entity
+                    .get("file")
+                    .ok_or_else(|| "func_instance has no file key".to_string())?;
+                let instance = load_instance(file, map_path, &replacements(&entity), expansion)?;
+
+                let transform = Transform::new(
+                    entity.get("origin").and_then(parse_vector),
+                    entity.get("angles").and_then(parse_vector),
+                    None,
+                );
+
+                // As in Valve's tools, unnamed instances get a generated name, so two copies
+                // of the same prefab never share their internal names
+                let fixup_name = match entity.get("targetname") {
+                    Some(targetname) if !targetname.is_empty() => targetname.to_string(),
+                    _ => {
+                        expansion.auto_names += 1;
+                        format!("InstanceAuto{}", expansion.auto_names)
+                    }
+                };
+                let fixup_style = entity
+                    .get("fixup_style")
+                    .and_then(parse_number)
+                    .unwrap_or(0.0) as i32;
+
+                for mut instance_entity in instance {
+                    if is_omitted_layer(&instance_entity) {
+                        continue;
+                    }
+
+                    if instance_entity.is_world() {
+                        world_brushes.extend(
+                            instance_entity
+                                .brushes
+                                .into_iter()
+                                .map(|brush| transform.brush(brush)),
+                        );
+                        continue;
+                    }
+
+                    fix_up_names(&mut instance_entity, &fixup_name, fixup_style);
+                    adopt_containers(&mut instance_entity, &entity);
+                    expanded.push(transform.entity(instance_entity));
+                }
+            }
+            _ => expanded.push(entity),
+        }
+    }
+
+    if !world_brushes.is_empty() {
+        match expanded
+            .iter_mut()
+            .find(|entity| entity.classname() == "worldspawn")
+        {
+            Some(worldspawn) => worldspawn.brushes.append(&mut world_brushes),
+            None => expanded.insert(
+                0,
+                MapEntity {
+                    properties: vec![("classname".into(), "worldspawn".into())],
+                    brushes: world_brushes,
+                },
+            ),
+        }
+    }
+
+    Ok(expanded)
+}
+
+fn load_instance(
+    file: &str,
+    map_path: &Path,
+    replacements: &[(String, String)],
+    expansion: &mut Expansion,
+) -> Result<Vec<MapEntity>, String> {
+    let path = instance_path(file, map_path);
+    let canonical = path
+        .canonicalize()
+        .map_err(|err| format!("Failed to resolve instance {:?}: {}", path, err))?;
+
+    if expansion.stack.contains(&canonical) {
+        let chain: Vec<String> = expansion
+            .stack
+            .iter()
+            .chain(std::iter::once(&canonical))
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        return Err(format!("Instance cycle: {}", chain.join(" -> ")));
+    }
+
+    if !expansion.instance_paths.contains(&canonical) {
+        expansion.instance_paths.push(canonical.clone());
+    }
+
+    let source = std::fs::read_to_string(&canonical)
+        .map_err(|err| format!("Failed to read instance {:?}: {}", canonical, err))?;
+    let (_, mut entities) =
+        parse_map(&source).map_err(|err| format!("{:?}: {}", canonical, err))?;
+
+    // Substituted before nested instances expand, so parameters can be passed down
+    for entity in &mut entities {
+        for (_, value) in &mut entity.properties {
+            for (variable, replacement) in replacements {
+                if value.contains(variable.as_str()) {
+                    *value = value.replace(variable.as_str(), replacement);
+                }
+            }
+        }
+    }
+
+    expansion.stack.push(canonical.clone());
+    let entities = expand_entities(entities, &canonical, expansion);
+    expansion.stack.pop();
+    entities
+}
+
+// Instance files are found relative to the map referencing them, or in the project
+fn instance_path(file: &str, map_path: &Path) -> PathBuf {
+    if file.starts_with("res://") || file.starts_with("user://") {
+        let path = gdnative::ProjectSettings::godot_singleton().globalize_path(file.into());
+        return PathBuf::from(path.to_string());
+    }
+
+    let file = Path::new(file);
+    match map_path.parent() {
+        Some(directory) if file.is_relative() => directory.join(file),
+        _ => file.to_path_buf(),
+    }
+}
+
+// Parameters are given as replace01 "$variable value", longest variable first so that
+// $a doesn't replace part of $ab
+fn replacements(entity: &MapEntity) -> Vec<(String, String)> {
+    let mut replacements: Vec<(String, String)> = entity
+        .properties
+        .iter()
+        .filter(|(key, _)| key.starts_with("replace"))
+        .filter_map(|(_, value)| {
+            let value = value.trim();
+            let split = value.find(char::is_whitespace)?;
+            let (variable, replacement) = value.split_at(split);
+            if variable.starts_with('$') {
+                Some((variable.to_string(), replacement.trim().to_string()))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    replacements.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    replacements
+}
+
+// Names starting with @ or ! refer outside the instance, and are left alone
+fn fix_up_names(entity: &mut MapEntity, name: &str, style: i32) {
+    for (key, value) in &mut entity.properties {
+        match key.as_str() {
+            "targetname" | "target" | "killtarget" | "parentname" => (),
+            _ => continue,
+        }
+
+        if value.is_empty() || value.starts_with('@') || value.starts_with('!') {
+            continue;
+        }
+
+        *value = match style {
+            0 => format!("{}-{}", name, value),
+            1 => format!("{}-{}", value, name),
+            _ => continue,
+        };
+    }
+}
+
+// Layer and group ids are local to the instance file, and would collide with the host's, so
+// instanced entities are placed in the func_instance's layer and group instead
+fn adopt_containers(entity: &mut MapEntity, instance: &MapEntity) {
+    entity
+        .properties
+        .retain(|(key, _)| key != "_tb_layer" && key != "_tb_group");
+
+    for key in &["_tb_layer", "_tb_group"] {
+        if let Some(id) = instance.get(key) {
+            entity.set(key, id.to_string());
+        }
+    }
+}
+
+fn is_omitted_layer(entity: &MapEntity) -> bool {
+    entity.get("_tb_type") == Some("_tb_layer")
+        && entity.get("_tb_layer_omit_from_export") == Some("1")
+}
+
+// Places an instance: scaled, then rotated by pitch, yaw and roll in Quake's order, then moved
+struct Transform {
+    rotation: [Vector; 3],
+    scale: Vector,
+    origin: Vector,
+    yaw: f64,
+    pitch_or_roll: bool,
+}
+
+impl Transform {
+    fn new(origin: Option<Vector>, angles: Option<Vector>, scale: Option<Vector>) -> Transform {
+        let [pitch, yaw, roll] = angles.unwrap_or([0.0, 0.0, 0.0]);
+        let rotation = multiply_matrices(
+            rotation_z(yaw.to_radians()),
+            multiply_matrices(
+                rotation_y(pitch.to_radians()),
+                rotation_x(roll.to_radians()),
+            ),
+        );
+
+        Transform {
+            rotation,
+            scale: scale.unwrap_or([1.0, 1.0, 1.0]),
+            origin: origin.unwrap_or([0.0, 0.0, 0.0]),
+            yaw,
+            pitch_or_roll: pitch != 0.0 || roll != 0.0,
+        }
+    }
+
+    fn point(&self, point: Vector) -> Vector {
+        let scaled = [
+            point[0] * self.scale[0],
+            point[1] * self.scale[1],
+            point[2] * self.scale[2],
+        ];
+        let rotated = multiply_vector(self.rotation, scaled);
+        [
+            rotated[0] + self.origin[0],
+            rotated[1] + self.origin[1],
+            rotated[2] + self.origin[2],
+        ]
+    }
+
+    fn is_mirrored(&self) -> bool {
+        self.scale[0] * self.scale[1] * self.scale[2] < 0.0
+    }
+
+    fn brush(&self, brush: Brush) -> Brush {
+        Brush {
+            lines: brush
+                .lines
+                .into_iter()
+                .map(|line| match line {
+                    BrushLine::Face(face) => BrushLine::Face(self.face(face)),
+                    raw => raw,
+                })
+                .collect(),
+        }
+    }
+
+    fn face(&self, face: Face) -> Face {
+        let mut points = [
+            self.point(face.points[0]),
+            self.point(face.points[1]),
+            self.point(face.points[2]),
+        ];
+
+        // Mirroring flips the plane's winding, which would turn it to face inward
+        if self.is_mirrored() {
+            points.swap(1, 2);
+        }
+
+        // Standard alignment has no explicit axes, so textures don't follow the transform.
+        // Valve axes are carried along with it, keeping textures locked in place.
+        let alignment = match face.alignment {
+            Alignment::Valve { u, v, rest } => self.valve_alignment(u, v, &rest),
+            standard => standard,
+        };
+
+        Face {
+            points,
+            texture: face.texture,
+            alignment,
+        }
+    }
+
+    // Each axis is carried through the inverse scale and rotation, its length folded into the
+    // texture scale, and its offset corrected for the move
+    fn valve_alignment(&self, u: [f64; 4], v: [f64; 4], rest: &str) -> Alignment {
+        let mut tokens: Vec<String> = rest.split_whitespace().map(String::from).collect();
+        let mut axes = [u, v];
+
+        for (index, axis) in axes.iter_mut().enumerate() {
+            let direction = multiply_vector(
+                self.rotation,
+                [
+                    axis[0] / self.scale[0],
+                    axis[1] / self.scale[1],
+                    axis[2] / self.scale[2],
+                ],
+            );
+            let length = dot(direction, direction).sqrt();
+            if length <= std::f64::EPSILON {
+                continue;
+            }
+
+            let direction = [
+                direction[0] / length,
+                direction[1] / length,
+                direction[2] / length,
+            ];
+            axis[0] = direction[0];
+            axis[1] = direction[1];
+            axis[2] = direction[2];
+
+            // Tokens are rotation, then the u and v scales
+            let scale = match tokens.get(index + 1).and_then(|scale| parse_number(scale)) {
+                Some(scale) => scale / length,
+                None => continue,
+            };
+            tokens[index + 1] = format_number(scale);
+
+            if scale.abs() > std::f64::EPSILON {
+                axis[3] -= dot(self.origin, direction) / scale;
+            }
+        }
+
+        Alignment::Valve {
+            u: axes[0],
+            v: axes[1],
+            rest: tokens.join(" "),
+        }
+    }
+
+    fn entity(&self, mut entity: MapEntity) -> MapEntity {
+        if let Some(origin) = entity.get("origin").and_then(parse_vector) {
+            entity.set("origin", format_vector(self.point(origin)));
+        }
+
+        // Only yaw is carried over to entity angles, which is enough for rooms rotated in place
+        if self.yaw != 0.0 && !self.pitch_or_roll {
+            if let Some(mut angles) = entity.get("angles").and_then(parse_vector) {
+                angles[1] += self.yaw;
+                entity.set("angles", format_vector(angles));
+            } else {
+                match entity.get("angle").and_then(parse_number) {
+                    // Straight up and down are unaffected by yaw
+                    Some(angle) if angle == -1.0 || angle == -2.0 => (),
+                    Some(angle) => entity.set("angle", format_number(angle + self.yaw)),
+                    None if entity.brushes.is_empty() => {
+                        entity.set("angle", format_number(self.yaw))
+                    }
+                    None => (),
+                }
+            }
+        }
+
+        entity.brushes = entity
+            .brushes
+            .into_iter()
+            .map(|brush| self.brush(brush))
+            .collect();
+        entity
+    }
+}
+
+fn rotation_x(angle: f64) -> [Vector; 3] {
+    let (sin, cos) = angle.sin_cos();
+    [[1.0, 0.0, 0.0], [0.0, cos, -sin], [0.0, sin, cos]]
+}
+
+fn rotation_y(angle: f64) -> [Vector; 3] {
+    let (sin, cos) = angle.sin_cos();
+    [[cos, 0.0, sin], [0.0, 1.0, 0.0], [-sin, 0.0, cos]]
+}
+
+fn rotation_z(angle: f64) -> [Vector; 3] {
+    let (sin, cos) = angle.sin_cos();
+    [[cos, -sin, 0.0], [sin, cos, 0.0], [0.0, 0.0, 1.0]]
+}
+
+fn multiply_matrices(a: [Vector; 3], b: [Vector; 3]) -> [Vector; 3] {
+    let mut result = [[0.0; 3]; 3];
+    for (row, result_row) in result.iter_mut().enumerate() {
+        for (column, value) in result_row.iter_mut().enumerate() {
+            *value = (0..3).map(|i| a[row][i] * b[i][column]).sum();
+        }
+    }
+    result
+}
+
+fn multiply_vector(matrix: [Vector; 3], vector: Vector) -> Vector {
+    [
+        dot(matrix[0], vector),
+        dot(matrix[1], vector),
+        dot(matrix[2], vector),
+    ]
+}
+
+fn dot(a: Vector, b: Vector) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    text.trim().parse::<f64>().ok()
+}
+
+fn parse_vector(text: &str) -> Option<Vector> {
+    let components: Vec<f64> = text
+        .split_whitespace()
+        .map(parse_number)
+        .collect::<Option<Vec<f64>>>()?;
+
+    if components.len() == 3 {
+        Some([components[0], components[1], components[2]])
+    } else {
+        None
+    }
+}
+
+// Rounded, so rotations don't leave points a hair off the grid
+fn format_number(value: f64) -> String {
+    let value = (value * 1_000_000.0).round() / 1_000_000.0;
+    if value == 0.0 {
+        "0".into()
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_vector(vector: Vector) -> String {
+    format!(
+        "{} {} {}",
+        format_number(vector[0]),
+        format_number(vector[1]),
+        format_number(vector[2])
+    )
+}
+
+// Splits a map into its leading comments, which carry the format TrenchBroom saved it in,
+// and its entities
+fn parse_map(source: &str) -> Result<(Vec<String>, Vec<MapEntity>), String> {
+    let mut header: Vec<String> = Vec::new();
+    let mut entities: Vec<MapEntity> = Vec::new();
+    let mut entity = MapEntity::default();
+    let mut brush = Brush::default();
+    let mut depth = 0;
+
+    for (line_number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        let error = |message: &str| format!("Line {}: {}", line_number + 1, message);
+
+        if line.is_empty() {
+            continue;
+        }
+
+        // TrenchBroom's entity and brush markers are written afresh, so only keep the header
+        if line.starts_with("//") {
+            if entities.is_empty() && depth == 0 && !line.starts_with("// entity") {
+                header.push(line.to_string());
+            }
+            continue;
+        }
+
+        match depth {
+            0 if line == "{" => depth = 1,
+            0 => return Err(error("Expected an entity")),
+            1 if line == "{" => depth = 2,
+            1 if line == "}" => {
+                entities.push(std::mem::replace(&mut entity, MapEntity::default()));
+                depth = 0;
+            }
+            1 => entity
+                .properties
+                .push(parse_property(line).ok_or_else(|| error("Invalid property"))?),
+            2 if line == "}" => {
+                entity
+                    .brushes
+                    .push(std::mem::replace(&mut brush, Brush::default()));
+                depth = 1;
+            }
+            2 if line.starts_with('(') => brush.lines.push(BrushLine::Face(
+                parse_face(line).ok_or_else(|| error("Invalid brush face"))?,
+            )),
+            _ => {
+                if line == "{" {
+                    depth += 1;
+                } else if line == "}" {
+                    depth -= 1;
+                }
+                brush.lines.push(BrushLine::Raw(line.to_string()));
+            }
+        }
+    }
+
+    if depth != 0 {
+        return Err("Unexpected end of map".into());
+    }
+
+    Ok((header, entities))
+}
+
+// Values keep any \" escapes as written, so they're written back out unchanged
+fn parse_property(line: &str) -> Option<(String, String)> {
+    let (key, rest) = parse_quoted(line)?;
+    let (value, _) = parse_quoted(rest.trim_start())?;
+    Some((key.to_string(), value.to_string()))
+}
+
+// Splits a leading quoted string from the rest of the text, skipping over escaped quotes
+fn parse_quoted(text: &str) -> Option<(&str, &str)> {
+    if !text.starts_with('"') {
+        return None;
+    }
+    let text = &text[1..];
+
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' if !escaped => return Some((&text[..i], &text[i + 1..])),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn parse_face(line: &str) -> Option<Face> {
+    let padded = line
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .replace('[', " [ ")
+        .replace(']', " ] ");
+    let mut tokens = padded.split_whitespace();
+
+    let mut points = [[0.0; 3]; 3];
+    for point in points.iter_mut() {
+        if tokens.next()? != "(" {
+            return None;
+        }
+        for component in point.iter_mut() {
+            *component = parse_number(tokens.next()?)?;
+        }
+        if tokens.next()? != ")" {
+            return None;
+        }
+    }
+
+    let texture = tokens.next()?.to_string();
+    let rest: Vec<&str> = tokens.collect();
+
+    let alignment = if rest.first() == Some(&"[") {
+        let axis = |offset: usize| -> Option<[f64; 4]> {
+            if rest.get(offset) != Some(&"[") || rest.get(offset + 5) != Some(&"]") {
+                return None;
+            }
+            let mut axis = [0.0; 4];
+            for (i, component) in axis.iter_mut().enumerate() {
+                *component = parse_number(rest.get(offset + 1 + i)?)?;
+            }
+            Some(axis)
+        };
+
+        Alignment::Valve {
+            u: axis(0)?,
+            v: axis(6)?,
+            rest: rest[12..].join(" "),
+        }
+    } else {
+        Alignment::Standard(rest.join(" "))
+    };
+
+    Some(Face {
+        points,
+        texture,
+        alignment,
+    })
+}
+
+fn write_map(header: &[String], entities: &[MapEntity]) -> String {
+    let mut out = String::new();
+    for line in header {
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    for (entity_index, entity) in entities.iter().enumerate() {
+        out.push_str(&format!("// entity {}\n{{\n", entity_index));
+        for (key, value) in &entity.properties {
+            out.push_str(&format!("\"{}\" \"{}\"\n", key, value));
+        }
+
+        for (brush_index, brush) in entity.brushes.iter().enumerate() {
+            out.push_str(&format!("// brush {}\n{{\n", brush_index));
+            for line in &brush.lines {
+                match line {
+                    BrushLine::Face(face) => write_face(&mut out, face),
+                    BrushLine::Raw(raw) => out.push_str(raw),
+                }
+                out.push('\n');
+            }
+            out.push_str("}\n");
+        }
+
+        out.push_str("}\n");
+    }
+
+    out
+}
+
+fn write_face(out: &mut String, face: &Face) {
+    for point in &face.points {
+        out.push_str(&format!("( {} ) ", format_vector(*point)));
+    }
+    out.push_str(&face.texture);
+
+    match &face.alignment {
+        Alignment::Standard(rest) => {
+            out.push(' ');
+            out.push_str(rest);
+        }
+        Alignment::Valve { u, v, rest } => {
+            for axis in &[u, v] {
+                out.push_str(&format!(
+                    " [ {} {} {} {} ]",
+                    format_number(axis[0]),
+                    format_number(axis[1]),
+                    format_number(axis[2]),
+                    format_number(axis[3])
+                ));
+            }
+            out.push(' ');
+            out.push_str(rest);
+        }
+    }
+}
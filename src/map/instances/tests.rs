@@ -0,0 +1,478 @@
+use std::path::{Path, PathBuf};
+
+use super::{
+    expand_instances, fix_up_names, parse_map, parse_property, replacements, write_map, Alignment,
+    BrushLine, Face, MapEntity, Transform, Vector,
+};
+
+const VALVE_MAP: &str = r#"// Game: Quake
+// Format: Valve
+// entity 0
+{
+"classname" "worldspawn"
+"message" "say \"hello\""
+// brush 0
+{
+( 0 0 0 ) ( 0 1 0 ) ( 1 0 0 ) base [ 1 0 0 8 ] [ 0 -1 0 4 ] 15 0.5 2
+( 0 0 64 ) ( 1 0 64 ) ( 0 1 64 ) base [ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1
+}
+}
+// entity 1
+{
+"classname" "light"
+"origin" "32 16 8"
+}
+"#;
+
+const VALVE_FACE: &str = "( 0 0 0 ) ( 0 1 0 ) ( 1 0 0 ) base [ 1 0 0 8 ] [ 0 -1 0 4 ] 15 0.5 2";
+const STANDARD_FACE: &str = "( 0 0 0 ) ( 0 1 0 ) ( 1 0 0 ) base 8 4 15 0.5 2";
+
+fn entity(properties: &[(&str, &str)]) -> MapEntity {
+    MapEntity {
+        properties: properties
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        brushes: Vec::new(),
+    }
+}
+
+fn faces(entity: &MapEntity) -> Vec<&Face> {
+    entity
+        .brushes
+        .iter()
+        .flat_map(|brush| brush.lines.iter())
+        .filter_map(|line| match line {
+            BrushLine::Face(face) => Some(face),
+            BrushLine::Raw(_) => None,
+        })
+        .collect()
+}
+
+fn face(line: &str) -> Face {
+    let (_, mut entities) = parse_map(&format!(
+        "{{\n\"classname\" \"worldspawn\"\n{{\n{}\n}}\n}}\n",
+        line
+    ))
+    .unwrap();
+    match entities.remove(0).brushes.remove(0).lines.remove(0) {
+        BrushLine::Face(face) => face,
+        BrushLine::Raw(raw) => panic!("Expected a face, got {}", raw),
+    }
+}
+
+fn assert_vector_eq(actual: Vector, expected: Vector) {
+    for (actual_component, expected_component) in actual.iter().zip(expected.iter()) {
+        assert!(
+            (actual_component - expected_component).abs() < 0.0001,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 0.0001,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+// A point's texture coordinates under Valve 220 alignment
+fn texture_coordinates(alignment: &Alignment, point: Vector) -> (f64, f64) {
+    match alignment {
+        Alignment::Valve { u, v, rest } => {
+            let tokens: Vec<f64> = rest
+                .split_whitespace()
+                .map(|token| token.parse().unwrap())
+                .collect();
+            let project = |axis: &[f64; 4], scale: f64| {
+                (point[0] * axis[0] + point[1] * axis[1] + point[2] * axis[2]) / scale + axis[3]
+            };
+            (project(u, tokens[1]), project(v, tokens[2]))
+        }
+        Alignment::Standard(_) => panic!("Expected Valve alignment"),
+    }
+}
+
+// A scratch directory of map files, removed when dropped
+struct MapDirectory(PathBuf);
+
+impl MapDirectory {
+    fn new(name: &str, files: &[(&str, &str)]) -> MapDirectory {
+        let directory =
+            std::env::temp_dir().join(format!("qodot_instances_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for (file, source) in files {
+            std::fs::write(directory.join(file), source).unwrap();
+        }
+        MapDirectory(directory)
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+
+    fn expand(&self, file: &str) -> Result<Vec<MapEntity>, String> {
+        let path = self.path(file);
+        let expanded = expand_instances(&path, &path)?.expect("Map has no instances");
+        Ok(parse_map(&expanded.source).unwrap().1)
+    }
+}
+
+impl Drop for MapDirectory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn instance_map(instance_properties: &str) -> String {
+    format!(
+        "{{\n\"classname\" \"worldspawn\"\n}}\n{{\n\"classname\" \"func_instance\"\n{}\n}}\n",
+        instance_properties
+    )
+}
+
+#[test]
+fn map_round_trips() {
+    let (header, entities) = parse_map(VALVE_MAP).unwrap();
+    let written = write_map(&header, &entities);
+    let (reparsed_header, reparsed) = parse_map(&written).unwrap();
+
+    assert_eq!(reparsed_header, header);
+    assert_eq!(header, vec!["// Game: Quake", "// Format: Valve"]);
+    assert_eq!(reparsed.len(), entities.len());
+
+    for (reparsed, entity) in reparsed.iter().zip(entities.iter()) {
+        assert_eq!(reparsed.properties, entity.properties);
+        assert_eq!(reparsed.brushes.len(), entity.brushes.len());
+
+        for (reparsed, face) in faces(reparsed).into_iter().zip(faces(entity)) {
+            assert_eq!(reparsed.points, face.points);
+            assert_eq!(reparsed.texture, face.texture);
+            match (&reparsed.alignment, &face.alignment) {
+                (
+                    Alignment::Valve { u, v, rest },
+                    Alignment::Valve {
+                        u: face_u,
+                        v: face_v,
+                        rest: face_rest,
+                    },
+                ) => {
+                    assert_eq!(u, face_u);
+                    assert_eq!(v, face_v);
+                    assert_eq!(rest, face_rest);
+                }
+                _ => panic!("Expected Valve alignment"),
+            }
+        }
+    }
+
+    // Writing is stable once parsed
+    assert_eq!(write_map(&reparsed_header, &reparsed), written);
+}
+
+#[test]
+fn escaped_quotes_are_kept() {
+    assert_eq!(
+        parse_property(r#""message" "say \"hello\"""#),
+        Some(("message".to_string(), r#"say \"hello\""#.to_string()))
+    );
+    assert_eq!(
+        parse_property(r#""path" "C:\\maps\\""#),
+        Some(("path".to_string(), r#"C:\\maps\\"#.to_string()))
+    );
+    assert_eq!(parse_property(r#""message" "unterminated"#), None);
+    assert_eq!(parse_property(r#""message"value"#), None);
+
+    let (_, entities) = parse_map(VALVE_MAP).unwrap();
+    assert_eq!(entities[0].get("message"), Some(r#"say \"hello\""#));
+    assert!(write_map(&[], &entities).contains(r#""message" "say \"hello\"""#));
+}
+
+#[test]
+fn transform_scales_rotates_then_moves() {
+    let transform = Transform::new(Some([10.0, 0.0, 0.0]), Some([0.0, 90.0, 0.0]), None);
+    assert_vector_eq(transform.point([1.0, 0.0, 0.0]), [10.0, 1.0, 0.0]);
+
+    let transform = Transform::new(None, None, Some([2.0, 2.0, 2.0]));
+    assert_vector_eq(transform.point([1.0, 2.0, 3.0]), [2.0, 4.0, 6.0]);
+
+    // Positive pitch turns +X down, as it does in Quake
+    let transform = Transform::new(None, Some([90.0, 0.0, 0.0]), None);
+    assert_vector_eq(transform.point([1.0, 0.0, 0.0]), [0.0, 0.0, -1.0]);
+}
+
+#[test]
+fn mirrored_transform_keeps_faces_outward() {
+    let transform = Transform::new(None, None, Some([-1.0, 1.0, 1.0]));
+    let face = transform.face(face(STANDARD_FACE));
+
+    assert_vector_eq(face.points[0], [0.0, 0.0, 0.0]);
+    assert_vector_eq(face.points[1], [-1.0, 0.0, 0.0]);
+    assert_vector_eq(face.points[2], [0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn standard_alignment_is_unchanged() {
+    let transform = Transform::new(Some([16.0, 0.0, 0.0]), Some([0.0, 90.0, 0.0]), None);
+    match transform.face(face(STANDARD_FACE)).alignment {
+        Alignment::Standard(rest) => assert_eq!(rest, "8 4 15 0.5 2"),
+        Alignment::Valve { .. } => panic!("Expected standard alignment"),
+    }
+}
+
+#[test]
+fn valve_texture_lock_follows_transform() {
+    let transforms = [
+        Transform::new(Some([16.0, -8.0, 4.0]), None, None),
+        Transform::new(Some([16.0, -8.0, 4.0]), Some([0.0, 90.0, 0.0]), None),
+        Transform::new(
+            Some([3.0, 5.0, 7.0]),
+            Some([0.0, 30.0, 0.0]),
+            Some([2.0, 2.0, 1.0]),
+        ),
+        Transform::new(None, Some([15.0, 45.0, 10.0]), Some([-1.0, 1.0, 3.0])),
+    ];
+    let points = [[0.0, 0.0, 0.0], [32.0, 16.0, 0.0], [-8.0, 64.0, 12.0]];
+
+    for transform in &transforms {
+        let original = face(VALVE_FACE);
+        let transformed = transform.face(face(VALVE_FACE));
+
+        for point in &points {
+            let (u, v) = texture_coordinates(&original.alignment, *point);
+            let (locked_u, locked_v) =
+                texture_coordinates(&transformed.alignment, transform.point(*point));
+            assert_close(locked_u, u);
+            assert_close(locked_v, v);
+        }
+
+        // Rotation stays as it was
+        match &transformed.alignment {
+            Alignment::Valve { rest, .. } => assert!(rest.starts_with("15 ")),
+            Alignment::Standard(_) => panic!("Expected Valve alignment"),
+        }
+    }
+}
+
+#[test]
+fn entity_yaw_follows_transform() {
+    let transform = Transform::new(Some([0.0, 0.0, 0.0]), Some([0.0, 90.0, 0.0]), None);
+
+    let rotated = transform.entity(entity(&[("classname", "light"), ("angle", "45")]));
+    assert_eq!(rotated.get("angle"), Some("135"));
+
+    let straight_up = transform.entity(entity(&[("classname", "light"), ("angle", "-1")]));
+    assert_eq!(straight_up.get("angle"), Some("-1"));
+
+    let unset = transform.entity(entity(&[("classname", "light")]));
+    assert_eq!(unset.get("angle"), Some("90"));
+
+    let angles = transform.entity(entity(&[("classname", "light"), ("angles", "10 20 0")]));
+    assert_eq!(angles.get("angles"), Some("10 110 0"));
+}
+
+#[test]
+fn fix_up_prefixes_names() {
+    let mut instanced = entity(&[
+        ("classname", "trigger_once"),
+        ("targetname", "button"),
+        ("target", "door"),
+        ("killtarget", "@global"),
+        ("parentname", "!player"),
+        ("message", "door"),
+    ]);
+    fix_up_names(&mut instanced, "room", 0);
+
+    assert_eq!(instanced.get("targetname"), Some("room-button"));
+    assert_eq!(instanced.get("target"), Some("room-door"));
+    assert_eq!(instanced.get("killtarget"), Some("@global"));
+    assert_eq!(instanced.get("parentname"), Some("!player"));
+    assert_eq!(instanced.get("message"), Some("door"));
+}
+
+#[test]
+fn fix_up_postfixes_names() {
+    let mut instanced = entity(&[("targetname", "button"), ("target", "")]);
+    fix_up_names(&mut instanced, "room", 1);
+
+    assert_eq!(instanced.get("targetname"), Some("button-room"));
+    assert_eq!(instanced.get("target"), Some(""));
+}
+
+#[test]
+fn fix_up_none_leaves_names() {
+    let mut instanced = entity(&[("targetname", "button"), ("target", "door")]);
+    fix_up_names(&mut instanced, "room", 2);
+
+    assert_eq!(instanced.get("targetname"), Some("button"));
+    assert_eq!(instanced.get("target"), Some("door"));
+}
+
+#[test]
+fn replacements_are_longest_first() {
+    let instance = entity(&[
+        ("classname", "func_instance"),
+        ("replace01", "$a short"),
+        ("replace02", "$abc longest"),
+        ("replace03", "$ab  middle value "),
+        ("replace04", "nodollar ignored"),
+        ("replace05", "$empty"),
+    ]);
+
+    assert_eq!(
+        replacements(&instance),
+        vec![
+            ("$abc".to_string(), "longest".to_string()),
+            ("$ab".to_string(), "middle value".to_string()),
+            ("$a".to_string(), "short".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn replacements_apply_to_instanced_entities() {
+    let maps = MapDirectory::new(
+        "replacements",
+        &[
+            (
+                "host.map",
+                instance_map(
+                    "\"file\" \"room.map\"\n\"replace01\" \"$a short\"\n\
+                     \"replace02\" \"$ab long\"",
+                )
+                .as_str(),
+            ),
+            (
+                "room.map",
+                "{\n\"classname\" \"worldspawn\"\n}\n\
+                 {\n\"classname\" \"light\"\n\"message\" \"$ab $a\"\n}\n",
+            ),
+        ],
+    );
+
+    let entities = maps.expand("host.map").unwrap();
+    let light = entities
+        .iter()
+        .find(|entity| entity.classname() == "light")
+        .unwrap();
+    assert_eq!(light.get("message"), Some("long short"));
+}
+
+#[test]
+fn instance_cycles_are_errors() {
+    let maps = MapDirectory::new(
+        "cycle",
+        &[
+            ("a.map", instance_map("\"file\" \"b.map\"").as_str()),
+            ("b.map", instance_map("\"file\" \"a.map\"").as_str()),
+        ],
+    );
+
+    let err = match maps.expand("a.map") {
+        Ok(_) => panic!("Expected an instance cycle"),
+        Err(err) => err,
+    };
+    assert!(err.starts_with("Instance cycle: "), "{}", err);
+
+    let chain: Vec<&str> = err["Instance cycle: ".len()..].split(" -> ").collect();
+    assert_eq!(chain.len(), 3);
+    assert!(chain[0].ends_with("a.map"));
+    assert!(chain[1].ends_with("b.map"));
+    assert!(chain[2].ends_with("a.map"));
+}
+
+#[test]
+fn relative_instances_resolve_from_base_path() {
+    let maps = MapDirectory::new(
+        "base_path",
+        &[
+            (
+                "room.map",
+                "{\n\"classname\" \"worldspawn\"\n}\n{\n\"classname\" \"light\"\n}\n",
+            ),
+            ("host.map", instance_map("\"file\" \"room.map\"").as_str()),
+        ],
+    );
+
+    // An embedded map is built from a generated copy somewhere else entirely
+    let generated = MapDirectory::new(
+        "base_path_generated",
+        &[("0123.map", instance_map("\"file\" \"room.map\"").as_str())],
+    );
+
+    let expanded = expand_instances(&generated.path("0123.map"), &maps.path("host.map"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        expanded.instance_paths,
+        vec![maps.path("room.map").canonicalize().unwrap()]
+    );
+    assert!(expand_instances(&generated.path("0123.map"), Path::new("missing.map")).is_err());
+}
+
+#[test]
+fn instanced_entities_join_the_instance_layer() {
+    let maps = MapDirectory::new(
+        "layers",
+        &[
+            (
+                "host.map",
+                instance_map("\"file\" \"room.map\"\n\"_tb_layer\" \"7\"").as_str(),
+            ),
+            (
+                "room.map",
+                "{\n\"classname\" \"worldspawn\"\n}\n\
+                 {\n\"classname\" \"light\"\n\"_tb_layer\" \"1\"\n\"_tb_group\" \"2\"\n}\n",
+            ),
+        ],
+    );
+
+    let entities = maps.expand("host.map").unwrap();
+    let light = entities
+        .iter()
+        .find(|entity| entity.classname() == "light")
+        .unwrap();
+    assert_eq!(light.get("_tb_layer"), Some("7"));
+    assert_eq!(light.get("_tb_group"), None);
+}
+
+#[test]
+fn unnamed_instances_get_distinct_names() {
+    let maps = MapDirectory::new(
+        "auto_names",
+        &[
+            (
+                "host.map",
+                "{\n\"classname\" \"worldspawn\"\n}\n\
+                 {\n\"classname\" \"func_instance\"\n\"file\" \"room.map\"\n}\n\
+                 {\n\"classname\" \"func_instance\"\n\"file\" \"room.map\"\n\
+                 \"fixup_style\" \"1\"\n}\n",
+            ),
+            (
+                "room.map",
+                "{\n\"classname\" \"worldspawn\"\n}\n\
+                 {\n\"classname\" \"func_button\"\n\"targetname\" \"button\"\n\
+                 \"target\" \"door\"\n}\n",
+            ),
+        ],
+    );
+
+    let entities = maps.expand("host.map").unwrap();
+    let buttons: Vec<(Option<&str>, Option<&str>)> = entities
+        .iter()
+        .filter(|entity| entity.classname() == "func_button")
+        .map(|entity| (entity.get("targetname"), entity.get("target")))
+        .collect();
+    assert_eq!(
+        buttons,
+        vec![
+            (Some("InstanceAuto1-button"), Some("InstanceAuto1-door")),
+            (Some("button-InstanceAuto2"), Some("door-InstanceAuto2")),
+        ]
+    );
+}
@@ -1,19 +1,31 @@
 #![allow(clippy::transmute_ptr_to_ptr)] // Silence gdnative clippy warnings
 
+pub mod instances;
+mod parse;
+
 use gdnative::{
-    godot_error, godot_wrap_method_inner,
-    godot_wrap_method_parameter_count, init::ClassBuilder, NativeClass, Resource,
+    godot_error, godot_print, godot_wrap_method_inner, godot_wrap_method_parameter_count,
+    init::ClassBuilder, GodotString, NativeClass, Resource,
 };
 
+// Where embedded map sources are written when there's no backing file to build from
+const EMBEDDED_MAP_DIRECTORY: &str = "user://qodot/maps";
+
 #[derive(Debug, NativeClass)]
 #[user_data[gdnative::user_data::RwLockData<QuakeMap>]]
 #[register_with(register_quake_map)]
 #[inherit(Resource)]
 pub struct QuakeMap {
     revision: i32,
+    source_file: GodotString,
+    source: String,
+    hash: u64,
+    stats: parse::MapStats,
 }
 
 fn register_quake_map(builder: &ClassBuilder<QuakeMap>) {
+    // Registration order is load order: the stored source has to be in place before
+    // the source file is checked against it
     builder
         .add_property::<i32>("revision")
         .with_default(0)
@@ -21,6 +33,24 @@ fn register_quake_map(builder: &ClassBuilder<QuakeMap>) {
         .with_setter(QuakeMap::set_revision)
         .with_usage(gdnative::init::property::Usage::NOEDITOR)
         .done();
+
+    builder
+        .add_property::<GodotString>("source")
+        .with_default(GodotString::new())
+        .with_getter(QuakeMap::get_source)
+        .with_setter(QuakeMap::set_source)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<GodotString>("source_file")
+        .with_default(GodotString::new())
+        .with_ref_getter(QuakeMap::get_source_file)
+        .with_setter(QuakeMap::set_source_file)
+        .with_hint(gdnative::init::property::StringHint::File(
+            gdnative::init::property::EnumHint::new(vec!["*.map".into()]),
+        ))
+        .done();
 }
 
 #[gdnative::methods]
@@ -31,8 +61,18 @@ impl QuakeMap {
         }
 
         let revision = 0;
+        let source_file = GodotString::new();
+        let source = String::new();
+        let hash = parse::hash(&source);
+        let stats = parse::MapStats::default();
 
-        QuakeMap { revision }
+        QuakeMap {
+            revision,
+            source_file,
+            source,
+            hash,
+            stats,
+        }
     }
 
     #[export]
@@ -54,4 +94,164 @@ impl QuakeMap {
     pub fn increment_revision(&mut self, owner: Resource) {
         self.set_revision(owner, self.revision + 1);
     }
+
+    pub fn get_source_file(&self, _owner: Resource) -> &GodotString {
+        &self.source_file
+    }
+
+    pub fn set_source_file(&mut self, owner: Resource, new_source_file: GodotString) {
+        if self.source_file != new_source_file {
+            self.source_file = new_source_file;
+            self.reload(owner);
+        }
+    }
+
+    #[export]
+    pub fn get_source(&self, _owner: Resource) -> GodotString {
+        self.source.as_str().into()
+    }
+
+    // Stored without bumping the revision, since this is also how saved maps are loaded
+    pub fn set_source(&mut self, _owner: Resource, new_source: GodotString) {
+        self.update_source(new_source.to_string());
+    }
+
+    // Hex-encoded, as Godot integers can't hold the full hash
+    #[export]
+    pub fn get_hash(&self, _owner: Resource) -> GodotString {
+        format!("{:016x}", self.hash).into()
+    }
+
+    #[export]
+    pub fn get_entity_count(&self, _owner: Resource) -> i64 {
+        self.stats.entity_count
+    }
+
+    #[export]
+    pub fn get_brush_count(&self, _owner: Resource) -> i64 {
+        self.stats.brush_count
+    }
+
+    // Re-reads the backing file, incrementing the revision if its contents changed
+    #[export]
+    pub fn reload(&mut self, owner: Resource) -> bool {
+        let path = self.backing_file(owner);
+        if path.is_empty() {
+            return false;
+        }
+
+        let path = gdnative::ProjectSettings::godot_singleton()
+            .globalize_path(path)
+            .to_string();
+
+        let source = match std::fs::read(&path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(err) => {
+                // Exported games keep the embedded source, so a missing file isn't an error
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    godot_error!("Failed to read map file {}: {}", path, err);
+                }
+                return false;
+            }
+        };
+
+        if parse::hash(&source) == self.hash {
+            return false;
+        }
+
+        godot_print!("Reloaded map file {}", path);
+        self.update_source(source);
+        self.increment_revision(owner);
+        true
+    }
+
+    // The file this map's source is read from, if any
+    pub fn backing_file(&self, owner: Resource) -> GodotString {
+        if !self.source_file.is_empty() {
+            return self.source_file.clone();
+        }
+
+        // Maps imported directly from .map files are backed by their own resource path
+        let path = owner.get_path();
+        if path.to_string().ends_with(".map") {
+            path
+        } else {
+            GodotString::new()
+        }
+    }
+
+    // Where relative instance paths are resolved from. Embedded maps are built from a generated
+    // copy, so they use the file the resource is saved in.
+    pub fn instance_base_path(&self, owner: Resource) -> GodotString {
+        let backing_file = self.backing_file(owner);
+        if !backing_file.is_empty() {
+            return backing_file;
+        }
+
+        // Built-in resources are addressed as scene.tscn::id
+        let path = owner.get_path().to_string();
+        path.split("::").next().unwrap_or_default().into()
+    }
+
+    // Returns a path quarchitect can build from, writing out the embedded source if the
+    // backing file isn't available. Reloading is left to the file watcher, so a build never
+    // bumps the revision and schedules another.
+    pub fn build_path(&self, owner: Resource) -> Result<GodotString, String> {
+        let project_settings = gdnative::ProjectSettings::godot_singleton();
+
+        let backing_file = self.backing_file(owner);
+        if !backing_file.is_empty() {
+            let path = project_settings
+                .globalize_path(backing_file.clone())
+                .to_string();
+            if std::path::Path::new(&path).is_file() {
+                return Ok(backing_file);
+            }
+        }
+
+        if self.source.is_empty() {
+            return Err("Map has no source file or embedded source".into());
+        }
+
+        // Named after the resource, so each one only ever has a single generated copy
+        let resource_path = owner.get_path().to_string();
+        let name = if resource_path.is_empty() {
+            format!("unsaved_{}", owner.get_instance_id())
+        } else {
+            format!("resource_{:016x}", parse::hash(&resource_path))
+        };
+
+        let path = write_map_source(&name, &self.source)?;
+        Ok(path.to_string_lossy().as_ref().into())
+    }
+
+    fn update_source(&mut self, source: String) {
+        self.hash = parse::hash(&source);
+        self.stats = parse::stats(&source);
+        self.source = source;
+    }
+}
+
+// Writes map source somewhere quarchitect can build it from. Each caller passes a name of its
+// own, so new source replaces the old rather than piling up, and an unchanged map isn't
+// rewritten.
+pub fn write_map_source(name: &str, source: &str) -> Result<std::path::PathBuf, String> {
+    let directory = gdnative::ProjectSettings::godot_singleton()
+        .globalize_path(EMBEDDED_MAP_DIRECTORY.into())
+        .to_string();
+    std::fs::create_dir_all(&directory)
+        .map_err(|err| format!("Failed to create {}: {}", directory, err))?;
+
+    let path = std::path::Path::new(&directory).join(format!("{}.map", name));
+    let unchanged = match std::fs::read_to_string(&path) {
+        Ok(existing) => existing == source,
+        Err(_) => false,
+    };
+
+    if !unchanged {
+        std::fs::write(&path, source)
+            .map_err(|err| format!("Failed to write {:?}: {}", path, err))?;
+    }
+
+    Ok(path)
 }
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests;
+
+// Summary of a map's contents, gathered without building any geometry
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MapStats {
+    pub entity_count: i64,
+    pub brush_count: i64,
+}
+
+// Counts entities and brushes by tracking brace depth. Quoted values and
+// comments may contain braces, so both are skipped. Patches nest a further
+// level inside their brush, so only the opening brace at depth one counts.
+pub fn stats(source: &str) -> MapStats {
+    let mut stats = MapStats::default();
+    let mut depth = 0;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                for c in chars.by_ref() {
+                    if c == '"' || c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '{' => {
+                match depth {
+                    0 => stats.entity_count += 1,
+                    1 => stats.brush_count += 1,
+                    _ => (),
+                }
+                depth += 1;
+            }
+            '}' => depth = (depth - 1).max(0),
+            _ => (),
+        }
+    }
+
+    stats
+}
+
+// FNV-1a, chosen over std's hasher because its output is stable across
+// Rust versions and can safely be persisted alongside cached builds
+pub fn hash(source: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in source.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
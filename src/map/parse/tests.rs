@@ -0,0 +1,51 @@
+use super::{hash, stats, MapStats};
+
+fn map_stats(entity_count: i64, brush_count: i64) -> MapStats {
+    MapStats {
+        entity_count,
+        brush_count,
+    }
+}
+
+const BRUSH: &str = "{\n( 0 0 0 ) ( 0 1 0 ) ( 1 0 0 ) base 0 0 0 1 1\n}\n";
+
+#[test]
+fn counts_entities_and_brushes() {
+    let source = format!(
+        "{{\n\"classname\" \"worldspawn\"\n{}{}}}\n{{\n\"classname\" \"func_door\"\n{}}}\n\
+         {{\n\"classname\" \"light\"\n}}\n",
+        BRUSH, BRUSH, BRUSH
+    );
+
+    assert_eq!(stats(&source), map_stats(3, 3));
+    assert_eq!(stats(""), map_stats(0, 0));
+}
+
+#[test]
+fn braces_in_values_and_comments_are_skipped() {
+    let source = "// { a comment with a brace\n\
+                  {\n\"classname\" \"worldspawn\"\n\"message\" \"{ not a brush }\"\n}\n";
+
+    assert_eq!(stats(source), map_stats(1, 0));
+}
+
+#[test]
+fn patches_count_as_one_brush() {
+    let source = "{\n\"classname\" \"worldspawn\"\n{\npatchDef2\n{\nbase\n( 3 3 0 0 0 )\n(\n\
+                  ( ( 0 0 0 0 0 ) )\n)\n}\n}\n}\n";
+
+    assert_eq!(stats(source), map_stats(1, 1));
+}
+
+#[test]
+fn unbalanced_braces_do_not_go_negative() {
+    assert_eq!(stats("}\n}\n{\n}\n"), map_stats(1, 0));
+}
+
+#[test]
+fn hash_is_stable_fnv1a() {
+    // Persisted alongside cached builds, so these must never change
+    assert_eq!(hash(""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(hash("a"), 0xaf63_dc4c_8601_ec8c);
+    assert_ne!(hash("{\n}\n"), hash("{\n}"));
+}
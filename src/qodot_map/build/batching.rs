@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use super::visual_geometry::MeshSurface;
+use quarchitect::Vector3;
+
+pub type BatchCell = (i32, i32, i32);
+
+// Merges surfaces that share a texture into spatially partitioned chunks
+pub struct MeshBatcher {
+    cell_size: f32,
+    batches: HashMap<BatchCell, HashMap<Option<String>, MeshSurface>>,
+}
+
+impl MeshBatcher {
+    pub fn new(cell_size: f32) -> MeshBatcher {
+        let cell_size = if cell_size > 0.0 { cell_size } else { 1024.0 };
+        let batches = HashMap::new();
+
+        MeshBatcher { cell_size, batches }
+    }
+
+    pub fn add_surfaces(&mut self, surfaces: Vec<MeshSurface>) {
+        for surface in surfaces {
+            self.add_surface(&surface);
+        }
+    }
+
+    fn add_surface(&mut self, surface: &MeshSurface) {
+        // Source index -> batch index, per destination cell
+        let mut remaps: HashMap<BatchCell, HashMap<usize, usize>> = HashMap::new();
+
+        for triangle in surface.indices.chunks(3) {
+            if triangle.len() < 3 {
+                continue;
+            }
+
+            let centroid = (surface.vertices[triangle[0]]
+                + surface.vertices[triangle[1]]
+                + surface.vertices[triangle[2]])
+                / 3.0;
+            let cell = self.cell(centroid);
+
+            let batch = self
+                .batches
+                .entry(cell)
+                .or_insert_with(HashMap::new)
+                .entry(surface.texture.clone())
+                .or_insert_with(|| {
+                    MeshSurface::empty(
+                        surface.texture.clone(),
+                        surface.colors.is_some(),
+                        surface.uvs.is_some(),
+                    )
+                });
+
+            let remap = remaps.entry(cell).or_insert_with(HashMap::new);
+
+            for index in triangle {
+                let batch_index = match remap.get(index) {
+                    Some(batch_index) => *batch_index,
+                    None => {
+                        let batch_index = push_vertex(batch, surface, *index);
+                        remap.insert(*index, batch_index);
+                        batch_index
+                    }
+                };
+                batch.indices.push(batch_index);
+            }
+        }
+    }
+
+    fn cell(&self, point: Vector3) -> BatchCell {
+        (
+            (point.x() / self.cell_size).floor() as i32,
+            (point.y() / self.cell_size).floor() as i32,
+            (point.z() / self.cell_size).floor() as i32,
+        )
+    }
+
+    // Consumes the batcher, returning its chunks in a stable order
+    pub fn into_batches(self) -> Vec<(BatchCell, Vec<MeshSurface>)> {
+        let mut batches: Vec<(BatchCell, Vec<MeshSurface>)> = self
+            .batches
+            .into_iter()
+            .map(|(cell, surfaces)| {
+                let mut surfaces: Vec<MeshSurface> = surfaces.into_iter().map(|(_, v)| v).collect();
+                surfaces.sort_by(|a, b| a.texture.cmp(&b.texture));
+                (cell, surfaces)
+            })
+            .collect();
+
+        batches.sort_by(|a, b| a.0.cmp(&b.0));
+        batches
+    }
+}
+
+pub fn is_batched(actor: &quarchitect::scene_tree::Actor, batch_brush_entities: bool) -> bool {
+    if super::scene_tree::actor_classname(actor) == "worldspawn" {
+        return true;
+    }
+
+    // Brush entities without a component script are considered static
+    batch_brush_entities && actor.component_class.is_none()
+}
+
+fn push_vertex(batch: &mut MeshSurface, surface: &MeshSurface, index: usize) -> usize {
+    batch.vertices.push(surface.vertices[index]);
+    batch.normals.push(surface.normals[index]);
+    batch.tangents.push(surface.tangents[index]);
+
+    if let Some(colors) = &mut batch.colors {
+        colors.push(match &surface.colors {
+            Some(surface_colors) => surface_colors[index],
+            None => quarchitect::Color::new(1.0, 1.0, 1.0),
+        });
+    }
+
+    if let Some(uvs) = &mut batch.uvs {
+        uvs.push(match &surface.uvs {
+            Some(surface_uvs) => surface_uvs[index],
+            None => (0.0, 0.0),
+        });
+    }
+
+    batch.vertices.len() - 1
+}
@@ -1,62 +1,249 @@
 use gdnative::{
-    CollisionShape, ConcavePolygonShape, ConvexPolygonShape, Node, Shape, Spatial, Vector3Array,
+    CollisionShape, ConcavePolygonShape, ConvexPolygonShape, Node, Resource, Shape, Spatial,
+    StaticBody, Vector3Array,
 };
 use quarchitect::Vector3;
+use std::collections::HashMap;
+
+use super::coordinates::CoordinateSystem;
+use super::resource_cache::ResourceCache;
+
+// Limits on how collision is divided up, to keep individual shapes and bodies small
+pub struct CollisionChunking {
+    // Edge length of the grid concave collision is split by, in Quake units. Zero disables.
+    pub cell_size: f32,
+    // Convex hulls per body before they're grouped under extra StaticBody nodes. Zero disables.
+    // Only static entities are split, as extra bodies would detach shapes from moving ones.
+    pub max_convex_shapes_per_body: usize,
+}
+
+type CollisionCell = (i32, i32, i32);
 
 pub fn spawn_collision_geometry(
-    inverse_scale_factor: f32,
+    coordinates: &CoordinateSystem,
     owner: Spatial,
     parent: &Option<Node>,
     collision_geometry: &quarchitect::scene_tree::CollisionGeometry,
     origin: Vector3,
+    resource_cache: Option<&ResourceCache>,
+    entity_index: usize,
+    chunking: &CollisionChunking,
+    warnings: &mut Vec<String>,
 ) {
-    let origin = super::quake_point_to_godot_point(origin, inverse_scale_factor);
+    let origin = coordinates.point(origin);
 
     match collision_geometry {
         quarchitect::scene_tree::CollisionGeometry::Convex(convex_collision) => {
-            for convex_collision in convex_collision {
-                let center = super::quake_point_to_godot_point(
-                    convex_collision.center,
-                    inverse_scale_factor,
-                );
+            let over_limit = chunking.max_convex_shapes_per_body > 0
+                && convex_collision.len() > chunking.max_convex_shapes_per_body;
+
+            let splittable = over_limit && can_hold_collision_bodies(owner, parent);
+            if over_limit && !splittable {
+                let parent_name = unsafe {
+                    match parent {
+                        Some(parent) => parent.get_name().to_string(),
+                        None => owner.get_name().to_string(),
+                    }
+                };
+                warnings.push(format!(
+                    "{}: {} convex shapes exceed the limit of {} per body, but only static \
+                     entities are split into extra bodies",
+                    parent_name,
+                    convex_collision.len(),
+                    chunking.max_convex_shapes_per_body
+                ));
+            }
+
+            let groups = if splittable {
+                let centers: Vec<Vector3> = convex_collision
+                    .iter()
+                    .map(|convex_collision| convex_collision.center)
+                    .collect();
+                let indices: Vec<usize> = (0..centers.len()).collect();
+                group_spatially(&centers, indices, chunking.max_convex_shapes_per_body)
+            } else {
+                vec![(0..convex_collision.len()).collect()]
+            };
+
+            let grouped = groups.len() > 1;
+            for (group_index, group) in groups.into_iter().enumerate() {
+                // Extra bodies are positioned at the origin of the entity, like its own shapes
+                let group_parent = if grouped {
+                    spawn_collision_body(owner, parent, group_index)
+                } else {
+                    *parent
+                };
+
+                for brush_index in group {
+                    let convex_collision = &convex_collision[brush_index];
+                    let center = coordinates.point(convex_collision.center);
+
+                    let mut vertices = Vector3Array::new();
+                    for vertex in &convex_collision.points {
+                        let vertex = coordinates.point(*vertex);
+                        vertices.push(&super::godot_vector3_from_quarchitect_vector3(
+                            vertex - center,
+                        ));
+                    }
+                    let mut shape = ConvexPolygonShape::new();
+                    shape.set_points(vertices);
+
+                    if let Some(resource_cache) = resource_cache {
+                        resource_cache.save(
+                            shape.cast::<Resource>(),
+                            &ResourceCache::convex_shape_name(entity_index, brush_index),
+                        );
+                    }
 
-                let mut vertices = Vector3Array::new();
-                for vertex in &convex_collision.points {
-                    let vertex = super::quake_point_to_godot_point(*vertex, inverse_scale_factor);
-                    vertices.push(&super::godot_vector3_from_quarchitect_vector3(
-                        vertex - center,
-                    ));
+                    spawn_collision_shape(
+                        owner,
+                        &group_parent,
+                        shape.cast::<Shape>(),
+                        center - origin,
+                    );
                 }
-                let mut shape = ConvexPolygonShape::new();
-                shape.set_points(vertices);
-                spawn_collision_shape(owner, parent, shape.cast::<Shape>(), center - origin);
             }
         }
         quarchitect::scene_tree::CollisionGeometry::Concave(concave_collision) => {
-            let mut vertices = Vector3Array::new();
+            // Triangles are assigned to cells by their centroid, in Quake space
+            let mut cells: HashMap<CollisionCell, Vector3Array> = HashMap::new();
             for concave_collision in concave_collision {
-                for index in concave_collision.indices.iter() {
-                    let vertex = &concave_collision.vertices[*index];
-                    let vertex = super::quake_point_to_godot_point(*vertex, inverse_scale_factor);
-                    vertices.push(&super::godot_vector3_from_quarchitect_vector3(
-                        vertex - origin,
-                    ));
+                for triangle in concave_collision.indices.chunks(3) {
+                    if triangle.len() < 3 {
+                        continue;
+                    }
+
+                    let cell = if chunking.cell_size > 0.0 {
+                        let centroid = (concave_collision.vertices[triangle[0]]
+                            + concave_collision.vertices[triangle[1]]
+                            + concave_collision.vertices[triangle[2]])
+                            * (1.0 / 3.0);
+                        (
+                            (centroid.x() / chunking.cell_size).floor() as i32,
+                            (centroid.y() / chunking.cell_size).floor() as i32,
+                            (centroid.z() / chunking.cell_size).floor() as i32,
+                        )
+                    } else {
+                        (0, 0, 0)
+                    };
+
+                    // Mirroring reverses winding, which would turn faces inside out
+                    let triangle = if coordinates.is_mirrored() {
+                        [triangle[0], triangle[2], triangle[1]]
+                    } else {
+                        [triangle[0], triangle[1], triangle[2]]
+                    };
+
+                    let vertices = cells.entry(cell).or_insert_with(Vector3Array::new);
+                    for index in &triangle {
+                        let vertex = &concave_collision.vertices[*index];
+                        let vertex = coordinates.point(*vertex);
+                        vertices.push(&super::godot_vector3_from_quarchitect_vector3(
+                            vertex - origin,
+                        ));
+                    }
                 }
             }
 
-            let mut shape = ConcavePolygonShape::new();
-            shape.set_faces(vertices);
-            spawn_collision_shape(
-                owner,
-                parent,
-                shape.cast::<Shape>(),
-                Vector3::new(0.0, 0.0, 0.0),
-            );
+            // Sorted so spawn order, and with it node naming, is stable between builds
+            let mut cells: Vec<(CollisionCell, Vector3Array)> = cells.into_iter().collect();
+            cells.sort_by(|a, b| a.0.cmp(&b.0));
+
+            for ((x, y, z), vertices) in cells {
+                let mut shape = ConcavePolygonShape::new();
+                shape.set_faces(vertices);
+
+                if let Some(resource_cache) = resource_cache {
+                    let name = if chunking.cell_size > 0.0 {
+                        ResourceCache::concave_cell_shape_name(entity_index, x, y, z)
+                    } else {
+                        ResourceCache::concave_shape_name(entity_index)
+                    };
+                    resource_cache.save(shape.cast::<Resource>(), &name);
+                }
+
+                spawn_collision_shape(
+                    owner,
+                    parent,
+                    shape.cast::<Shape>(),
+                    Vector3::new(0.0, 0.0, 0.0),
+                );
+            }
         }
         quarchitect::scene_tree::CollisionGeometry::None => (),
     }
 }
 
+// Recursively halves the set along its longest axis until each group fits the limit
+fn group_spatially(centers: &[Vector3], mut indices: Vec<usize>, max: usize) -> Vec<Vec<usize>> {
+    if indices.len() <= max {
+        return vec![indices];
+    }
+
+    let extent = |axis: fn(&Vector3) -> f32| {
+        let values = indices.iter().map(|index| axis(&centers[*index]));
+        let min = values.clone().fold(std::f32::INFINITY, f32::min);
+        let max = values.fold(std::f32::NEG_INFINITY, f32::max);
+        max - min
+    };
+    let (x, y, z) = (extent(Vector3::x), extent(Vector3::y), extent(Vector3::z));
+
+    let axis = |point: &Vector3| {
+        if x >= y && x >= z {
+            point.x()
+        } else if y >= z {
+            point.y()
+        } else {
+            point.z()
+        }
+    };
+
+    indices.sort_by(|a, b| {
+        axis(&centers[*a])
+            .partial_cmp(&axis(&centers[*b]))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let upper = indices.split_off(indices.len() / 2);
+    let mut groups = group_spatially(centers, indices, max);
+    groups.append(&mut group_spatially(centers, upper, max));
+    groups
+}
+
+// Shapes only join the nearest body above them, so moving bodies must keep all of theirs
+fn can_hold_collision_bodies(owner: Spatial, parent: &Option<Node>) -> bool {
+    match parent {
+        Some(parent) => unsafe {
+            parent.get_instance_id() == owner.get_instance_id()
+                || parent.is_class("StaticBody".into())
+        },
+        None => true,
+    }
+}
+
+// Adds a StaticBody to hold a group of an entity's convex shapes, matching the entity's
+// collision layers if it's a physics body itself
+fn spawn_collision_body(owner: Spatial, parent: &Option<Node>, group_index: usize) -> Option<Node> {
+    let mut parent_node: Node = match parent {
+        Some(p) => *p,
+        None => unsafe { owner.cast::<Node>().unwrap() },
+    };
+
+    let mut body = StaticBody::new();
+    unsafe {
+        body.set_name(format!("Collision Group {}", group_index + 1).into());
+
+        if parent_node.is_class("CollisionObject".into()) {
+            body.set_collision_layer(parent_node.get("collision_layer".into()).to_i64());
+            body.set_collision_mask(parent_node.get("collision_mask".into()).to_i64());
+        }
+
+        let body = body.cast::<Node>();
+        crate::QodotMap::add_child_editor(owner, &mut parent_node, body);
+        body
+    }
+}
+
 fn spawn_collision_shape(
     owner: Spatial,
     parent: &Option<Node>,
@@ -0,0 +1,245 @@
+use quarchitect::game_data::{Properties, Property};
+use quarchitect::{Quat, Vector3};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Axis {
+    PositiveX,
+    PositiveY,
+    PositiveZ,
+    NegativeX,
+    NegativeY,
+    NegativeZ,
+}
+
+impl Into<i32> for Axis {
+    fn into(self) -> i32 {
+        match self {
+            Axis::PositiveX => 0,
+            Axis::PositiveY => 1,
+            Axis::PositiveZ => 2,
+            Axis::NegativeX => 3,
+            Axis::NegativeY => 4,
+            Axis::NegativeZ => 5,
+        }
+    }
+}
+
+impl From<i32> for Axis {
+    fn from(val: i32) -> Axis {
+        match val {
+            0 => Axis::PositiveX,
+            1 => Axis::PositiveY,
+            2 => Axis::PositiveZ,
+            3 => Axis::NegativeX,
+            4 => Axis::NegativeY,
+            5 => Axis::NegativeZ,
+            _ => panic!("Invalid axis"),
+        }
+    }
+}
+
+impl Axis {
+    fn vector(self) -> Vector3 {
+        match self {
+            Axis::PositiveX => Vector3::new(1.0, 0.0, 0.0),
+            Axis::PositiveY => Vector3::new(0.0, 1.0, 0.0),
+            Axis::PositiveZ => Vector3::new(0.0, 0.0, 1.0),
+            Axis::NegativeX => Vector3::new(-1.0, 0.0, 0.0),
+            Axis::NegativeY => Vector3::new(0.0, -1.0, 0.0),
+            Axis::NegativeZ => Vector3::new(0.0, 0.0, -1.0),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Handedness {
+    Right,
+    Left,
+}
+
+impl Into<i32> for Handedness {
+    fn into(self) -> i32 {
+        match self {
+            Handedness::Right => 0,
+            Handedness::Left => 1,
+        }
+    }
+}
+
+impl From<i32> for Handedness {
+    fn from(val: i32) -> Handedness {
+        match val {
+            0 => Handedness::Right,
+            1 => Handedness::Left,
+            _ => panic!("Invalid handedness"),
+        }
+    }
+}
+
+// Converts map-space points and directions into Godot space. The map's up axis becomes Godot's
+// +Y and its forward axis Godot's +X, which for Quake's Z-up, X-forward, right-handed convention
+// is the same -90 degree rotation about X the importer has always applied.
+#[derive(Debug, Copy, Clone)]
+pub struct CoordinateSystem {
+    // Map-space axes that become Godot's X, Y and Z
+    axes: [Vector3; 3],
+    // Per map axis, including the inverse scale factor
+    scale: Vector3,
+    // Whether the conversion flips handedness, reversing triangle winding
+    mirrored: bool,
+    // Map-space axes that yaw, pitch and roll rotate about
+    yaw_axis: Vector3,
+    pitch_axis: Vector3,
+    roll_axis: Vector3,
+}
+
+impl CoordinateSystem {
+    pub fn new(
+        up_axis: Axis,
+        forward_axis: Axis,
+        handedness: Handedness,
+        axis_scale: Vector3,
+        inverse_scale_factor: f32,
+    ) -> Result<CoordinateSystem, String> {
+        let up = up_axis.vector();
+        let forward = forward_axis.vector();
+
+        if up.dot(forward) != 0.0 {
+            return Err(format!(
+                "Up axis {:?} and forward axis {:?} must be perpendicular",
+                up_axis, forward_axis
+            ));
+        }
+
+        if inverse_scale_factor == 0.0 {
+            return Err("Inverse scale factor must be non-zero".into());
+        }
+
+        if axis_scale.x() == 0.0 || axis_scale.y() == 0.0 || axis_scale.z() == 0.0 {
+            return Err("Axis scale must be non-zero on every axis".into());
+        }
+
+        let third = match handedness {
+            Handedness::Right => forward.cross(up),
+            Handedness::Left => up.cross(forward),
+        };
+
+        let negative_scales = [axis_scale.x(), axis_scale.y(), axis_scale.z()]
+            .iter()
+            .filter(|scale| **scale < 0.0)
+            .count();
+
+        Ok(CoordinateSystem {
+            axes: [forward, up, third],
+            scale: axis_scale / inverse_scale_factor,
+            mirrored: (handedness == Handedness::Left) != (negative_scales % 2 == 1),
+            yaw_axis: up,
+            pitch_axis: up.cross(forward),
+            roll_axis: forward,
+        })
+    }
+
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    pub fn point(&self, point: Vector3) -> Vector3 {
+        self.project(multiply(point, self.scale))
+    }
+
+    // Normals are scaled by the inverse, so they stay perpendicular to scaled surfaces
+    pub fn normal(&self, normal: Vector3) -> Vector3 {
+        normalized(self.project(divide(normal, self.scale)))
+    }
+
+    // Returns the tangent and binormal sign, which flips along with handedness
+    pub fn tangent(&self, tangent: Vector3, flip_binormal: f32) -> (Vector3, f32) {
+        let tangent = normalized(self.project(multiply(tangent, self.scale)));
+        if self.mirrored {
+            (tangent, -flip_binormal)
+        } else {
+            (tangent, flip_binormal)
+        }
+    }
+
+    // Godot-space basis columns for a pitch, yaw and roll in degrees, applied in Quake's order
+    pub fn rotation(&self, angles: Vector3) -> [Vector3; 3] {
+        // Mirroring the space reverses the direction each axis rotates in
+        let sign = if self.mirrored { -1.0 } else { 1.0 };
+        let axis = |axis: Vector3| normalized(self.project(axis)) * sign;
+
+        let rotation = Quat::from_axis_angle(axis(self.yaw_axis), angles.y().to_radians())
+            * Quat::from_axis_angle(axis(self.pitch_axis), angles.x().to_radians())
+            * Quat::from_axis_angle(axis(self.roll_axis), angles.z().to_radians());
+
+        [
+            rotation * Vector3::new(1.0, 0.0, 0.0),
+            rotation * Vector3::new(0.0, 1.0, 0.0),
+            rotation * Vector3::new(0.0, 0.0, 1.0),
+        ]
+    }
+
+    fn project(&self, vector: Vector3) -> Vector3 {
+        Vector3::new(
+            self.axes[0].dot(vector),
+            self.axes[1].dot(vector),
+            self.axes[2].dot(vector),
+        )
+    }
+}
+
+// An entity's orientation as pitch, yaw and roll degrees, read from its angles key or its
+// yaw-only angle key, where -1 and -2 face straight up and down
+pub fn actor_angles(actor: &quarchitect::scene_tree::Actor) -> Option<Vector3> {
+    property_angles(&actor.properties)
+}
+
+fn property_angles(properties: &Properties) -> Option<Vector3> {
+    let Properties(properties) = properties;
+
+    if let Some(angles) = properties.get("angles") {
+        let angles: Vec<f32> = match angles {
+            Property::Vector3(angles) => vec![angles.x(), angles.y(), angles.z()],
+            angles => super::properties::property_text(angles)?
+                .split_whitespace()
+                .map(|component| component.parse::<f32>().ok())
+                .collect::<Option<Vec<f32>>>()?,
+        };
+
+        if angles.len() == 3 {
+            return Some(Vector3::new(angles[0], angles[1], angles[2]));
+        }
+    }
+
+    let angle = properties
+        .get("angle")
+        .and_then(super::properties::property_text)
+        .and_then(|angle| angle.trim().parse::<f32>().ok())?;
+
+    Some(if angle == -1.0 {
+        Vector3::new(-90.0, 0.0, 0.0)
+    } else if angle == -2.0 {
+        Vector3::new(90.0, 0.0, 0.0)
+    } else {
+        Vector3::new(0.0, angle, 0.0)
+    })
+}
+
+fn multiply(a: Vector3, b: Vector3) -> Vector3 {
+    Vector3::new(a.x() * b.x(), a.y() * b.y(), a.z() * b.z())
+}
+
+fn divide(a: Vector3, b: Vector3) -> Vector3 {
+    Vector3::new(a.x() / b.x(), a.y() / b.y(), a.z() / b.z())
+}
+
+fn normalized(vector: Vector3) -> Vector3 {
+    if vector.length() > std::f32::EPSILON {
+        vector.normalize()
+    } else {
+        vector
+    }
+}
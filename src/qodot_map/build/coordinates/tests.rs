@@ -0,0 +1,184 @@
+use super::{property_angles, Axis, CoordinateSystem, Handedness};
+use quarchitect::game_data::{Properties, Property};
+use quarchitect::Vector3;
+
+fn quake(inverse_scale_factor: f32) -> CoordinateSystem {
+    CoordinateSystem::new(
+        Axis::PositiveZ,
+        Axis::PositiveX,
+        Handedness::Right,
+        Vector3::new(1.0, 1.0, 1.0),
+        inverse_scale_factor,
+    )
+    .unwrap()
+}
+
+fn with_scale(handedness: Handedness, axis_scale: Vector3) -> CoordinateSystem {
+    CoordinateSystem::new(
+        Axis::PositiveZ,
+        Axis::PositiveX,
+        handedness,
+        axis_scale,
+        1.0,
+    )
+    .unwrap()
+}
+
+fn properties(pairs: &[(&str, &str)]) -> Properties {
+    Properties(
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), Property::String(value.to_string())))
+            .collect(),
+    )
+}
+
+fn assert_vector_eq(actual: Vector3, expected: Vector3) {
+    assert!(
+        (actual - expected).length() < 0.0001,
+        "expected {:?}, got {:?}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn quake_points_map_to_godot() {
+    let coordinates = quake(1.0);
+    assert!(!coordinates.is_mirrored());
+
+    // (x, y, z) becomes (x, z, -y)
+    assert_vector_eq(
+        coordinates.point(Vector3::new(1.0, 2.0, 3.0)),
+        Vector3::new(1.0, 3.0, -2.0),
+    );
+    assert_vector_eq(
+        coordinates.normal(Vector3::new(0.0, 0.0, 1.0)),
+        Vector3::new(0.0, 1.0, 0.0),
+    );
+}
+
+#[test]
+fn inverse_scale_factor_divides_points() {
+    let coordinates = quake(16.0);
+
+    assert_vector_eq(
+        coordinates.point(Vector3::new(16.0, 32.0, 48.0)),
+        Vector3::new(1.0, 3.0, -2.0),
+    );
+
+    // Directions stay unit length whatever the scale
+    assert_vector_eq(
+        coordinates.normal(Vector3::new(0.0, 1.0, 0.0)),
+        Vector3::new(0.0, 0.0, -1.0),
+    );
+}
+
+#[test]
+fn left_handed_maps_are_mirrored() {
+    let coordinates = with_scale(Handedness::Left, Vector3::new(1.0, 1.0, 1.0));
+    assert!(coordinates.is_mirrored());
+
+    assert_vector_eq(
+        coordinates.point(Vector3::new(1.0, 2.0, 3.0)),
+        Vector3::new(1.0, 3.0, 2.0),
+    );
+
+    let (tangent, flip_binormal) = coordinates.tangent(Vector3::new(1.0, 0.0, 0.0), 1.0);
+    assert_vector_eq(tangent, Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(flip_binormal, -1.0);
+}
+
+#[test]
+fn negative_axis_scales_toggle_mirroring() {
+    let one = with_scale(Handedness::Right, Vector3::new(-1.0, 1.0, 1.0));
+    assert!(one.is_mirrored());
+    assert_vector_eq(
+        one.point(Vector3::new(1.0, 2.0, 3.0)),
+        Vector3::new(-1.0, 3.0, -2.0),
+    );
+
+    let two = with_scale(Handedness::Right, Vector3::new(-1.0, -1.0, 1.0));
+    assert!(!two.is_mirrored());
+
+    let left_and_one = with_scale(Handedness::Left, Vector3::new(1.0, 1.0, -1.0));
+    assert!(!left_and_one.is_mirrored());
+}
+
+#[test]
+fn invalid_systems_are_rejected() {
+    assert!(CoordinateSystem::new(
+        Axis::PositiveZ,
+        Axis::NegativeZ,
+        Handedness::Right,
+        Vector3::new(1.0, 1.0, 1.0),
+        1.0,
+    )
+    .is_err());
+
+    assert!(CoordinateSystem::new(
+        Axis::PositiveZ,
+        Axis::PositiveX,
+        Handedness::Right,
+        Vector3::new(1.0, 0.0, 1.0),
+        1.0,
+    )
+    .is_err());
+
+    assert!(CoordinateSystem::new(
+        Axis::PositiveZ,
+        Axis::PositiveX,
+        Handedness::Right,
+        Vector3::new(1.0, 1.0, 1.0),
+        0.0,
+    )
+    .is_err());
+}
+
+#[test]
+fn yaw_turns_forward_towards_map_left() {
+    // Quake's 90 degree yaw faces +Y, which is Godot's -Z
+    let [forward, up, _] = quake(1.0).rotation(Vector3::new(0.0, 90.0, 0.0));
+    assert_vector_eq(forward, Vector3::new(0.0, 0.0, -1.0));
+    assert_vector_eq(up, Vector3::new(0.0, 1.0, 0.0));
+
+    // Mirrored, map +Y lands on Godot's +Z, and the rotation follows it there
+    let mirrored = with_scale(Handedness::Left, Vector3::new(1.0, 1.0, 1.0));
+    let [forward, _, _] = mirrored.rotation(Vector3::new(0.0, 90.0, 0.0));
+    assert_vector_eq(forward, Vector3::new(0.0, 0.0, 1.0));
+}
+
+#[test]
+fn angle_key_reads_yaw() {
+    assert_vector_eq(
+        property_angles(&properties(&[("angle", "45")])).unwrap(),
+        Vector3::new(0.0, 45.0, 0.0),
+    );
+}
+
+#[test]
+fn angle_minus_one_faces_up() {
+    let angles = property_angles(&properties(&[("angle", "-1")])).unwrap();
+    assert_vector_eq(angles, Vector3::new(-90.0, 0.0, 0.0));
+
+    let [forward, _, _] = quake(1.0).rotation(angles);
+    assert_vector_eq(forward, Vector3::new(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn angle_minus_two_faces_down() {
+    let angles = property_angles(&properties(&[("angle", "-2")])).unwrap();
+    assert_vector_eq(angles, Vector3::new(90.0, 0.0, 0.0));
+
+    let [forward, _, _] = quake(1.0).rotation(angles);
+    assert_vector_eq(forward, Vector3::new(0.0, -1.0, 0.0));
+}
+
+#[test]
+fn angles_key_takes_precedence() {
+    let angles = property_angles(&properties(&[("angles", "10 20 30"), ("angle", "-1")]));
+    assert_vector_eq(angles.unwrap(), Vector3::new(10.0, 20.0, 30.0));
+
+    assert!(property_angles(&properties(&[])).is_none());
+    assert!(property_angles(&properties(&[("angle", "north")])).is_none());
+}
@@ -1,20 +1,38 @@
 use gdnative::{ClassDB, Node, Spatial, Variant};
 use quarchitect::Vector3;
 
+use super::coordinates::CoordinateSystem;
+
 pub fn spawn_class_entity(
     owner: Spatial,
     parent: &mut Node,
     class_name: &str,
     origin: Vector3,
-    inverse_scale_factor: f32
+    angles: Option<Vector3>,
+    coordinates: &CoordinateSystem,
 ) -> Option<Node> {
     let class_db = ClassDB::godot_singleton();
     let instance: Variant = class_db.instance(class_name.into());
 
     if let Some(mut entity) = instance.try_to_object::<Spatial>() {
         unsafe {
-            let origin = super::quake_point_to_godot_point(origin, inverse_scale_factor);
+            let origin = coordinates.point(origin);
             entity.set_translation(super::godot_vector3_from_quarchitect_vector3(origin));
+
+            if let Some(angles) = angles {
+                let [x, y, z] = coordinates.rotation(angles);
+
+                // Basis elements are rows, where the rotation gives its axes as columns
+                let mut transform = entity.get_transform();
+                transform.basis = gdnative::Basis {
+                    elements: [
+                        gdnative::Vector3::new(x.x(), y.x(), z.x()),
+                        gdnative::Vector3::new(x.y(), y.y(), z.y()),
+                        gdnative::Vector3::new(x.z(), y.z(), z.z()),
+                    ],
+                };
+                entity.set_transform(transform);
+            }
         }
     }
 
@@ -28,7 +46,8 @@ pub fn spawn_prefab_entity(
     _parent: &mut Node,
     prefab_name: &str,
     _origin: Vector3,
-    _inverse_scale_factor: f32
+    _angles: Option<Vector3>,
+    _coordinates: &CoordinateSystem,
 ) -> Option<Node> {
     println!("TODO: Implement Prefab Entities. Tried to spawn {:#?}", prefab_name);
     None
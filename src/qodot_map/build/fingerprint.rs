@@ -0,0 +1,151 @@
+use std::hash::{Hash, Hasher};
+
+use quarchitect::game_data::{Properties, PropertyApplicationType};
+use quarchitect::scene_tree::{CollisionGeometry, SceneTreeNode, SceneTreeType, VisualGeometry};
+use quarchitect::Vector3;
+
+// Metadata key holding the fingerprint an entity node was built from
+pub const META: &str = "qodot_fingerprint";
+
+// Summarizes everything a top-level scene tree node is built from: its classname and
+// properties, the geometry and textures quarchitect produced from its brushes, and the build
+// settings it's spawned with. Two nodes with equal fingerprints produce the same spawned subtree.
+pub fn fingerprint(node: &SceneTreeNode, settings: u64) -> u64 {
+    let mut hasher = FnvHasher::default();
+    settings.hash(&mut hasher);
+    hash_node(&mut hasher, node);
+    hasher.finish()
+}
+
+// Summarizes the map settings that change how entities are spawned, such as the coordinate
+// system and materials, so that changing one rebuilds every entity
+pub fn settings_fingerprint(settings: &[String]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    settings.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn to_string(fingerprint: u64) -> String {
+    format!("{:016x}", fingerprint)
+}
+
+pub fn from_string(fingerprint: &str) -> Option<u64> {
+    u64::from_str_radix(fingerprint, 16).ok()
+}
+
+fn hash_node(hasher: &mut FnvHasher, node: &SceneTreeNode) {
+    hash_vector(hasher, node.origin);
+
+    match &node.data {
+        SceneTreeType::Actor(actor, children) => {
+            0u8.hash(hasher);
+            actor.name.hash(hasher);
+            format!("{:?}", actor.entity_type).hash(hasher);
+            actor.component_class.hash(hasher);
+
+            match actor.property_application_type {
+                PropertyApplicationType::Properties => 0u8,
+                PropertyApplicationType::Dictionary => 1u8,
+                PropertyApplicationType::Metadata => 2u8,
+            }
+            .hash(hasher);
+
+            // Property order isn't stable between runs, so keys are sorted first
+            let Properties(properties) = &actor.properties;
+            let mut keys: Vec<&String> = properties.keys().collect();
+            keys.sort();
+            for key in keys {
+                key.hash(hasher);
+                super::properties::property_text(&properties[key]).hash(hasher);
+            }
+
+            children.len().hash(hasher);
+            for child in children {
+                hash_node(hasher, child);
+            }
+        }
+        SceneTreeType::VisualGeometry(visual_geometry) => {
+            1u8.hash(hasher);
+            match visual_geometry {
+                VisualGeometry::Mesh(visual_mesh) => {
+                    for surface in &visual_mesh.surfaces {
+                        surface.texture.hash(hasher);
+                        hash_vectors(hasher, &surface.vertices);
+                        hash_vectors(hasher, &surface.normals);
+                        surface.indices.hash(hasher);
+
+                        if let Some(uvs) = &surface.uvs {
+                            for uv in uvs {
+                                uv.x().to_bits().hash(hasher);
+                                uv.y().to_bits().hash(hasher);
+                            }
+                        }
+
+                        if let Some(colors) = &surface.colors {
+                            for color in colors {
+                                color.r.to_bits().hash(hasher);
+                                color.g.to_bits().hash(hasher);
+                                color.b.to_bits().hash(hasher);
+                            }
+                        }
+                    }
+                }
+                VisualGeometry::None => (),
+            }
+        }
+        SceneTreeType::CollisionGeometry(collision_geometry) => {
+            2u8.hash(hasher);
+            match collision_geometry {
+                CollisionGeometry::Convex(convex_collision) => {
+                    for convex_collision in convex_collision {
+                        hash_vector(hasher, convex_collision.center);
+                        hash_vectors(hasher, &convex_collision.points);
+                    }
+                }
+                CollisionGeometry::Concave(concave_collision) => {
+                    for concave_collision in concave_collision {
+                        hash_vectors(hasher, &concave_collision.vertices);
+                        concave_collision.indices.hash(hasher);
+                    }
+                }
+                CollisionGeometry::None => (),
+            }
+        }
+    }
+}
+
+fn hash_vector(hasher: &mut FnvHasher, vector: Vector3) {
+    vector.x().to_bits().hash(hasher);
+    vector.y().to_bits().hash(hasher);
+    vector.z().to_bits().hash(hasher);
+}
+
+fn hash_vectors(hasher: &mut FnvHasher, vectors: &[Vector3]) {
+    vectors.len().hash(hasher);
+    for vector in vectors {
+        hash_vector(hasher, *vector);
+    }
+}
+
+// FNV-1a, used over std's hasher since fingerprints are saved with the scene
+// and need to stay stable across Rust versions
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use quarchitect::game_data::Properties;
+use quarchitect::scene_tree::{SceneTreeNode, SceneTreeType};
+use quarchitect::Vector3;
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum ContainerType {
+    Layer,
+    Group,
+}
+
+// Reproduces TrenchBroom's layer and group hierarchy by moving each top-level entity beneath
+// the func_group standing in for its group or layer. Entities in the default layer stay at the
+// root, and layers marked to be omitted from export are dropped along with their contents.
+pub fn nest_layers_and_groups(scene_tree: Vec<SceneTreeNode>) -> Vec<SceneTreeNode> {
+    let entities: Vec<Option<(&str, &Properties)>> = scene_tree
+        .iter()
+        .map(|node| match &node.data {
+            SceneTreeType::Actor(actor, _) => {
+                Some((super::scene_tree::actor_classname(actor), &actor.properties))
+            }
+            _ => None,
+        })
+        .collect();
+    let hierarchy = Hierarchy::new(&entities);
+
+    let mut slots: Vec<Option<SceneTreeNode>> = scene_tree.into_iter().map(Some).collect();
+
+    let mut nested: Vec<SceneTreeNode> = Vec::new();
+    for (index, parent) in hierarchy.parents.iter().enumerate() {
+        if parent.is_none() {
+            if let Some(node) = nest(index, &mut slots, &hierarchy) {
+                nested.push(node);
+            }
+        }
+    }
+    nested
+}
+
+// Where each top-level entity belongs in TrenchBroom's hierarchy, by index
+struct Hierarchy {
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    omitted: Vec<bool>,
+}
+
+impl Hierarchy {
+    // Takes the classname and properties of each entity, or None for other nodes
+    fn new(entities: &[Option<(&str, &Properties)>]) -> Hierarchy {
+        let mut containers: HashMap<(ContainerType, String), usize> = HashMap::new();
+        for (index, entity) in entities.iter().enumerate() {
+            if let Some(container) = entity.and_then(|(classname, properties)| {
+                let container_type = tb_type(classname, properties)?;
+                let id = tb_key(properties, "_tb_id")?;
+                Some((container_type, id))
+            }) {
+                containers.insert(container, index);
+            }
+        }
+
+        // Groups belong to a parent group or a layer, the same as any other entity
+        let parents: Vec<Option<usize>> = entities
+            .iter()
+            .map(|entity| {
+                let (_, properties) = (*entity)?;
+                let group = tb_key(properties, "_tb_group")
+                    .and_then(|id| containers.get(&(ContainerType::Group, id)));
+                let layer = tb_key(properties, "_tb_layer")
+                    .and_then(|id| containers.get(&(ContainerType::Layer, id)));
+                group.or(layer).cloned()
+            })
+            .collect();
+
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); entities.len()];
+        for (index, parent) in parents.iter().enumerate() {
+            if let Some(parent) = parent {
+                children[*parent].push(index);
+            }
+        }
+
+        let omitted: Vec<bool> = entities
+            .iter()
+            .map(|entity| match entity {
+                Some((classname, properties)) => {
+                    tb_type(classname, properties) == Some(ContainerType::Layer)
+                        && tb_key(properties, "_tb_layer_omit_from_export") == Some("1".into())
+                }
+                None => false,
+            })
+            .collect();
+
+        Hierarchy {
+            parents,
+            children,
+            omitted,
+        }
+    }
+}
+
+fn nest(
+    index: usize,
+    slots: &mut [Option<SceneTreeNode>],
+    hierarchy: &Hierarchy,
+) -> Option<SceneTreeNode> {
+    // Taking the node out of its slot also guards against malformed maps with cyclic groups
+    let mut node = slots[index].take()?;
+    if hierarchy.omitted[index] {
+        return None;
+    }
+
+    if hierarchy.children[index].is_empty() {
+        return Some(node);
+    }
+
+    // Nested entities are positioned in map space, so containers sit at the map origin
+    node.origin = Vector3::default();
+    if let SceneTreeType::Actor(_, node_children) = &mut node.data {
+        for child in node_children.iter_mut() {
+            if let SceneTreeType::Actor(_, _) = child.data {
+                continue;
+            }
+            child.origin = Vector3::default();
+        }
+
+        for child in &hierarchy.children[index] {
+            if let Some(child) = nest(*child, slots, hierarchy) {
+                node_children.push(child);
+            }
+        }
+    }
+
+    Some(node)
+}
+
+// Hidden layers are spawned, but start invisible as they are in the editor
+pub fn is_hidden_layer(actor: &quarchitect::scene_tree::Actor) -> bool {
+    is_hidden(super::scene_tree::actor_classname(actor), &actor.properties)
+}
+
+fn is_hidden(classname: &str, properties: &Properties) -> bool {
+    tb_type(classname, properties) == Some(ContainerType::Layer)
+        && tb_key(properties, "_tb_layer_hidden") == Some("1".into())
+}
+
+fn tb_type(classname: &str, properties: &Properties) -> Option<ContainerType> {
+    if classname != "func_group" {
+        return None;
+    }
+
+    match tb_key(properties, "_tb_type")?.as_str() {
+        "_tb_layer" => Some(ContainerType::Layer),
+        "_tb_group" => Some(ContainerType::Group),
+        _ => None,
+    }
+}
+
+fn tb_key(properties: &Properties, key: &str) -> Option<String> {
+    let Properties(properties) = properties;
+    properties
+        .get(key)
+        .and_then(super::properties::property_text)
+        .map(|value| value.trim().to_string())
+}
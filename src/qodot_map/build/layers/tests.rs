@@ -0,0 +1,119 @@
+use super::{is_hidden, Hierarchy};
+use quarchitect::game_data::{Properties, Property};
+
+fn properties(pairs: &[(&str, &str)]) -> Properties {
+    Properties(
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), Property::String(value.to_string())))
+            .collect(),
+    )
+}
+
+fn layer(id: &str, extra: &[(&str, &str)]) -> (&'static str, Properties) {
+    let mut pairs = vec![("_tb_type", "_tb_layer"), ("_tb_id", id)];
+    pairs.extend_from_slice(extra);
+    ("func_group", properties(&pairs))
+}
+
+fn group(id: &str, extra: &[(&str, &str)]) -> (&'static str, Properties) {
+    let mut pairs = vec![("_tb_type", "_tb_group"), ("_tb_id", id)];
+    pairs.extend_from_slice(extra);
+    ("func_group", properties(&pairs))
+}
+
+fn entity(classname: &'static str, keys: &[(&str, &str)]) -> (&'static str, Properties) {
+    (classname, properties(keys))
+}
+
+fn hierarchy(entities: &[(&str, Properties)]) -> Hierarchy {
+    let entities: Vec<Option<(&str, &Properties)>> = entities
+        .iter()
+        .map(|(classname, properties)| Some((*classname, properties)))
+        .collect();
+    Hierarchy::new(&entities)
+}
+
+#[test]
+fn entities_nest_under_their_layer() {
+    let hierarchy = hierarchy(&[
+        entity("worldspawn", &[]),
+        layer("1", &[]),
+        entity("light", &[("_tb_layer", "1")]),
+        entity("light", &[]),
+    ]);
+
+    assert_eq!(hierarchy.parents, vec![None, None, Some(1), None]);
+    assert_eq!(hierarchy.children[1], vec![2]);
+    assert_eq!(hierarchy.omitted, vec![false; 4]);
+}
+
+#[test]
+fn groups_nest_inside_layers_and_groups() {
+    let hierarchy = hierarchy(&[
+        layer("1", &[]),
+        group("2", &[("_tb_layer", "1")]),
+        group("3", &[("_tb_group", "2"), ("_tb_layer", "1")]),
+        entity("func_door", &[("_tb_group", "3"), ("_tb_layer", "1")]),
+    ]);
+
+    // Group membership wins over the layer, which the group already belongs to
+    assert_eq!(hierarchy.parents, vec![None, Some(0), Some(1), Some(2)]);
+    assert_eq!(hierarchy.children, vec![vec![1], vec![2], vec![3], vec![]]);
+}
+
+#[test]
+fn omitted_layers_take_their_contents_with_them() {
+    let hierarchy = hierarchy(&[
+        layer("1", &[("_tb_layer_omit_from_export", "1")]),
+        group("2", &[("_tb_layer", "1")]),
+        entity("func_door", &[("_tb_group", "2"), ("_tb_layer", "1")]),
+        layer("3", &[("_tb_layer_omit_from_export", "0")]),
+    ]);
+
+    // Only the layer itself is marked, since nesting drops everything beneath it
+    assert_eq!(hierarchy.omitted, vec![true, false, false, false]);
+    assert_eq!(hierarchy.parents, vec![None, Some(0), Some(1), None]);
+}
+
+#[test]
+fn only_layers_are_omitted_or_hidden() {
+    let hierarchy = hierarchy(&[group(
+        "1",
+        &[
+            ("_tb_layer_omit_from_export", "1"),
+            ("_tb_layer_hidden", "1"),
+        ],
+    )]);
+    assert_eq!(hierarchy.omitted, vec![false]);
+
+    let (classname, properties) = layer("1", &[("_tb_layer_hidden", " 1 ")]);
+    assert!(is_hidden(classname, &properties));
+
+    let (classname, properties) = group("1", &[("_tb_layer_hidden", "1")]);
+    assert!(!is_hidden(classname, &properties));
+
+    let (_, properties) = layer("1", &[("_tb_layer_hidden", "1")]);
+    assert!(!is_hidden("light", &properties));
+}
+
+#[test]
+fn unknown_containers_leave_entities_at_the_root() {
+    let hierarchy = hierarchy(&[
+        layer("1", &[]),
+        entity("light", &[("_tb_layer", "2")]),
+        entity("light", &[("_tb_group", "1")]),
+    ]);
+
+    // Layer and group IDs are separate, so a group ID can't match a layer
+    assert_eq!(hierarchy.parents, vec![None, None, None]);
+}
+
+#[test]
+fn other_nodes_are_left_alone() {
+    let layer = layer("1", &[]);
+    let hierarchy = Hierarchy::new(&[Some((layer.0, &layer.1)), None]);
+
+    assert_eq!(hierarchy.parents, vec![None, None]);
+    assert_eq!(hierarchy.omitted, vec![false, false]);
+}
@@ -1,22 +1,28 @@
+pub mod batching;
 pub mod collision_geometry;
+pub mod coordinates;
 pub mod entities;
+pub mod fingerprint;
+pub mod layers;
+pub mod origins;
+pub mod properties;
+pub mod resource_cache;
 pub mod scene_tree;
+pub mod smoothing;
 pub mod visual_geometry;
+pub mod welding;
 pub mod worker;
 
-pub fn quake_point_to_godot_point(
-    point: quarchitect::Vector3,
-    inverse_scale_factor: f32,
-) -> quarchitect::Vector3 {
-    quake_direction_to_godot_direction(point) / inverse_scale_factor
-}
+// Vertices closer than this (in map units) are considered to share a position
+pub const POSITION_EPSILON: f32 = 0.01;
 
-pub fn quake_direction_to_godot_direction(dir: quarchitect::Vector3) -> quarchitect::Vector3 {
-    let rot = quarchitect::Quat::from_axis_angle(
-        quarchitect::Vector3::new(-1.0, 0.0, 0.0),
-        90.0_f32.to_radians(),
-    );
-    rot * dir
+// Quantizes a position so that vertices sharing it hash alike
+pub fn position_key(position: quarchitect::Vector3) -> (i64, i64, i64) {
+    (
+        (position.x() / POSITION_EPSILON).round() as i64,
+        (position.y() / POSITION_EPSILON).round() as i64,
+        (position.z() / POSITION_EPSILON).round() as i64,
+    )
 }
 
 pub fn godot_vector3_from_quarchitect_vector3(vec: quarchitect::Vector3) -> gdnative::Vector3 {
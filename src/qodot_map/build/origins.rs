@@ -0,0 +1,232 @@
+use quarchitect::game_data::{Properties, Property};
+use quarchitect::scene_tree::{CollisionGeometry, SceneTreeNode, SceneTreeType, VisualGeometry};
+use quarchitect::Vector3;
+
+#[cfg(test)]
+mod tests;
+
+// Points closer than this (in map units) are considered the same vertex
+const VERTEX_EPSILON: f32 = 0.01;
+
+// The origin-textured surfaces removed from an entity, kept to find the collision built from them
+struct OriginBrush {
+    min: Vector3,
+    max: Vector3,
+    vertices: Vec<Vector3>,
+    triangles: Vec<[Vector3; 3]>,
+}
+
+impl OriginBrush {
+    fn new(vertex: Vector3) -> OriginBrush {
+        OriginBrush {
+            min: vertex,
+            max: vertex,
+            vertices: Vec::new(),
+            triangles: Vec::new(),
+        }
+    }
+
+    // Grows the brush to take in one of its surfaces
+    fn add_surface(&mut self, vertices: &[Vector3], indices: &[usize]) {
+        for vertex in vertices {
+            self.min = Vector3::new(
+                self.min.x().min(vertex.x()),
+                self.min.y().min(vertex.y()),
+                self.min.z().min(vertex.z()),
+            );
+            self.max = Vector3::new(
+                self.max.x().max(vertex.x()),
+                self.max.y().max(vertex.y()),
+                self.max.z().max(vertex.z()),
+            );
+            self.vertices.push(*vertex);
+        }
+
+        for triangle in indices.chunks(3).filter(|triangle| triangle.len() == 3) {
+            self.triangles.push([
+                vertices[triangle[0]],
+                vertices[triangle[1]],
+                vertices[triangle[2]],
+            ]);
+        }
+    }
+
+    fn center(&self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+}
+
+// Moves brush entity pivots to their origin brush, or failing that their origin key. The origin
+// brush is removed, and its entity's geometry is offset relative to the new pivot. Runs before
+// fingerprinting, so changes to an origin brush rebuild its entity.
+pub fn apply_origin_brushes(scene_tree: &mut [SceneTreeNode]) {
+    for node in scene_tree {
+        apply_to_node(node);
+    }
+}
+
+fn apply_to_node(node: &mut SceneTreeNode) {
+    // Point entities are already placed at their origin key
+    let brush_entity = super::scene_tree::has_geometry(node);
+
+    let (actor, children) = match &mut node.data {
+        SceneTreeType::Actor(actor, children) => (actor, children),
+        _ => return,
+    };
+
+    for child in children.iter_mut() {
+        apply_to_node(child);
+    }
+
+    // Worldspawn is always placed at the map origin
+    if !brush_entity || super::scene_tree::actor_classname(actor) == "worldspawn" {
+        return;
+    }
+
+    let origin_brush = remove_origin_brush(children);
+    if let Some(origin_brush) = &origin_brush {
+        remove_origin_collision(children, origin_brush);
+    }
+
+    let pivot = match pivot(origin_brush.as_ref(), &actor.properties) {
+        Some(pivot) => pivot,
+        None => return,
+    };
+
+    node.origin = pivot;
+    for child in children.iter_mut().filter(|child| is_geometry(child)) {
+        child.origin = pivot;
+    }
+}
+
+// The centre of the entity's origin brush, falling back to its origin key
+fn pivot(origin_brush: Option<&OriginBrush>, properties: &Properties) -> Option<Vector3> {
+    match origin_brush {
+        Some(origin_brush) => Some(origin_brush.center()),
+        None => origin_key(properties),
+    }
+}
+
+// Textures marking a brush as its entity's pivot, as in Half-Life's origin and Source's
+// tools/toolsorigin
+fn is_origin_texture(texture: &str) -> bool {
+    let name = texture.rsplit('/').next().unwrap_or(texture);
+    name.eq_ignore_ascii_case("origin") || name.eq_ignore_ascii_case("toolsorigin")
+}
+
+// Removes origin-textured surfaces, returning their geometry
+fn remove_origin_brush(children: &mut [SceneTreeNode]) -> Option<OriginBrush> {
+    let mut origin_brush: Option<OriginBrush> = None;
+
+    for child in children.iter_mut() {
+        let visual_mesh = match &mut child.data {
+            SceneTreeType::VisualGeometry(VisualGeometry::Mesh(visual_mesh)) => visual_mesh,
+            _ => continue,
+        };
+
+        for surface in &visual_mesh.surfaces {
+            match &surface.texture {
+                Some(texture) if is_origin_texture(texture) && !surface.vertices.is_empty() => (),
+                _ => continue,
+            }
+
+            origin_brush
+                .get_or_insert_with(|| OriginBrush::new(surface.vertices[0]))
+                .add_surface(&surface.vertices, &surface.indices);
+        }
+
+        visual_mesh
+            .surfaces
+            .retain(|surface| match &surface.texture {
+                Some(texture) => !is_origin_texture(texture),
+                None => true,
+            });
+    }
+
+    origin_brush
+}
+
+// Collision is generated per brush regardless of texture, so the origin brush's shapes are
+// found by matching its vertices. Other brushes inside its bounds keep their collision.
+fn remove_origin_collision(children: &mut [SceneTreeNode], origin_brush: &OriginBrush) {
+    let is_origin_vertex = |point: &Vector3| {
+        origin_brush
+            .vertices
+            .iter()
+            .any(|vertex| coincident(*vertex, *point))
+    };
+
+    let is_origin_triangle = |triangle: [Vector3; 3]| {
+        origin_brush.triangles.iter().any(|origin_triangle| {
+            triangle.iter().all(|point| {
+                origin_triangle
+                    .iter()
+                    .any(|vertex| coincident(*vertex, *point))
+            })
+        })
+    };
+
+    for child in children.iter_mut() {
+        match &mut child.data {
+            SceneTreeType::CollisionGeometry(CollisionGeometry::Convex(convex_collision)) => {
+                convex_collision.retain(|convex_collision| {
+                    convex_collision.points.is_empty()
+                        || !convex_collision.points.iter().all(is_origin_vertex)
+                });
+            }
+            SceneTreeType::CollisionGeometry(CollisionGeometry::Concave(concave_collision)) => {
+                for concave_collision in concave_collision.iter_mut() {
+                    let vertices = &concave_collision.vertices;
+                    let indices: Vec<usize> = concave_collision
+                        .indices
+                        .chunks(3)
+                        .filter(|triangle| {
+                            triangle.len() != 3
+                                || !is_origin_triangle([
+                                    vertices[triangle[0]],
+                                    vertices[triangle[1]],
+                                    vertices[triangle[2]],
+                                ])
+                        })
+                        .flatten()
+                        .cloned()
+                        .collect();
+                    concave_collision.indices = indices;
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn coincident(a: Vector3, b: Vector3) -> bool {
+    (a.x() - b.x()).abs() <= VERTEX_EPSILON
+        && (a.y() - b.y()).abs() <= VERTEX_EPSILON
+        && (a.z() - b.z()).abs() <= VERTEX_EPSILON
+}
+
+fn origin_key(properties: &Properties) -> Option<Vector3> {
+    let Properties(properties) = properties;
+    match properties.get("origin")? {
+        Property::Vector3(origin) => Some(*origin),
+        origin => {
+            let components: Vec<f32> = super::properties::property_text(origin)?
+                .split_whitespace()
+                .map(|component| component.parse::<f32>().ok())
+                .collect::<Option<Vec<f32>>>()?;
+
+            if components.len() == 3 {
+                Some(Vector3::new(components[0], components[1], components[2]))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn is_geometry(node: &SceneTreeNode) -> bool {
+    match node.data {
+        SceneTreeType::Actor(_, _) => false,
+        _ => true,
+    }
+}
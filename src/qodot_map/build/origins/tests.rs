@@ -0,0 +1,105 @@
+use super::{is_origin_texture, origin_key, pivot, OriginBrush};
+use quarchitect::game_data::{Properties, Property};
+use quarchitect::Vector3;
+
+fn properties(pairs: Vec<(&str, Property)>) -> Properties {
+    Properties(
+        pairs
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+fn origin(value: &str) -> Properties {
+    properties(vec![("origin", Property::String(value.into()))])
+}
+
+// An axis-aligned box, as the eight corners a brush's surfaces would share
+fn box_brush(min: Vector3, max: Vector3) -> OriginBrush {
+    let corners: Vec<Vector3> = (0..8)
+        .map(|corner| {
+            Vector3::new(
+                if corner & 1 == 0 { min.x() } else { max.x() },
+                if corner & 2 == 0 { min.y() } else { max.y() },
+                if corner & 4 == 0 { min.z() } else { max.z() },
+            )
+        })
+        .collect();
+
+    let mut origin_brush = OriginBrush::new(corners[0]);
+    origin_brush.add_surface(&corners[..4], &[0, 1, 2, 0, 2, 3]);
+    origin_brush.add_surface(&corners[4..], &[0, 1, 2, 0, 2, 3]);
+    origin_brush
+}
+
+fn assert_vector_eq(actual: Option<Vector3>, expected: Vector3) {
+    let actual = actual.expect("Expected a pivot");
+    assert!(
+        (actual - expected).length() < 0.0001,
+        "expected {:?}, got {:?}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn origin_brush_centre_is_the_pivot() {
+    let origin_brush = box_brush(Vector3::new(32.0, -8.0, 0.0), Vector3::new(48.0, 8.0, 64.0));
+
+    assert_eq!(origin_brush.vertices.len(), 8);
+    assert_eq!(origin_brush.triangles.len(), 4);
+    assert_vector_eq(
+        pivot(Some(&origin_brush), &properties(Vec::new())),
+        Vector3::new(40.0, 0.0, 32.0),
+    );
+}
+
+#[test]
+fn origin_brush_wins_over_origin_key() {
+    let origin_brush = box_brush(Vector3::new(-8.0, -8.0, -8.0), Vector3::new(8.0, 8.0, 8.0));
+
+    assert_vector_eq(
+        pivot(Some(&origin_brush), &origin("64 64 64")),
+        Vector3::new(0.0, 0.0, 0.0),
+    );
+}
+
+#[test]
+fn origin_key_is_the_fallback() {
+    assert_vector_eq(
+        pivot(None, &origin(" 16 -32 8 ")),
+        Vector3::new(16.0, -32.0, 8.0),
+    );
+    assert_vector_eq(
+        pivot(
+            None,
+            &properties(vec![(
+                "origin",
+                Property::Vector3(Vector3::new(1.0, 2.0, 3.0)),
+            )]),
+        ),
+        Vector3::new(1.0, 2.0, 3.0),
+    );
+
+    assert!(pivot(None, &properties(Vec::new())).is_none());
+}
+
+#[test]
+fn malformed_origin_keys_are_ignored() {
+    assert!(origin_key(&origin("16 32")).is_none());
+    assert!(origin_key(&origin("16 32 8 4")).is_none());
+    assert!(origin_key(&origin("16 x 8")).is_none());
+    assert!(origin_key(&properties(vec![("origin", Property::TargetSource)])).is_none());
+}
+
+#[test]
+fn origin_textures_match_any_wad_or_directory() {
+    assert!(is_origin_texture("origin"));
+    assert!(is_origin_texture("ORIGIN"));
+    assert!(is_origin_texture("tools/toolsorigin"));
+    assert!(is_origin_texture("halflife/origin"));
+
+    assert!(!is_origin_texture("originals"));
+    assert!(!is_origin_texture("origin/wall"));
+}
@@ -0,0 +1,225 @@
+use quarchitect::game_data::forge::{
+    Choice as QuarchitectChoice, ChoiceData, Entity as QuarchitectForgeEntity, PropertyData,
+};
+use quarchitect::game_data::{Properties, Property};
+use std::collections::{HashMap, HashSet};
+
+#[cfg(test)]
+mod tests;
+
+// Resolves the properties to apply to an actor: map keys are coerced to the type declared by
+// the entity's definition, and keys the map leaves unset are filled with definition defaults.
+// Values that don't fit their declared type are left as written, with a build warning.
+pub fn actor_properties(
+    actor: &quarchitect::scene_tree::Actor,
+    definition: Option<&QuarchitectForgeEntity>,
+    color255_properties: Option<&HashSet<String>>,
+    choices_as_names: bool,
+    warnings: &mut Vec<String>,
+) -> HashMap<String, Property> {
+    let Properties(properties) = &actor.properties;
+    let mut properties = properties.clone();
+
+    let definition = match definition {
+        Some(definition) => definition,
+        None => return properties,
+    };
+
+    for property in &definition.properties {
+        let value = match properties.get(&property.name) {
+            Some(value) => value.clone(),
+            None => {
+                if let Some(default) = property_default(&property.data, choices_as_names) {
+                    properties.insert(property.name.clone(), default);
+                }
+                continue;
+            }
+        };
+
+        let color255 = color255_properties
+            .map(|color255_properties| color255_properties.contains(&property.name))
+            .unwrap_or(false);
+
+        match coerce_property(&value, &property.data, color255, choices_as_names) {
+            Some(coerced) => {
+                properties.insert(property.name.clone(), coerced);
+            }
+            None => warnings.push(format!(
+                "{}: Value {} for '{}' is not a valid {}",
+                definition.class_name,
+                property_text(&value).unwrap_or_default(),
+                property.name,
+                type_name(&property.data)
+            )),
+        }
+    }
+
+    properties
+}
+
+// Splits each flags property into one boolean per named flag, so scripts don't need to know
+// bit values. Each boolean is prefixed with its property's key, as in "spawnflags_silent", so
+// it can't overwrite a map key of the same name.
+pub fn flag_booleans(
+    properties: &HashMap<String, Property>,
+    definition: &QuarchitectForgeEntity,
+) -> Vec<(String, bool)> {
+    let mut flag_booleans: Vec<(String, bool)> = Vec::new();
+
+    for property in &definition.properties {
+        let flags = match &property.data {
+            PropertyData::Flags(flags, _) => flags,
+            _ => continue,
+        };
+
+        let value = match properties.get(&property.name) {
+            Some(Property::Flags(value)) | Some(Property::Integer(value)) => *value,
+            _ => continue,
+        };
+
+        for (bit, flag) in flags.iter().enumerate() {
+            if flag.is_empty() {
+                continue;
+            }
+
+            let key = format!("{}_{}", property.name, flag_key(flag));
+            flag_booleans.push((key, value & (1 << bit) != 0));
+        }
+    }
+
+    flag_booleans
+}
+
+// "Not in Deathmatch" -> "not_in_deathmatch"
+fn flag_key(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<String>>()
+        .join("_")
+}
+
+fn property_default(data: &PropertyData, choices_as_names: bool) -> Option<Property> {
+    match data {
+        PropertyData::Integer(value) => Some(Property::Integer(*value)),
+        PropertyData::Float(value) => Some(Property::Float(*value)),
+        PropertyData::Vector3(value) => Some(Property::Vector3(*value)),
+        PropertyData::String(value) => Some(Property::String(value.clone())),
+        PropertyData::Color(value) => Some(Property::Color(*value)),
+        // Choice defaults are stored as an index into the choice list
+        PropertyData::Choices(choices, default) => match choices.get(*default as usize) {
+            Some(choice) => Some(choice_property(choice, choices_as_names)),
+            None => Some(Property::Choices(*default)),
+        },
+        PropertyData::Flags(_, default) => Some(Property::Flags(*default)),
+        PropertyData::TargetSource => None,
+        PropertyData::TargetDestination => None,
+    }
+}
+
+fn coerce_property(
+    value: &Property,
+    data: &PropertyData,
+    color255: bool,
+    choices_as_names: bool,
+) -> Option<Property> {
+    let text = match property_text(value) {
+        Some(text) => text,
+        None => return Some(value.clone()),
+    };
+    let trimmed = text.trim();
+
+    match data {
+        PropertyData::Integer(_) => parse_integer(trimmed).map(Property::Integer),
+        PropertyData::Float(_) => trimmed.parse::<f32>().ok().map(Property::Float),
+        PropertyData::Vector3(_) => parse_floats(trimmed)
+            .filter(|v| v.len() == 3)
+            .map(|v| Property::Vector3(quarchitect::Vector3::new(v[0], v[1], v[2]))),
+        PropertyData::String(_) => Some(Property::String(text)),
+        // Half-Life appends a brightness to light colours, which has no place in a Color
+        PropertyData::Color(_) => parse_floats(trimmed)
+            .filter(|c| c.len() == 3 || c.len() == 4)
+            .map(|c| {
+                let scale = if color255 { 255.0 } else { 1.0 };
+                Property::Color(quarchitect::Color::new(
+                    c[0] / scale,
+                    c[1] / scale,
+                    c[2] / scale,
+                ))
+            }),
+        PropertyData::Choices(choices, _) => choices
+            .iter()
+            .find(|choice| choice_matches(choice, trimmed))
+            .map(|choice| choice_property(choice, choices_as_names)),
+        PropertyData::Flags(_, _) => parse_integer(trimmed).map(Property::Flags),
+        PropertyData::TargetSource => Some(value.clone()),
+        PropertyData::TargetDestination => Some(value.clone()),
+    }
+}
+
+fn choice_matches(choice: &QuarchitectChoice, text: &str) -> bool {
+    let value_matches = match &choice.value {
+        ChoiceData::Integer(value) => parse_integer(text) == Some(*value),
+        ChoiceData::Float(value) => text.parse::<f32>().ok() == Some(*value),
+        ChoiceData::String(value) => value == text,
+    };
+
+    value_matches || choice.name == text
+}
+
+fn choice_property(choice: &QuarchitectChoice, choices_as_names: bool) -> Property {
+    if choices_as_names {
+        return Property::String(choice.name.clone());
+    }
+
+    match &choice.value {
+        ChoiceData::Integer(value) => Property::Choices(*value),
+        ChoiceData::Float(value) => Property::Float(*value),
+        ChoiceData::String(value) => Property::String(value.clone()),
+    }
+}
+
+fn parse_integer(text: &str) -> Option<i32> {
+    match text.parse::<i32>() {
+        Ok(value) => Some(value),
+        Err(_) => match text.parse::<f32>() {
+            Ok(value) if value.fract() == 0.0 => Some(value as i32),
+            _ => None,
+        },
+    }
+}
+
+fn parse_floats(text: &str) -> Option<Vec<f32>> {
+    text.split_whitespace()
+        .map(|component| component.parse::<f32>().ok())
+        .collect()
+}
+
+// Textual form of a property, as it would appear in the map file
+pub fn property_text(property: &Property) -> Option<String> {
+    match property {
+        Property::Integer(value) => Some(value.to_string()),
+        Property::Float(value) => Some(value.to_string()),
+        Property::Vector3(value) => Some(format!("{} {} {}", value.x(), value.y(), value.z())),
+        Property::String(value) => Some(value.clone()),
+        Property::Color(value) => Some(format!("{} {} {}", value.r, value.g, value.b)),
+        Property::Choices(value) => Some(value.to_string()),
+        Property::Flags(value) => Some(value.to_string()),
+        Property::TargetSource => None,
+        Property::TargetDestination => None,
+    }
+}
+
+fn type_name(data: &PropertyData) -> &'static str {
+    match data {
+        PropertyData::Integer(_) => "integer",
+        PropertyData::Float(_) => "float",
+        PropertyData::Vector3(_) => "vector3",
+        PropertyData::String(_) => "string",
+        PropertyData::Color(_) => "color",
+        PropertyData::Choices(_, _) => "choice",
+        PropertyData::Flags(_, _) => "flags value",
+        PropertyData::TargetSource => "target source",
+        PropertyData::TargetDestination => "target destination",
+    }
+}
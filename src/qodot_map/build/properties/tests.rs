@@ -0,0 +1,126 @@
+use super::{coerce_property, flag_booleans};
+use quarchitect::game_data::forge::{
+    Choice as QuarchitectChoice, ChoiceData, Entity as QuarchitectForgeEntity,
+    Property as QuarchitectProperty, PropertyData,
+};
+use quarchitect::game_data::Property;
+use std::collections::HashMap;
+
+fn string(value: &str) -> Property {
+    Property::String(value.to_string())
+}
+
+fn color(value: &str, color255: bool) -> Option<(f32, f32, f32)> {
+    let data = PropertyData::Color(quarchitect::Color::new(1.0, 1.0, 1.0));
+    match coerce_property(&string(value), &data, color255, false) {
+        Some(Property::Color(color)) => Some((color.r, color.g, color.b)),
+        Some(other) => panic!("Expected a color, got {:?}", other),
+        None => None,
+    }
+}
+
+fn choices() -> PropertyData {
+    let choice = |name: &str, value: i32| {
+        let mut choice = QuarchitectChoice::default();
+        choice.name = name.into();
+        choice.value = ChoiceData::Integer(value);
+        choice
+    };
+
+    PropertyData::Choices(vec![choice("Normal", 0), choice("Fancy", 1)], 0)
+}
+
+#[test]
+fn color255_values_are_scaled() {
+    assert_eq!(color("255 0 51", true), Some((1.0, 0.0, 0.2)));
+}
+
+#[test]
+fn color1_values_are_kept() {
+    assert_eq!(color("1 0.5 0", false), Some((1.0, 0.5, 0.0)));
+
+    // The declared range wins, even when every component would fit the other one
+    assert_eq!(
+        color("1 1 1", true),
+        Some((1.0 / 255.0, 1.0 / 255.0, 1.0 / 255.0))
+    );
+}
+
+#[test]
+fn light_brightness_is_ignored() {
+    assert_eq!(color("255 255 255 200", true), Some((1.0, 1.0, 1.0)));
+    assert_eq!(color("255 255", true), None);
+    assert_eq!(color("255 255 255 200 1", true), None);
+}
+
+#[test]
+fn vectors_need_three_components() {
+    let data = PropertyData::Vector3(quarchitect::Vector3::default());
+
+    match coerce_property(&string(" 1 2 3 "), &data, false, false) {
+        Some(Property::Vector3(vector)) => {
+            assert_eq!((vector.x(), vector.y(), vector.z()), (1.0, 2.0, 3.0))
+        }
+        other => panic!("Expected a vector, got {:?}", other),
+    }
+
+    assert!(coerce_property(&string("1 2 3 4"), &data, false, false).is_none());
+    assert!(coerce_property(&string("1 2 x"), &data, false, false).is_none());
+}
+
+#[test]
+fn integers_accept_whole_floats() {
+    let data = PropertyData::Integer(0);
+
+    match coerce_property(&string("4.0"), &data, false, false) {
+        Some(Property::Integer(value)) => assert_eq!(value, 4),
+        other => panic!("Expected an integer, got {:?}", other),
+    }
+    assert!(coerce_property(&string("4.5"), &data, false, false).is_none());
+}
+
+#[test]
+fn choices_match_values_or_names() {
+    match coerce_property(&string("1"), &choices(), false, false) {
+        Some(Property::Choices(value)) => assert_eq!(value, 1),
+        other => panic!("Expected a choice, got {:?}", other),
+    }
+
+    match coerce_property(&string("Fancy"), &choices(), false, true) {
+        Some(Property::String(name)) => assert_eq!(name, "Fancy"),
+        other => panic!("Expected a choice name, got {:?}", other),
+    }
+
+    assert!(coerce_property(&string("2"), &choices(), false, false).is_none());
+}
+
+#[test]
+fn flag_booleans_are_prefixed_with_their_key() {
+    let mut definition = QuarchitectForgeEntity::default();
+    definition.properties = ["spawnflags", "style"]
+        .iter()
+        .map(|name| {
+            let mut property = QuarchitectProperty::default();
+            property.name = name.to_string();
+            property.data = PropertyData::Flags(
+                vec!["Not in Deathmatch".into(), String::new(), "Silent".into()],
+                0,
+            );
+            property
+        })
+        .collect();
+
+    let mut properties: HashMap<String, Property> = HashMap::new();
+    properties.insert("spawnflags".into(), Property::Flags(5));
+    properties.insert("style".into(), Property::Integer(0));
+
+    assert_eq!(
+        flag_booleans(&properties, &definition),
+        vec![
+            ("spawnflags_not_in_deathmatch".to_string(), true),
+            ("spawnflags_silent".to_string(), true),
+            ("style_not_in_deathmatch".to_string(), false),
+            ("style_silent".to_string(), false),
+        ]
+    );
+}
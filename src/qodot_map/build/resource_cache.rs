@@ -0,0 +1,137 @@
+use std::cell::RefCell;
+
+use gdnative::{Directory, GodotString, Resource, ResourceSaver};
+
+// Prefixes of files owned by the cache, used to clear stale output between builds
+const CACHE_PREFIXES: &[&str] = &["entity_", "batch_"];
+const CACHE_EXTENSIONS: &[&str] = &[".mesh", ".res"];
+
+// Writes generated resources to disk so scenes reference them by path instead of embedding them
+pub struct ResourceCache {
+    directory: String,
+    // Files that couldn't be saved or cleared, reported once the build is done
+    failures: RefCell<Vec<String>>,
+}
+
+// A subdirectory of the cache unique to one map node, so maps sharing the cache directory never
+// clear or overwrite each other's files. Readable, with a hash to tell apart paths that sanitize
+// to the same name.
+pub fn namespace(scene_path: &str, node_path: &str) -> String {
+    let readable: String = format!("{}_{}", scene_path.trim_start_matches("res://"), node_path)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let hash = super::fingerprint::settings_fingerprint(&[
+        scene_path.to_string(),
+        node_path.to_string(),
+    ]);
+    format!("{}_{:08x}", readable.trim_matches('_'), hash as u32)
+}
+
+impl ResourceCache {
+    pub fn new(directory: &str) -> Result<ResourceCache, String> {
+        let directory = directory.trim_end_matches('/').to_string();
+        if directory.is_empty() {
+            return Err("No resource cache directory set".into());
+        }
+
+        let mut dir = Directory::new();
+        if !dir.dir_exists(directory.clone().into()) {
+            if let Err(err) = dir.make_dir_recursive(directory.clone().into()) {
+                return Err(format!(
+                    "Failed to create resource cache directory {}: {:?}",
+                    directory, err
+                ));
+            }
+        }
+
+        let resource_cache = ResourceCache {
+            directory,
+            failures: RefCell::new(Vec::new()),
+        };
+        resource_cache.clear(&mut dir);
+        Ok(resource_cache)
+    }
+
+    pub fn take_failures(&self) -> Vec<String> {
+        self.failures.replace(Vec::new())
+    }
+
+    pub fn mesh_name(entity_index: usize) -> String {
+        format!("entity_{}_mesh.mesh", entity_index)
+    }
+
+    pub fn convex_shape_name(entity_index: usize, brush_index: usize) -> String {
+        format!("entity_{}_brush_{}_shape.res", entity_index, brush_index)
+    }
+
+    pub fn concave_shape_name(entity_index: usize) -> String {
+        format!("entity_{}_concave_shape.res", entity_index)
+    }
+
+    pub fn concave_cell_shape_name(entity_index: usize, x: i32, y: i32, z: i32) -> String {
+        format!("entity_{}_concave_{}_{}_{}_shape.res", entity_index, x, y, z)
+    }
+
+    pub fn batch_name(x: i32, y: i32, z: i32) -> String {
+        format!("batch_{}_{}_{}.mesh", x, y, z)
+    }
+
+    // Saves the resource under the given name and points it at the saved file
+    pub fn save(&self, resource: Option<Resource>, name: &str) {
+        let mut resource = match resource {
+            Some(resource) => resource,
+            None => return,
+        };
+
+        let path: GodotString = format!("{}/{}", self.directory, name).into();
+
+        match ResourceSaver::godot_singleton().save(path.clone(), Some(resource), 0) {
+            Ok(()) => resource.take_over_path(path),
+            Err(err) => self.failures.borrow_mut().push(format!(
+                "Failed to save resource {}: {:?}",
+                path.to_string(),
+                err
+            )),
+        }
+    }
+
+    fn clear(&self, dir: &mut Directory) {
+        if dir.open(self.directory.clone().into()).is_err() {
+            return;
+        }
+
+        if dir.list_dir_begin(true, true).is_err() {
+            return;
+        }
+
+        let mut stale: Vec<GodotString> = Vec::new();
+        loop {
+            let file = dir.get_next();
+            if file.is_empty() {
+                break;
+            }
+
+            let name = file.to_string();
+            if !dir.current_is_dir()
+                && CACHE_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+                && CACHE_EXTENSIONS
+                    .iter()
+                    .any(|extension| name.ends_with(extension))
+            {
+                stale.push(file);
+            }
+        }
+        dir.list_dir_end();
+
+        for file in stale {
+            if let Err(err) = dir.remove(file.clone()) {
+                self.failures.borrow_mut().push(format!(
+                    "Failed to remove {}: {:?}",
+                    file.to_string(),
+                    err
+                ));
+            }
+        }
+    }
+}
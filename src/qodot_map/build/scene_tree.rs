@@ -1,25 +1,44 @@
 use gdnative::{godot_error, Dictionary, GodotString, Node, ResourceLoader, Spatial, Variant};
+use quarchitect::game_data::forge::Entity as QuarchitectForgeEntity;
 use quarchitect::game_data::{Properties, Property};
+use std::collections::{HashMap, HashSet};
+
+use super::coordinates::CoordinateSystem;
 
 pub fn spawn_scene_tree_actor(
-    inverse_scale_factor: f32,
+    coordinates: &CoordinateSystem,
     owner: Spatial,
     parent: &Option<Node>,
     scene_tree: &quarchitect::scene_tree::SceneTreeNode,
     actor: &quarchitect::scene_tree::Actor,
+    definitions: &HashMap<String, QuarchitectForgeEntity>,
+    color255_properties: &HashMap<String, HashSet<String>>,
+    choices_as_names: bool,
+    expand_flags: bool,
+    rotate_point_entities: bool,
+    trenchbroom_hierarchy: bool,
+    warnings: &mut Vec<String>,
 ) -> Option<Node> {
     let mut parent: Node = match parent {
         Some(p) => *p,
         None => unsafe { owner.cast::<Node>().unwrap() },
     };
 
+    // Point entities can be turned to face their angles. Brush geometry is already in place.
+    let angles = if !rotate_point_entities || has_geometry(scene_tree) {
+        None
+    } else {
+        super::coordinates::actor_angles(actor)
+    };
+
     let entity = match &actor.entity_type {
         quarchitect::game_data::EntityType::Placeholder => super::entities::spawn_class_entity(
             owner,
             &mut parent,
             "Position3D",
             scene_tree.origin,
-            inverse_scale_factor,
+            angles,
+            coordinates,
         ),
         quarchitect::game_data::EntityType::Class(class_name) => {
             super::entities::spawn_class_entity(
@@ -27,7 +46,8 @@ pub fn spawn_scene_tree_actor(
                 &mut parent,
                 class_name,
                 scene_tree.origin,
-                inverse_scale_factor,
+                angles,
+                coordinates,
             )
         }
         quarchitect::game_data::EntityType::Prefab(prefab_name) => {
@@ -36,7 +56,8 @@ pub fn spawn_scene_tree_actor(
                 &mut parent,
                 prefab_name,
                 scene_tree.origin,
-                inverse_scale_factor,
+                angles,
+                coordinates,
             )
         }
     };
@@ -54,6 +75,21 @@ pub fn spawn_scene_tree_actor(
 
         unsafe { object.set_name(object_name) }
 
+        if trenchbroom_hierarchy && super::layers::is_hidden_layer(actor) {
+            if let Some(mut spatial) = object.cast::<Spatial>() {
+                unsafe { spatial.set_visible(false) }
+            }
+        }
+
+        if let Some(entity_key) = crate::qodot_map::overrides::entity_key(properties) {
+            unsafe {
+                object.set_meta(
+                    crate::qodot_map::overrides::ENTITY_KEY_META.into(),
+                    Variant::from_str(entity_key),
+                )
+            }
+        }
+
         if let Some(component_class) = &actor.component_class {
             let component_script = ResourceLoader::godot_singleton().load(
                 component_class.into(),
@@ -64,15 +100,40 @@ pub fn spawn_scene_tree_actor(
             if let Some(component_script) = component_script {
                 unsafe { object.set_script(Some(component_script.to_reference())) }
 
+                let definition = definitions.get(actor_classname(actor));
+                let properties = super::properties::actor_properties(
+                    actor,
+                    definition,
+                    color255_properties.get(actor_classname(actor)),
+                    choices_as_names,
+                    warnings,
+                );
+
+                let flags = match definition {
+                    Some(definition) if expand_flags => {
+                        super::properties::flag_booleans(&properties, definition)
+                    }
+                    _ => Vec::new(),
+                };
+
+                let mut properties: HashMap<String, Variant> = properties
+                    .iter()
+                    .map(|(key, value)| (key.clone(), quarchitect_property_to_variant(value)))
+                    .collect();
+
+                for (key, value) in flags {
+                    properties.insert(key, Variant::from_bool(value));
+                }
+
                 match actor.property_application_type {
                     quarchitect::game_data::PropertyApplicationType::Properties => {
-                        populate_properties(actor, object)
+                        populate_properties(&properties, object)
                     }
                     quarchitect::game_data::PropertyApplicationType::Dictionary => {
-                        populate_property_dictionary(actor, object)
+                        populate_property_dictionary(&properties, object)
                     }
                     quarchitect::game_data::PropertyApplicationType::Metadata => {
-                        populate_property_metadata(actor, object)
+                        populate_property_metadata(&properties, object)
                     }
                 }
             };
@@ -86,21 +147,44 @@ pub fn spawn_scene_tree_actor(
     entity
 }
 
-fn populate_properties(actor: &quarchitect::scene_tree::Actor, mut object: Node) {
+pub fn has_geometry(scene_tree: &quarchitect::scene_tree::SceneTreeNode) -> bool {
+    match &scene_tree.data {
+        quarchitect::scene_tree::SceneTreeType::Actor(_, children) => {
+            children.iter().any(|child| match &child.data {
+                quarchitect::scene_tree::SceneTreeType::VisualGeometry(
+                    quarchitect::scene_tree::VisualGeometry::None,
+                ) => false,
+                quarchitect::scene_tree::SceneTreeType::CollisionGeometry(
+                    quarchitect::scene_tree::CollisionGeometry::None,
+                ) => false,
+                quarchitect::scene_tree::SceneTreeType::Actor(_, _) => false,
+                _ => true,
+            })
+        }
+        _ => false,
+    }
+}
+
+pub fn actor_classname(actor: &quarchitect::scene_tree::Actor) -> &str {
     let Properties(properties) = &actor.properties;
+    match properties.get("classname") {
+        Some(Property::String(classname)) => classname,
+        _ => &actor.name,
+    }
+}
+
+fn populate_properties(properties: &HashMap<String, Variant>, mut object: Node) {
     for (key, value) in properties {
         unsafe {
-            object.set(key.into(), quarchitect_property_to_variant(value));
+            object.set(key.into(), value.clone());
         }
     }
 }
 
-fn populate_property_dictionary(actor: &quarchitect::scene_tree::Actor, mut object: Node) {
+fn populate_property_dictionary(properties: &HashMap<String, Variant>, mut object: Node) {
     let mut property_dict = Dictionary::new();
-    let Properties(properties) = &actor.properties;
     for (key, value) in properties {
-        let value = quarchitect_property_to_variant(value);
-        property_dict.set(&Variant::from_str(&key), &value)
+        property_dict.set(&Variant::from_str(&key), value)
     }
 
     unsafe {
@@ -111,11 +195,10 @@ fn populate_property_dictionary(actor: &quarchitect::scene_tree::Actor, mut obje
     }
 }
 
-fn populate_property_metadata(actor: &quarchitect::scene_tree::Actor, mut object: Node) {
-    let Properties(properties) = &actor.properties;
+fn populate_property_metadata(properties: &HashMap<String, Variant>, mut object: Node) {
     for (key, value) in properties {
         unsafe {
-            object.set_meta(key.into(), quarchitect_property_to_variant(value));
+            object.set_meta(key.into(), value.clone());
         }
     }
 }
@@ -0,0 +1,158 @@
+use gdnative::{GlobalConstants, Variant};
+use std::collections::HashMap;
+
+use super::position_key;
+use super::visual_geometry::MeshSurface;
+use quarchitect::game_data::{Properties, Property};
+use quarchitect::Vector3;
+
+#[cfg(test)]
+mod tests;
+
+const PHONG_SETTING: &str = "qodot/geometry/phong";
+const PHONG_ANGLE_SETTING: &str = "qodot/geometry/phong_angle";
+
+// Matches the ericw-tools default for _phong_angle
+const DEFAULT_PHONG_ANGLE: f32 = 89.0;
+
+// Lists the smoothing defaults in the project settings, so they can be found and edited there.
+// Initial values aren't saved to project.godot until they're changed.
+pub fn register_project_settings() {
+    let mut project_settings = gdnative::ProjectSettings::godot_singleton();
+
+    let settings = [
+        (
+            PHONG_SETTING,
+            Variant::from_bool(false),
+            GlobalConstants::TYPE_BOOL,
+            None,
+        ),
+        (
+            PHONG_ANGLE_SETTING,
+            Variant::from_f64(f64::from(DEFAULT_PHONG_ANGLE)),
+            GlobalConstants::TYPE_REAL,
+            Some("0,180,0.1"),
+        ),
+    ];
+
+    for (name, default, property_type, range) in settings.iter() {
+        if !project_settings.has_setting((*name).into()) {
+            project_settings.set_setting((*name).into(), default.clone());
+        }
+        project_settings.set_initial_value((*name).into(), default.clone());
+
+        let hint = range.map(|_| GlobalConstants::PROPERTY_HINT_RANGE);
+        project_settings.add_property_info(crate::util::build_property_dictionary(
+            name,
+            *property_type,
+            hint,
+            *range,
+            None,
+        ));
+    }
+}
+
+// Reads the project-level smoothing default, returning the angle if smoothing is enabled
+pub fn default_phong_angle() -> Option<f32> {
+    let project_settings = gdnative::ProjectSettings::godot_singleton();
+
+    if !project_settings.has_setting(PHONG_SETTING.into()) {
+        return None;
+    }
+
+    let phong = project_settings.get_setting(PHONG_SETTING.into());
+    if !phong.try_to_bool().unwrap_or(false) {
+        return None;
+    }
+
+    if project_settings.has_setting(PHONG_ANGLE_SETTING.into()) {
+        let phong_angle = project_settings.get_setting(PHONG_ANGLE_SETTING.into());
+        if let Some(phong_angle) = phong_angle.try_to_f64() {
+            return Some(phong_angle as f32);
+        }
+    }
+
+    Some(DEFAULT_PHONG_ANGLE)
+}
+
+// Resolves the smoothing angle for an entity from its _phong and _phong_angle keys
+pub fn phong_angle(
+    actor: &quarchitect::scene_tree::Actor,
+    default_phong_angle: Option<f32>,
+) -> Option<f32> {
+    let Properties(properties) = &actor.properties;
+
+    let phong = properties.get("_phong").and_then(property_to_f32);
+    let phong_angle = properties.get("_phong_angle").and_then(property_to_f32);
+
+    match phong {
+        Some(phong) if phong != 0.0 => Some(phong_angle.unwrap_or(DEFAULT_PHONG_ANGLE)),
+        Some(_) => None,
+        None => default_phong_angle.map(|default| phong_angle.unwrap_or(default)),
+    }
+}
+
+fn property_to_f32(property: &Property) -> Option<f32> {
+    match property {
+        Property::Integer(value) => Some(*value as f32),
+        Property::Float(value) => Some(*value as f32),
+        Property::Choices(value) => Some(*value as f32),
+        Property::String(value) => value.trim().parse::<f32>().ok(),
+        _ => None,
+    }
+}
+
+// Averages the normals of adjacent faces meeting below the given angle, then
+// re-orthogonalizes each tangent against its new normal
+pub fn smooth_normals(surfaces: &mut [MeshSurface], angle: f32) {
+    let threshold = angle.to_radians().cos();
+
+    // Group every vertex in the mesh by position, across surfaces
+    let mut groups: HashMap<(i64, i64, i64), Vec<(usize, usize)>> = HashMap::new();
+    for (surface_index, surface) in surfaces.iter().enumerate() {
+        for (vertex_index, vertex) in surface.vertices.iter().enumerate() {
+            groups
+                .entry(position_key(*vertex))
+                .or_insert_with(Vec::new)
+                .push((surface_index, vertex_index));
+        }
+    }
+
+    for members in groups.values() {
+        // Collect distinct face normals so large faces don't outweigh small ones
+        let mut face_normals: Vec<Vector3> = Vec::new();
+        for (surface_index, vertex_index) in members {
+            let normal = surfaces[*surface_index].normals[*vertex_index];
+            if !face_normals.iter().any(|other| other.dot(normal) > 0.9999) {
+                face_normals.push(normal);
+            }
+        }
+
+        if face_normals.len() < 2 {
+            continue;
+        }
+
+        for (surface_index, vertex_index) in members {
+            let surface = &mut surfaces[*surface_index];
+            let normal = surface.normals[*vertex_index];
+
+            let smoothed = face_normals
+                .iter()
+                .filter(|face_normal| face_normal.dot(normal) >= threshold)
+                .fold(Vector3::default(), |acc, face_normal| acc + *face_normal);
+
+            if smoothed.length() <= std::f32::EPSILON {
+                continue;
+            }
+
+            let smoothed = smoothed.normalize();
+            surface.normals[*vertex_index] = smoothed;
+
+            let (tangent, flip_binormal) = surface.tangents[*vertex_index];
+            let tangent = tangent - smoothed * smoothed.dot(tangent);
+            if tangent.length() > std::f32::EPSILON {
+                surface.tangents[*vertex_index] = (tangent.normalize(), flip_binormal);
+            }
+        }
+    }
+}
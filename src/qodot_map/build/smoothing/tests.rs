@@ -0,0 +1,102 @@
+use super::{smooth_normals, MeshSurface};
+use quarchitect::Vector3;
+
+// A single triangle, sharing its edge along the Y axis with any other face built here
+fn face(normal: Vector3, tip: Vector3) -> MeshSurface {
+    let mut surface = MeshSurface::empty(Some("wall".into()), false, false);
+    for vertex in &[
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 64.0, 0.0),
+        tip,
+    ] {
+        surface.vertices.push(*vertex);
+        surface.normals.push(normal);
+        surface.tangents.push((Vector3::new(0.0, 1.0, 0.0), 1.0));
+    }
+    surface.indices = vec![0, 1, 2];
+    surface
+}
+
+// A floor facing +Z and a wall facing +X, meeting at a right angle
+fn corner() -> Vec<MeshSurface> {
+    vec![
+        face(Vector3::new(0.0, 0.0, 1.0), Vector3::new(64.0, 0.0, 0.0)),
+        face(Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 64.0)),
+    ]
+}
+
+fn assert_close(actual: Vector3, expected: Vector3) {
+    assert!(
+        (actual - expected).length() < 0.0001,
+        "Expected {:?}, got {:?}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn corners_wider_than_the_angle_stay_sharp() {
+    let mut surfaces = corner();
+    smooth_normals(&mut surfaces, 89.0);
+
+    for normal in &surfaces[0].normals {
+        assert_close(*normal, Vector3::new(0.0, 0.0, 1.0));
+    }
+    for normal in &surfaces[1].normals {
+        assert_close(*normal, Vector3::new(1.0, 0.0, 0.0));
+    }
+}
+
+#[test]
+fn corners_within_the_angle_are_averaged() {
+    let mut surfaces = corner();
+    smooth_normals(&mut surfaces, 91.0);
+
+    let smoothed = Vector3::new(1.0, 0.0, 1.0).normalize();
+    for surface in &surfaces {
+        assert_close(surface.normals[0], smoothed);
+        assert_close(surface.normals[1], smoothed);
+    }
+
+    // Unshared vertices keep their face normal
+    assert_close(surfaces[0].normals[2], Vector3::new(0.0, 0.0, 1.0));
+    assert_close(surfaces[1].normals[2], Vector3::new(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn shallow_bends_are_smoothed() {
+    let tilted = Vector3::new(-0.2, 0.0, 1.0).normalize();
+    let mut surfaces = vec![
+        face(Vector3::new(0.0, 0.0, 1.0), Vector3::new(64.0, 0.0, 0.0)),
+        face(tilted, Vector3::new(-64.0, 0.0, -12.8)),
+    ];
+    smooth_normals(&mut surfaces, 89.0);
+
+    let smoothed = (Vector3::new(0.0, 0.0, 1.0) + tilted).normalize();
+    for surface in &surfaces {
+        assert_close(surface.normals[0], smoothed);
+        assert_close(surface.normals[1], smoothed);
+    }
+}
+
+#[test]
+fn tangents_stay_perpendicular_to_smoothed_normals() {
+    let mut surfaces = corner();
+    for surface in &mut surfaces {
+        for tangent in &mut surface.tangents {
+            *tangent = (Vector3::new(-1.0, 1.0, 1.0).normalize(), -1.0);
+        }
+    }
+    smooth_normals(&mut surfaces, 91.0);
+
+    for surface in &surfaces {
+        for vertex_index in 0..2 {
+            let normal = surface.normals[vertex_index];
+            let (tangent, flip_binormal) = surface.tangents[vertex_index];
+
+            assert!(normal.dot(tangent).abs() < 0.0001);
+            assert!((tangent.length() - 1.0).abs() < 0.0001);
+            assert_eq!(flip_binormal, -1.0);
+        }
+    }
+}
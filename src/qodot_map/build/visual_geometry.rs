@@ -1,148 +1,212 @@
 use gdnative::{
     ArrayMesh, ColorArray, Float32Array, GodotString, Int32Array, Material, Mesh, MeshInstance,
-    Node, Spatial, Variant, VariantArray, Vector2Array, Vector3Array,
+    Node, Resource, Spatial, Variant, VariantArray, Vector2Array, Vector3Array,
 };
 use quarchitect::Vector3;
 use std::collections::HashMap;
 
-pub fn spawn_mesh_instance(
-    owner: Spatial,
-    parent: &Option<Node>,
+pub struct MeshSurface {
+    pub texture: Option<String>,
+    pub vertices: Vec<Vector3>,
+    pub normals: Vec<Vector3>,
+    pub tangents: Vec<(Vector3, f32)>,
+    pub colors: Option<Vec<quarchitect::Color>>,
+    pub uvs: Option<Vec<(f32, f32)>>,
+    pub indices: Vec<usize>,
+}
+
+impl MeshSurface {
+    pub fn empty(texture: Option<String>, has_colors: bool, has_uvs: bool) -> MeshSurface {
+        MeshSurface {
+            texture,
+            vertices: Vec::new(),
+            normals: Vec::new(),
+            tangents: Vec::new(),
+            colors: if has_colors { Some(Vec::new()) } else { None },
+            uvs: if has_uvs { Some(Vec::new()) } else { None },
+            indices: Vec::new(),
+        }
+    }
+}
+
+pub fn mesh_surfaces(
     visual_geometry: &quarchitect::scene_tree::VisualGeometry,
-) -> Option<MeshInstance> {
+) -> Vec<MeshSurface> {
+    match visual_geometry {
+        quarchitect::scene_tree::VisualGeometry::Mesh(visual_mesh) => visual_mesh
+            .surfaces
+            .iter()
+            .map(|surface| MeshSurface {
+                texture: surface.texture.clone(),
+                vertices: surface.vertices.clone(),
+                normals: surface.normals.clone(),
+                tangents: surface.tangents.clone(),
+                colors: surface.colors.clone(),
+                uvs: surface
+                    .uvs
+                    .as_ref()
+                    .map(|uvs| uvs.iter().map(|uv| (uv.x(), uv.y())).collect()),
+                indices: surface.indices.clone(),
+            })
+            .collect(),
+        quarchitect::scene_tree::VisualGeometry::None => Vec::new(),
+    }
+}
+
+pub fn spawn_mesh_instance(owner: Spatial, parent: &Option<Node>) -> Option<MeshInstance> {
     let mut parent: Node = match parent {
         Some(p) => *p,
         None => unsafe { owner.cast::<Node>().unwrap() },
     };
 
-    match visual_geometry {
-        quarchitect::scene_tree::VisualGeometry::Mesh(_visual_mesh) => {
-            // Create mesh and mesh instance
-            let mesh = ArrayMesh::new();
-            let mut mesh_instance = MeshInstance::new();
-            unsafe {
-                mesh_instance.set_mesh(mesh.cast::<Mesh>());
-                crate::QodotMap::add_child_editor(owner, &mut parent, mesh_instance.cast::<Node>());
-                Some(mesh_instance)
-            }
-        }
-        quarchitect::scene_tree::VisualGeometry::None => None,
+    // Create mesh and mesh instance
+    let mesh = ArrayMesh::new();
+    let mut mesh_instance = MeshInstance::new();
+    unsafe {
+        mesh_instance.set_mesh(mesh.cast::<Mesh>());
+        crate::QodotMap::add_child_editor(owner, &mut parent, mesh_instance.cast::<Node>());
+        Some(mesh_instance)
     }
 }
 
+// Returns the vertex counts before and after welding, which are equal if it's disabled
 pub fn populate_mesh_geometry(
-    visual_geometry: &quarchitect::scene_tree::VisualGeometry,
+    surfaces: &mut Vec<MeshSurface>,
     mesh_instance: Option<MeshInstance>,
     origin: Vector3,
-    inverse_scale_factor: f32,
-) {
-    let origin = super::quake_point_to_godot_point(origin, inverse_scale_factor);
+    coordinates: &super::coordinates::CoordinateSystem,
+    weld_vertices: bool,
+) -> (usize, usize) {
+    let origin = coordinates.point(origin);
 
-    match visual_geometry {
-        quarchitect::scene_tree::VisualGeometry::Mesh(visual_mesh) => {
-            let mesh_instance = mesh_instance.unwrap();
-            let mut mesh;
-            unsafe {
-                mesh = mesh_instance
-                    .get_mesh()
-                    .unwrap()
-                    .cast::<ArrayMesh>()
-                    .unwrap();
-            }
+    let mesh_instance = match mesh_instance {
+        Some(mesh_instance) => mesh_instance,
+        None => return (0, 0),
+    };
 
-            for surface in visual_mesh.surfaces.iter() {
-                let mut arrays = VariantArray::new();
-                let blend_shapes = VariantArray::new();
+    let vertex_counts = if weld_vertices {
+        super::welding::clean_surfaces(surfaces)
+    } else {
+        let vertex_count = surfaces.iter().map(|surface| surface.vertices.len()).sum();
+        (vertex_count, vertex_count)
+    };
 
-                // Vertices
-                arrays.push(&Variant::from_vector3_array(&surface.vertices.iter().fold(
-                    Vector3Array::new(),
-                    |mut acc, next| {
-                        let vertex = super::quake_point_to_godot_point(*next, inverse_scale_factor);
-                        let vertex = super::godot_vector3_from_quarchitect_vector3(vertex - origin);
-                        acc.push(&vertex);
-                        acc
-                    },
-                )));
+    let mut mesh;
+    unsafe {
+        mesh = mesh_instance
+            .get_mesh()
+            .unwrap()
+            .cast::<ArrayMesh>()
+            .unwrap();
+    }
+
+    for surface in surfaces.iter() {
+        let mut arrays = VariantArray::new();
+        let blend_shapes = VariantArray::new();
+
+        // Vertices
+        arrays.push(&Variant::from_vector3_array(&surface.vertices.iter().fold(
+            Vector3Array::new(),
+            |mut acc, next| {
+                let vertex = coordinates.point(*next);
+                let vertex = super::godot_vector3_from_quarchitect_vector3(vertex - origin);
+                acc.push(&vertex);
+                acc
+            },
+        )));
 
-                // Normals
-                arrays.push(&Variant::from_vector3_array(&surface.normals.iter().fold(
-                    Vector3Array::new(),
+        // Normals
+        arrays.push(&Variant::from_vector3_array(&surface.normals.iter().fold(
+            Vector3Array::new(),
+            |mut acc, next| {
+                let normal = coordinates.normal(*next);
+                let normal = super::godot_vector3_from_quarchitect_vector3(normal);
+                acc.push(&normal);
+                acc
+            },
+        )));
+
+        // Tangents
+        arrays.push(&Variant::from_float32_array(&surface.tangents.iter().fold(
+            Float32Array::new(),
+            |mut acc, next| {
+                let (tangent, flip_binormal) = coordinates.tangent(next.0, next.1);
+                acc.push(tangent.x());
+                acc.push(tangent.y());
+                acc.push(tangent.z());
+                acc.push(flip_binormal);
+                acc
+            },
+        )));
+
+        // Colors
+        match &surface.colors {
+            Some(surface_colors) => {
+                arrays.push(&Variant::from_color_array(&surface_colors.iter().fold(
+                    ColorArray::new(),
                     |mut acc, next| {
-                        let normal = super::quake_direction_to_godot_direction(*next);
-                        let normal = super::godot_vector3_from_quarchitect_vector3(normal);
-                        acc.push(&normal);
+                        let color = gdnative::Color::rgb(next.r, next.g, next.b);
+                        acc.push(&color);
                         acc
                     },
                 )));
+            }
+            None => arrays.push(&Variant::new()),
+        };
 
-                // Tangents
-                arrays.push(&Variant::from_float32_array(&surface.tangents.iter().fold(
-                    Float32Array::new(),
-                    |mut acc, next| {
-                        let (tangent, flip_binormal) = next;
-                        let tangent = super::quake_direction_to_godot_direction(*tangent);
-                        acc.push(tangent.x());
-                        acc.push(tangent.y());
-                        acc.push(tangent.z());
-                        acc.push(*flip_binormal);
+        // UVs
+        match &surface.uvs {
+            Some(surface_uvs) => {
+                arrays.push(&Variant::from_vector2_array(&surface_uvs.iter().fold(
+                    Vector2Array::new(),
+                    |mut acc, (u, v)| {
+                        let uv = gdnative::Vector2::new(*u, *v);
+                        acc.push(&uv);
                         acc
                     },
                 )));
+            }
+            None => arrays.push(&Variant::new()),
+        }
 
-                // Colors
-                match &surface.colors {
-                    Some(surface_colors) => {
-                        arrays.push(&Variant::from_color_array(&surface_colors.iter().fold(
-                            ColorArray::new(),
-                            |mut acc, next| {
-                                let color = gdnative::Color::rgb(next.r, next.g, next.b);
-                                acc.push(&color);
-                                acc
-                            },
-                        )));
-                    }
-                    None => arrays.push(&Variant::new()),
-                };
-
-                // UVs
-                match &surface.uvs {
-                    Some(surface_uvs) => {
-                        arrays.push(&Variant::from_vector2_array(&surface_uvs.iter().fold(
-                            Vector2Array::new(),
-                            |mut acc, next| {
-                                let uv = gdnative::Vector2::new(next.x(), next.y());
-                                acc.push(&uv);
-                                acc
-                            },
-                        )));
+        arrays.push(&Variant::new()); // UV2s
+        arrays.push(&Variant::new()); // Bones
+        arrays.push(&Variant::new()); // Weights
+
+        // Indices, reversing winding if the coordinate system mirrors the map
+        arrays.push(&Variant::from_int32_array(
+            &surface.indices.chunks(3).fold(Int32Array::new(), |mut acc, next| {
+                if coordinates.is_mirrored() && next.len() == 3 {
+                    acc.push(next[0] as i32);
+                    acc.push(next[2] as i32);
+                    acc.push(next[1] as i32);
+                } else {
+                    for index in next {
+                        acc.push(*index as i32);
                     }
-                    None => arrays.push(&Variant::new()),
                 }
+                acc
+            }),
+        ));
 
-                arrays.push(&Variant::new()); // UV2s
-                arrays.push(&Variant::new()); // Bones
-                arrays.push(&Variant::new()); // Weights
+        // Add Surface
+        mesh.add_surface_from_arrays(Mesh::PRIMITIVE_TRIANGLES, arrays, blend_shapes, 31744);
+    }
 
-                // Indices
-                arrays.push(&Variant::from_int32_array(&surface.indices.iter().fold(
-                    Int32Array::new(),
-                    |mut acc, next| {
-                        acc.push(*next as i32);
-                        acc
-                    },
-                )));
+    vertex_counts
+}
 
-                // Add Surface
-                mesh.add_surface_from_arrays(
-                    Mesh::PRIMITIVE_TRIANGLES,
-                    arrays,
-                    blend_shapes,
-                    31744,
-                );
-            }
+pub fn save_mesh(
+    resource_cache: &super::resource_cache::ResourceCache,
+    mesh_instance: Option<MeshInstance>,
+    name: &str,
+) {
+    if let Some(mesh_instance) = mesh_instance {
+        let mesh;
+        unsafe {
+            mesh = mesh_instance.get_mesh();
         }
-        quarchitect::scene_tree::VisualGeometry::None => (),
+        resource_cache.save(mesh.and_then(|mesh| mesh.cast::<Resource>()), name);
     }
 }
 
@@ -189,12 +253,11 @@ pub fn populate_mesh_materials(
     default_material: Option<Material>,
     default_spatial_material_texture_param: i32,
     default_shader_material_texture_param: &GodotString,
-    visual_geometry: &quarchitect::scene_tree::VisualGeometry,
+    surfaces: &[MeshSurface],
     mesh_instance: Option<MeshInstance>,
 ) {
-    match visual_geometry {
-        quarchitect::scene_tree::VisualGeometry::Mesh(visual_mesh) => {
-            let mesh_instance = mesh_instance.unwrap();
+    match mesh_instance {
+        Some(mesh_instance) => {
             let mut mesh;
             unsafe {
                 mesh = mesh_instance
@@ -213,7 +276,7 @@ pub fn populate_mesh_materials(
             let texture_pairs = iter_slots.zip(iter_patterns);
             let texture_pairs: HashMap<i64, String> = texture_pairs.collect();
 
-            for (i, surface) in visual_mesh.surfaces.iter().enumerate() {
+            for (i, surface) in surfaces.iter().enumerate() {
                 if let Some(texture) = &surface.texture {
                     let index = i as i64;
 
@@ -270,6 +333,6 @@ pub fn populate_mesh_materials(
                 }
             }
         }
-        quarchitect::scene_tree::VisualGeometry::None => (),
+        None => (),
     }
 }
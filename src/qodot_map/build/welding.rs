@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet};
+
+use super::visual_geometry::MeshSurface;
+use super::{position_key, POSITION_EPSILON};
+use quarchitect::Vector3;
+
+#[cfg(test)]
+mod tests;
+
+// Attribute tolerance used when deciding whether two vertices can be merged
+const ATTRIBUTE_EPSILON: f32 = 0.0001;
+
+// Triangles with less area than this (in square map units) are discarded
+const AREA_EPSILON: f32 = 0.0001;
+
+// Cell size of the spatial hash used for T-junction lookups
+const GRID_CELL_SIZE: f32 = 64.0;
+
+// Upper bound on splits per source triangle, guarding against pathological input
+const MAX_SPLITS: usize = 64;
+
+type Cell = (i32, i32, i32);
+
+// Repairs T-junctions, drops zero-area triangles and welds identical vertices.
+// Returns the vertex count before and after processing.
+pub fn clean_surfaces(surfaces: &mut Vec<MeshSurface>) -> (usize, usize) {
+    let before = surfaces.iter().map(|surface| surface.vertices.len()).sum();
+
+    let grid = PositionGrid::new(surfaces);
+    for surface in surfaces.iter_mut() {
+        fix_t_junctions(surface, &grid);
+        remove_degenerate_triangles(surface);
+        weld_vertices(surface);
+        remove_degenerate_triangles(surface);
+    }
+
+    surfaces.retain(|surface| !surface.indices.is_empty());
+
+    let after = surfaces.iter().map(|surface| surface.vertices.len()).sum();
+    (before, after)
+}
+
+struct PositionGrid {
+    cells: HashMap<Cell, Vec<Vector3>>,
+}
+
+impl PositionGrid {
+    fn new(surfaces: &[MeshSurface]) -> PositionGrid {
+        let mut seen: HashSet<(i64, i64, i64)> = HashSet::new();
+        let mut cells: HashMap<Cell, Vec<Vector3>> = HashMap::new();
+
+        for surface in surfaces {
+            for vertex in &surface.vertices {
+                if seen.insert(position_key(*vertex)) {
+                    cells
+                        .entry(grid_cell(*vertex))
+                        .or_insert_with(Vec::new)
+                        .push(*vertex);
+                }
+            }
+        }
+
+        PositionGrid { cells }
+    }
+
+    // Returns the interpolants of every known position lying strictly inside the segment
+    fn points_on_segment(&self, a: Vector3, b: Vector3) -> Vec<f32> {
+        let edge = b - a;
+        let length = edge.length();
+        if length <= POSITION_EPSILON * 2.0 {
+            return Vec::new();
+        }
+
+        let mut visited: HashSet<Cell> = HashSet::new();
+        let mut interpolants: Vec<f32> = Vec::new();
+
+        let steps = (length / (GRID_CELL_SIZE * 0.5)).ceil() as usize;
+        for step in 0..=steps {
+            let sample = a + edge * (step as f32 / steps.max(1) as f32);
+            let (x, y, z) = grid_cell(sample);
+
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let cell = (x + dx, y + dy, z + dz);
+                        if !visited.insert(cell) {
+                            continue;
+                        }
+
+                        let points = match self.cells.get(&cell) {
+                            Some(points) => points,
+                            None => continue,
+                        };
+
+                        for point in points {
+                            let t = (*point - a).dot(edge) / (length * length);
+                            if t * length <= POSITION_EPSILON
+                                || (1.0 - t) * length <= POSITION_EPSILON
+                            {
+                                continue;
+                            }
+
+                            let closest = a + edge * t;
+                            if (*point - closest).length() <= POSITION_EPSILON {
+                                interpolants.push(t);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        interpolants.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        interpolants.dedup_by(|a, b| ((*a - *b) * length).abs() <= POSITION_EPSILON);
+        interpolants
+    }
+}
+
+fn fix_t_junctions(surface: &mut MeshSurface, grid: &PositionGrid) {
+    let mut queue: Vec<([usize; 3], usize)> = surface
+        .indices
+        .chunks(3)
+        .filter(|triangle| triangle.len() == 3)
+        .map(|triangle| ([triangle[0], triangle[1], triangle[2]], 0))
+        .collect();
+    queue.reverse();
+
+    let mut indices: Vec<usize> = Vec::with_capacity(surface.indices.len());
+
+    while let Some((triangle, splits)) = queue.pop() {
+        let mut split = false;
+
+        if splits < MAX_SPLITS {
+            for edge in 0..3 {
+                let a = triangle[edge];
+                let b = triangle[(edge + 1) % 3];
+                let c = triangle[(edge + 2) % 3];
+
+                let interpolants = grid.points_on_segment(surface.vertices[a], surface.vertices[b]);
+
+                if interpolants.is_empty() {
+                    continue;
+                }
+
+                // Fan the triangle out from the opposite corner through each point on the edge
+                let mut previous = a;
+                for t in interpolants {
+                    let next = push_interpolated_vertex(surface, a, b, t);
+                    queue.push(([previous, next, c], splits + 1));
+                    previous = next;
+                }
+                queue.push(([previous, b, c], splits + 1));
+
+                split = true;
+                break;
+            }
+        }
+
+        if !split {
+            indices.extend_from_slice(&triangle);
+        }
+    }
+
+    surface.indices = indices;
+}
+
+fn push_interpolated_vertex(surface: &mut MeshSurface, a: usize, b: usize, t: f32) -> usize {
+    let lerp = |from: Vector3, to: Vector3| from + (to - from) * t;
+
+    surface
+        .vertices
+        .push(lerp(surface.vertices[a], surface.vertices[b]));
+
+    let normal = lerp(surface.normals[a], surface.normals[b]);
+    surface
+        .normals
+        .push(if normal.length() > std::f32::EPSILON {
+            normal.normalize()
+        } else {
+            surface.normals[a]
+        });
+
+    let (tangent_a, flip_binormal) = surface.tangents[a];
+    let (tangent_b, _) = surface.tangents[b];
+    let tangent = lerp(tangent_a, tangent_b);
+    surface.tangents.push((
+        if tangent.length() > std::f32::EPSILON {
+            tangent.normalize()
+        } else {
+            tangent_a
+        },
+        flip_binormal,
+    ));
+
+    if let Some(colors) = &mut surface.colors {
+        let (from, to) = (colors[a], colors[b]);
+        colors.push(quarchitect::Color::new(
+            from.r + (to.r - from.r) * t,
+            from.g + (to.g - from.g) * t,
+            from.b + (to.b - from.b) * t,
+        ));
+    }
+
+    if let Some(uvs) = &mut surface.uvs {
+        let ((from_u, from_v), (to_u, to_v)) = (uvs[a], uvs[b]);
+        uvs.push((from_u + (to_u - from_u) * t, from_v + (to_v - from_v) * t));
+    }
+
+    surface.vertices.len() - 1
+}
+
+fn remove_degenerate_triangles(surface: &mut MeshSurface) {
+    let vertices = &surface.vertices;
+    let indices: Vec<usize> = surface
+        .indices
+        .chunks(3)
+        .filter(|triangle| {
+            if triangle.len() < 3 {
+                return false;
+            }
+
+            if triangle[0] == triangle[1]
+                || triangle[1] == triangle[2]
+                || triangle[0] == triangle[2]
+            {
+                return false;
+            }
+
+            let (a, b, c) = (
+                vertices[triangle[0]],
+                vertices[triangle[1]],
+                vertices[triangle[2]],
+            );
+            (b - a).cross(c - a).length() * 0.5 > AREA_EPSILON
+        })
+        .flatten()
+        .cloned()
+        .collect();
+
+    surface.indices = indices;
+}
+
+fn weld_vertices(surface: &mut MeshSurface) {
+    let mut welded = MeshSurface::empty(
+        surface.texture.clone(),
+        surface.colors.is_some(),
+        surface.uvs.is_some(),
+    );
+
+    let mut lookup: HashMap<Vec<i64>, usize> = HashMap::new();
+    let mut remap: Vec<Option<usize>> = vec![None; surface.vertices.len()];
+
+    for index in surface.indices.iter() {
+        if remap[*index].is_some() {
+            continue;
+        }
+
+        let key = vertex_key(surface, *index);
+        let welded_index = match lookup.get(&key) {
+            Some(welded_index) => *welded_index,
+            None => {
+                welded.vertices.push(surface.vertices[*index]);
+                welded.normals.push(surface.normals[*index]);
+                welded.tangents.push(surface.tangents[*index]);
+                if let (Some(colors), Some(surface_colors)) = (&mut welded.colors, &surface.colors)
+                {
+                    colors.push(surface_colors[*index]);
+                }
+                if let (Some(uvs), Some(surface_uvs)) = (&mut welded.uvs, &surface.uvs) {
+                    uvs.push(surface_uvs[*index]);
+                }
+
+                let welded_index = welded.vertices.len() - 1;
+                lookup.insert(key, welded_index);
+                welded_index
+            }
+        };
+
+        remap[*index] = Some(welded_index);
+    }
+
+    welded.indices = surface
+        .indices
+        .iter()
+        .flat_map(|index| remap[*index])
+        .collect();
+
+    *surface = welded;
+}
+
+fn vertex_key(surface: &MeshSurface, index: usize) -> Vec<i64> {
+    let quantize = |value: f32, epsilon: f32| (value / epsilon).round() as i64;
+
+    let vertex = surface.vertices[index];
+    let normal = surface.normals[index];
+    let (tangent, flip_binormal) = surface.tangents[index];
+
+    let mut key = vec![
+        quantize(vertex.x(), POSITION_EPSILON),
+        quantize(vertex.y(), POSITION_EPSILON),
+        quantize(vertex.z(), POSITION_EPSILON),
+        quantize(normal.x(), ATTRIBUTE_EPSILON),
+        quantize(normal.y(), ATTRIBUTE_EPSILON),
+        quantize(normal.z(), ATTRIBUTE_EPSILON),
+        quantize(tangent.x(), ATTRIBUTE_EPSILON),
+        quantize(tangent.y(), ATTRIBUTE_EPSILON),
+        quantize(tangent.z(), ATTRIBUTE_EPSILON),
+        quantize(flip_binormal, ATTRIBUTE_EPSILON),
+    ];
+
+    if let Some(colors) = &surface.colors {
+        let color = colors[index];
+        key.push(quantize(color.r, ATTRIBUTE_EPSILON));
+        key.push(quantize(color.g, ATTRIBUTE_EPSILON));
+        key.push(quantize(color.b, ATTRIBUTE_EPSILON));
+    }
+
+    if let Some(uvs) = &surface.uvs {
+        let (u, v) = uvs[index];
+        key.push(quantize(u, ATTRIBUTE_EPSILON));
+        key.push(quantize(v, ATTRIBUTE_EPSILON));
+    }
+
+    key
+}
+
+fn grid_cell(position: Vector3) -> Cell {
+    (
+        (position.x() / GRID_CELL_SIZE).floor() as i32,
+        (position.y() / GRID_CELL_SIZE).floor() as i32,
+        (position.z() / GRID_CELL_SIZE).floor() as i32,
+    )
+}
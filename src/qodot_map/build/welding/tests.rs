@@ -0,0 +1,76 @@
+use super::{clean_surfaces, MeshSurface};
+use quarchitect::Vector3;
+
+// A flat surface facing +Z, with UVs following its position so shared corners can weld
+fn surface(points: &[(f32, f32)], indices: &[usize]) -> MeshSurface {
+    let mut surface = MeshSurface::empty(Some("wall".into()), false, true);
+    for (x, y) in points {
+        surface.vertices.push(Vector3::new(*x, *y, 0.0));
+        surface.normals.push(Vector3::new(0.0, 0.0, 1.0));
+        surface.tangents.push((Vector3::new(1.0, 0.0, 0.0), 1.0));
+        surface.uvs.as_mut().unwrap().push((*x / 64.0, *y / 64.0));
+    }
+    surface.indices = indices.to_vec();
+    surface
+}
+
+fn quad(x: f32, y: f32, width: f32, height: f32) -> Vec<(f32, f32)> {
+    vec![
+        (x, y),
+        (x + width, y),
+        (x + width, y + height),
+        (x, y + height),
+    ]
+}
+
+fn find_vertex(surface: &MeshSurface, x: f32, y: f32) -> Option<usize> {
+    surface
+        .vertices
+        .iter()
+        .position(|vertex| (vertex.x() - x).abs() < 0.001 && (vertex.y() - y).abs() < 0.001)
+}
+
+#[test]
+fn welds_quads_sharing_an_edge() {
+    let mut points = quad(0.0, 0.0, 64.0, 64.0);
+    points.extend(quad(64.0, 0.0, 64.0, 64.0));
+    let mut surfaces = vec![surface(&points, &[0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7])];
+
+    assert_eq!(clean_surfaces(&mut surfaces), (8, 6));
+    assert_eq!(surfaces[0].indices.len(), 12);
+    assert_eq!(surfaces[0].normals.len(), 6);
+    assert_eq!(surfaces[0].uvs.as_ref().unwrap().len(), 6);
+}
+
+#[test]
+fn splits_t_junctions() {
+    // Two small quads meet halfway along the large quad's top edge
+    let large = surface(&quad(0.0, 0.0, 128.0, 64.0), &[0, 1, 2, 0, 2, 3]);
+    let mut points = quad(0.0, 64.0, 64.0, 64.0);
+    points.extend(quad(64.0, 64.0, 64.0, 64.0));
+    let small = surface(&points, &[0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7]);
+    let mut surfaces = vec![large, small];
+
+    clean_surfaces(&mut surfaces);
+
+    assert_eq!(surfaces[0].indices.len(), 9);
+
+    // The new vertex takes its UV from its position along the split edge
+    let index = find_vertex(&surfaces[0], 64.0, 64.0).expect("Expected a vertex at the junction");
+    let (u, v) = surfaces[0].uvs.as_ref().unwrap()[index];
+    assert!((u - 1.0).abs() < 0.001 && (v - 1.0).abs() < 0.001);
+}
+
+#[test]
+fn removes_zero_area_slivers() {
+    let sliver = surface(&[(0.0, 0.0), (64.0, 0.0), (128.0, 0.0)], &[0, 1, 2]);
+    let triangle = surface(&[(0.0, 64.0), (64.0, 64.0), (0.0, 128.0)], &[0, 1, 2]);
+    let mut surfaces = vec![sliver, triangle];
+
+    clean_surfaces(&mut surfaces);
+
+    // The sliver's surface has nothing left, so it's dropped entirely
+    assert_eq!(surfaces.len(), 1);
+    assert_eq!(surfaces[0].indices, vec![0, 1, 2]);
+    assert!(find_vertex(&surfaces[0], 0.0, 128.0).is_some());
+}
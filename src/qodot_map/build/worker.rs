@@ -1,11 +1,12 @@
 #![allow(clippy::transmute_ptr_to_ptr)] // Suppress gdnative clippy warnings
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::texture_loader::TextureInfo;
 use gdnative::{
     godot_error, godot_print, godot_wrap_method_inner, godot_wrap_method_parameter_count, methods,
-    FromVariant, GodotString, Instance, Map, Material, NativeClass, Node, Spatial, Variant
+    FromVariant, GodotString, Instance, Map, Material, NativeClass, Node, Spatial, StringArray,
+    Variant,
 };
 use quarchitect::scene_tree::SceneTreeNode;
 
@@ -15,11 +16,15 @@ enum BuildCommand {
 
 enum BuildMessage {
     Tick,
-    Complete,
+    // Entities left over from the previous build, to be removed on the main thread
+    RemoveStale(Vec<Variant>),
+    // Carries problems that didn't stop the build, for the map to report
+    Complete(Vec<String>),
+    Failed(String),
 }
 
 enum FlatSceneTree<'a> {
-    Node(&'a quarchitect::scene_tree::SceneTreeNode),
+    Node(&'a quarchitect::scene_tree::SceneTreeNode, Option<u64>),
     PushParent,
     PopParent,
 }
@@ -39,13 +44,20 @@ pub struct Config {
     default_spatial_material_texture_param: i32,
     default_shader_material_texture_param: GodotString,
     quarchitect_forge_game_data: quarchitect::game_data::forge::GameData,
+    color255_properties: HashMap<String, HashSet<String>>,
     quarchitect_game_data: quarchitect::game_data::GameData,
     chunk_size: i32,
+    coordinates: super::coordinates::CoordinateSystem,
+    trenchbroom_hierarchy: bool,
+    settings_fingerprint: u64,
+    resource_cache_directory: Option<GodotString>,
+    previous_entities: HashMap<u64, Vec<Variant>>,
 }
 
 impl Config {
     pub fn new(
         quarchitect_forge_game_data: quarchitect::game_data::forge::GameData,
+        color255_properties: HashMap<String, HashSet<String>>,
         quarchitect_game_data: quarchitect::game_data::GameData,
         map_file: GodotString,
         texture_info: HashMap<String, TextureInfo>,
@@ -54,11 +66,17 @@ impl Config {
         default_spatial_material_texture_param: i32,
         default_shader_material_texture_param: GodotString,
         chunk_size: i32,
+        coordinates: super::coordinates::CoordinateSystem,
+        trenchbroom_hierarchy: bool,
+        settings_fingerprint: u64,
+        resource_cache_directory: Option<GodotString>,
+        previous_entities: HashMap<u64, Vec<Variant>>,
     ) -> Config {
         godot_print!("TODO-2: Refactor to store default material + params in an enum");
 
         Config {
             quarchitect_forge_game_data,
+            color255_properties,
             quarchitect_game_data,
             map_file,
             texture_info,
@@ -67,6 +85,11 @@ impl Config {
             default_spatial_material_texture_param,
             default_shader_material_texture_param,
             chunk_size,
+            coordinates,
+            trenchbroom_hierarchy,
+            settings_fingerprint,
+            resource_cache_directory,
+            previous_entities,
         }
     }
 }
@@ -118,6 +141,8 @@ impl QodotBuildWorker {
     #[export]
     fn idle_frame(&mut self, mut owner: Spatial) {
         let mut done = false;
+        let mut error = String::new();
+        let mut warnings: Vec<String> = Vec::new();
 
         if let (Some(tick_tx), Some(build_rx)) = (&self.tick_tx, &self.build_rx) {
             loop {
@@ -131,8 +156,25 @@ impl QodotBuildWorker {
                                 break;
                             }
                         },
-                        BuildMessage::Complete => {
+                        BuildMessage::RemoveStale(stale_entities) => unsafe {
+                            if let Some(mut parent) = owner.get_parent() {
+                                for stale_entity in stale_entities {
+                                    if let Some(mut node) = stale_entity.try_to_object::<Node>() {
+                                        parent.remove_child(Some(node));
+                                        node.queue_free();
+                                    }
+                                }
+                            }
+                        },
+                        BuildMessage::Complete(build_warnings) => {
                             godot_print!("Build complete");
+                            warnings = build_warnings;
+                            done = true;
+                            break;
+                        }
+                        BuildMessage::Failed(err) => {
+                            godot_error!("Build failed: {}", err);
+                            error = err;
                             done = true;
                             break;
                         }
@@ -151,6 +193,20 @@ impl QodotBuildWorker {
             self.tick_tx = None;
             self.build_rx = None;
             unsafe {
+                // Failed builds still hand back any nodes the map detached
+                if let Some(mut parent) = owner.get_parent() {
+                    let warnings = warnings.iter().fold(StringArray::new(), |mut acc, warning| {
+                        acc.push(&warning.into());
+                        acc
+                    });
+                    parent.call_deferred(
+                        "build_complete".into(),
+                        &[
+                            Variant::from_str(error),
+                            Variant::from_string_array(&warnings),
+                        ],
+                    );
+                }
                 owner.queue_free();
             }
         }
@@ -168,6 +224,15 @@ impl QodotBuildWorker {
 
         let quarchitect_game_data = config.quarchitect_game_data;
         let quarchitect_forge_game_data = config.quarchitect_forge_game_data;
+        let color255_properties = config.color255_properties;
+
+        // Flattened definitions, used to fill in defaults for keys the map leaves unset
+        let definitions: HashMap<String, quarchitect::game_data::forge::Entity> =
+            quarchitect_forge_game_data
+                .definitions
+                .iter()
+                .map(|definition| (definition.class_name.clone(), definition.clone()))
+                .collect();
 
         let default_material = config.default_material;
         let default_spatial_material_texture_param = config.default_spatial_material_texture_param;
@@ -194,6 +259,12 @@ impl QodotBuildWorker {
 
         let texture_blacklist = config.texture_blacklist;
         let chunk_size = config.chunk_size;
+        let coordinates = config.coordinates;
+        let trenchbroom_hierarchy = config.trenchbroom_hierarchy;
+        let settings_fingerprint = config.settings_fingerprint;
+        let resource_cache_directory = config.resource_cache_directory;
+        let mut previous_entities = config.previous_entities;
+        let default_phong_angle = super::smoothing::default_phong_angle();
 
         let owner = Variant::from_object(&owner);
         std::thread::spawn(move || {
@@ -213,151 +284,336 @@ impl QodotBuildWorker {
                 quarchitect_game_data,
             );
             let scene_tree = quarchitect::run(config);
-            let scene_tree = match scene_tree {
-                Ok(scene_tree) => scene_tree,
+            let mut scene_tree: Vec<SceneTreeNode> = match scene_tree {
+                Ok(scene_tree) => scene_tree.into_iter().collect(),
                 Err(err) => {
                     eprintln!("Build error: {}", err);
+                    if let Err(err) = build_tx.send(BuildMessage::Failed(err.to_string())) {
+                        eprintln!("Error sending message to main thread: {:?}", err);
+                    }
                     return;
                 }
             };
 
-            println!("Populating scene tree");
-            script
-                .map(|script| {
-                    let scene_tree: Vec<FlatSceneTree> = scene_tree.iter().flat_map(flatten_scene_tree_node).collect();
-                    let mut scene_tree_iter = scene_tree.into_iter();
-                    let mut current_node: Option<Node> = None;
-                    let mut parent_stack: Vec<Option<Node>>;
-                    unsafe {
-                        parent_stack = vec![owner.cast::<Node>()];
+            super::origins::apply_origin_brushes(&mut scene_tree);
+            if trenchbroom_hierarchy {
+                scene_tree = super::layers::nest_layers_and_groups(scene_tree);
+            }
+
+            // Entities whose fingerprint matches a node from the previous build keep that node
+            let mut kept_count = 0;
+            let scene_tree: Vec<FlatSceneTree> = scene_tree
+                .iter()
+                .flat_map(|node| {
+                    let fingerprint = super::fingerprint::fingerprint(node, settings_fingerprint);
+                    let previous_entity = previous_entities
+                        .get_mut(&fingerprint)
+                        .and_then(|previous_entities| previous_entities.pop());
+
+                    if previous_entity.is_some() {
+                        kept_count += 1;
+                        Vec::new()
+                    } else {
+                        flatten_scene_tree_node(node, Some(fingerprint))
                     }
+                })
+                .collect();
 
-                    loop {
-                        let mut done = false;
-                        for _i in 0..chunk_size {
-                            if let Some(scene_tree) = scene_tree_iter.next() {
-                                let scene_tree = match scene_tree {
-                                    FlatSceneTree::Node(node) => node,
-                                    FlatSceneTree::PushParent => {
-                                        parent_stack.push(current_node);
-                                        continue;
+            let stale_entities: Vec<Variant> =
+                previous_entities.into_iter().flat_map(|(_, nodes)| nodes).collect();
+            let stale_count = stale_entities.len();
+
+            // Removed before population starts, so new nodes don't collide with stale names.
+            // The main thread handles messages in order, so once the tick returns they're gone.
+            if stale_count > 0 {
+                println!("Removing stale entities");
+                if let Err(err) = build_tx.send(BuildMessage::RemoveStale(stale_entities)) {
+                    eprintln!("Error sending message to main thread: {:?}", err);
+                    return;
+                }
+                if !tick(&build_tx, &tick_rx) {
+                    return;
+                }
+            }
+
+            if kept_count > 0 {
+                println!("Kept {} unchanged entities, removed {}", kept_count, stale_count);
+            }
+
+            println!("Populating scene tree");
+            let populated = script.map(|script| {
+                let mut warnings: Vec<String> = Vec::new();
+
+                let mut scene_tree_iter = scene_tree.into_iter();
+                let mut current_node: Option<Node> = None;
+                let mut current_actor: Option<&quarchitect::scene_tree::Actor> = None;
+                let mut parent_stack: Vec<Option<Node>>;
+                unsafe {
+                    parent_stack = vec![owner.cast::<Node>()];
+                }
+                let mut actor_stack: Vec<Option<&quarchitect::scene_tree::Actor>> = vec![None];
+
+                // Actors are numbered in traversal order so cached resource names stay stable
+                let mut entity_count: usize = 0;
+                let mut current_entity: usize = 0;
+                let mut entity_stack: Vec<usize> = vec![0];
+
+                // Mesh vertices before and after welding, across the whole build
+                let mut vertex_counts: (usize, usize) = (0, 0);
+
+                let resource_cache = match &resource_cache_directory {
+                    Some(directory) => {
+                        match super::resource_cache::ResourceCache::new(&directory.to_string()) {
+                            Ok(resource_cache) => Some(resource_cache),
+                            Err(err) => {
+                                warnings.push(format!("Resource cache disabled: {}", err));
+                                None
+                            }
+                        }
+                    }
+                    None => None,
+                };
+
+                let mut batcher = if script.batch_meshes {
+                    Some(super::batching::MeshBatcher::new(script.batch_cell_size))
+                } else {
+                    None
+                };
+
+                let collision_chunking = super::collision_geometry::CollisionChunking {
+                    cell_size: script.collision_cell_size,
+                    max_convex_shapes_per_body: script.max_convex_shapes_per_body.max(0)
+                        as usize,
+                };
+
+                loop {
+                    let mut done = false;
+                    for _i in 0..chunk_size {
+                        if let Some(scene_tree) = scene_tree_iter.next() {
+                            let (scene_tree, fingerprint) = match scene_tree {
+                                FlatSceneTree::Node(node, fingerprint) => (node, fingerprint),
+                                FlatSceneTree::PushParent => {
+                                    parent_stack.push(current_node);
+                                    actor_stack.push(current_actor);
+                                    entity_stack.push(current_entity);
+                                    continue;
+                                }
+                                FlatSceneTree::PopParent => {
+                                    parent_stack.pop();
+                                    actor_stack.pop();
+                                    entity_stack.pop();
+                                    continue;
+                                }
+                            };
+
+                            match &scene_tree.data {
+                                quarchitect::scene_tree::SceneTreeType::Actor(
+                                    actor,
+                                    _children,
+                                ) => {
+                                    current_node = super::scene_tree::spawn_scene_tree_actor(
+                                        &coordinates,
+                                        owner,
+                                        &parent_stack[parent_stack.len() - 1],
+                                        scene_tree,
+                                        actor,
+                                        &definitions,
+                                        &color255_properties,
+                                        script.choices_as_names,
+                                        script.expand_flags,
+                                        script.rotate_point_entities,
+                                        script.trenchbroom_hierarchy,
+                                        &mut warnings,
+                                    );
+
+                                    if let (Some(fingerprint), Some(mut node)) =
+                                        (fingerprint, current_node)
+                                    {
+                                        unsafe {
+                                            node.set_meta(
+                                                super::fingerprint::META.into(),
+                                                Variant::from_str(
+                                                    super::fingerprint::to_string(fingerprint),
+                                                ),
+                                            );
+                                        }
                                     }
-                                    FlatSceneTree::PopParent => {
-                                        parent_stack.pop();
+
+                                    current_actor = Some(actor);
+                                    current_entity = entity_count;
+                                    entity_count += 1;
+                                }
+                                quarchitect::scene_tree::SceneTreeType::VisualGeometry(
+                                    visual_geometry,
+                                ) => {
+                                    let mut surfaces =
+                                        super::visual_geometry::mesh_surfaces(visual_geometry);
+
+                                    if surfaces.is_empty() {
                                         continue;
                                     }
-                                };
 
-                                match &scene_tree.data {
-                                    quarchitect::scene_tree::SceneTreeType::Actor(
-                                        actor,
-                                        _children,
-                                    ) => {
-                                        current_node = super::scene_tree::spawn_scene_tree_actor(
-                                            script.inverse_scale_factor,
-                                            owner,
-                                            &parent_stack[parent_stack.len() - 1],
-                                            scene_tree,
+                                    let actor = actor_stack[actor_stack.len() - 1];
+
+                                    if let Some(phong_angle) = actor.and_then(|actor| {
+                                        super::smoothing::phong_angle(
                                             actor,
+                                            default_phong_angle,
+                                        )
+                                    }) {
+                                        super::smoothing::smooth_normals(
+                                            &mut surfaces,
+                                            phong_angle,
                                         );
                                     }
-                                    quarchitect::scene_tree::SceneTreeType::VisualGeometry(
-                                        visual_geometry,
-                                    ) => {
-                                        let mesh_instance =
-                                            super::visual_geometry::spawn_mesh_instance(
-                                                owner,
-                                                &parent_stack[parent_stack.len() - 1],
-                                                visual_geometry,
-                                            );
 
-                                        match build_tx.send(BuildMessage::Tick) {
-                                            Ok(_) => (),
-                                            Err(err) => {
-                                                eprintln!(
-                                                    "Error sending message to main thread: {:?}",
-                                                    err
-                                                );
-                                                break;
-                                            }
+                                    if let (Some(batcher), Some(actor)) = (&mut batcher, actor)
+                                    {
+                                        if super::batching::is_batched(
+                                            actor,
+                                            script.batch_brush_entities,
+                                        ) {
+                                            batcher.add_surfaces(surfaces);
+                                            continue;
                                         }
+                                    }
 
-                                        match tick_rx.recv() {
-                                            Ok(_delta) => (),
-                                            Err(_err) => break,
-                                        }
+                                    let mesh_instance =
+                                        super::visual_geometry::spawn_mesh_instance(
+                                            owner,
+                                            &parent_stack[parent_stack.len() - 1],
+                                        );
 
-                                        match build_tx.send(BuildMessage::Tick) {
-                                            Ok(_) => (),
-                                            Err(err) => {
-                                                eprintln!(
-                                                    "Error sending message to main thread: {:?}",
-                                                    err
-                                                );
-                                                break;
-                                            }
-                                        }
+                                    if !tick(&build_tx, &tick_rx) {
+                                        break;
+                                    }
 
-                                        match tick_rx.recv() {
-                                            Ok(_delta) => (),
-                                            Err(_err) => break,
-                                        }
+                                    if !tick(&build_tx, &tick_rx) {
+                                        break;
+                                    }
 
+                                    let (before, after) =
                                         super::visual_geometry::populate_mesh_geometry(
-                                            visual_geometry,
+                                            &mut surfaces,
                                             mesh_instance,
                                             scene_tree.origin,
-                                            script.inverse_scale_factor,
+                                            &coordinates,
+                                            script.weld_vertices,
                                         );
-
-                                        super::visual_geometry::populate_mesh_materials(
-                                            &gdnative_texture_info,
-                                            default_material.try_to_object::<Material>(),
-                                            default_spatial_material_texture_param,
-                                            &default_shader_material_texture_param,
-                                            visual_geometry,
+                                    vertex_counts.0 += before;
+                                    vertex_counts.1 += after;
+
+                                    super::visual_geometry::populate_mesh_materials(
+                                        &gdnative_texture_info,
+                                        default_material.try_to_object::<Material>(),
+                                        default_spatial_material_texture_param,
+                                        &default_shader_material_texture_param,
+                                        &surfaces,
+                                        mesh_instance,
+                                    );
+
+                                    if let Some(resource_cache) = &resource_cache {
+                                        super::visual_geometry::save_mesh(
+                                            resource_cache,
                                             mesh_instance,
+                                            &super::resource_cache::ResourceCache::mesh_name(
+                                                entity_stack[entity_stack.len() - 1],
+                                            ),
                                         );
                                     }
-                                    quarchitect::scene_tree::SceneTreeType::CollisionGeometry(
+                                }
+                                quarchitect::scene_tree::SceneTreeType::CollisionGeometry(
+                                    collision_geometry,
+                                ) => {
+                                    super::collision_geometry::spawn_collision_geometry(
+                                        &coordinates,
+                                        owner,
+                                        &parent_stack[parent_stack.len() - 1],
                                         collision_geometry,
-                                    ) => {
-                                        super::collision_geometry::spawn_collision_geometry(
-                                            script.inverse_scale_factor,
-                                            owner,
-                                            &parent_stack[parent_stack.len() - 1],
-                                            collision_geometry,
-                                            scene_tree.origin,
-                                        );
-                                    }
+                                        scene_tree.origin,
+                                        resource_cache.as_ref(),
+                                        entity_stack[entity_stack.len() - 1],
+                                        &collision_chunking,
+                                        &mut warnings,
+                                    );
                                 }
-                            } else {
-                                done = true;
-                                break;
                             }
-                        }
-
-                        if done {
+                        } else {
+                            done = true;
                             break;
                         }
+                    }
 
-                        match build_tx.send(BuildMessage::Tick) {
-                            Ok(_) => (),
-                            Err(err) => {
-                                eprintln!("Error sending message to main thread: {:?}", err);
-                                break;
+                    if done {
+                        break;
+                    }
+
+                    if !tick(&build_tx, &tick_rx) {
+                        break;
+                    }
+                }
+
+                if let Some(batcher) = batcher {
+                    for ((x, y, z), mut surfaces) in batcher.into_batches() {
+                        let mesh_instance =
+                            super::visual_geometry::spawn_mesh_instance(owner, &None);
+
+                        if let Some(mut mesh_instance) = mesh_instance {
+                            unsafe {
+                                mesh_instance
+                                    .set_name(format!("Mesh Batch {} {} {}", x, y, z).into());
                             }
                         }
 
-                        match tick_rx.recv() {
-                            Ok(_delta) => (),
-                            Err(_err) => break,
+                        if !tick(&build_tx, &tick_rx) {
+                            break;
+                        }
+
+                        let (before, after) = super::visual_geometry::populate_mesh_geometry(
+                            &mut surfaces,
+                            mesh_instance,
+                            quarchitect::Vector3::default(),
+                            &coordinates,
+                            script.weld_vertices,
+                        );
+                        vertex_counts.0 += before;
+                        vertex_counts.1 += after;
+
+                        super::visual_geometry::populate_mesh_materials(
+                            &gdnative_texture_info,
+                            default_material.try_to_object::<Material>(),
+                            default_spatial_material_texture_param,
+                            &default_shader_material_texture_param,
+                            &surfaces,
+                            mesh_instance,
+                        );
+
+                        if let Some(resource_cache) = &resource_cache {
+                            super::visual_geometry::save_mesh(
+                                resource_cache,
+                                mesh_instance,
+                                &super::resource_cache::ResourceCache::batch_name(x, y, z),
+                            );
                         }
                     }
-                })
-                .unwrap_or_else(|err| eprintln!("Error populating scene tree: {:?}", err));
+                }
+
+                if script.weld_vertices {
+                    println!("Welded mesh vertices: {} -> {}", vertex_counts.0, vertex_counts.1);
+                }
 
-            match build_tx.send(BuildMessage::Complete) {
+                if let Some(resource_cache) = &resource_cache {
+                    warnings.extend(resource_cache.take_failures());
+                }
+                warnings
+            });
+
+            let message = match populated {
+                Ok(warnings) => BuildMessage::Complete(warnings),
+                Err(err) => BuildMessage::Failed(format!("Error populating scene tree: {:?}", err)),
+            };
+
+            match build_tx.send(message) {
                 Ok(()) => (),
                 Err(err) => {
                     eprintln!("Error sending message to main thread: {:?}", err);
@@ -367,21 +623,35 @@ impl QodotBuildWorker {
     }
 }
 
-fn flatten_scene_tree_node(node: &SceneTreeNode) -> Vec<FlatSceneTree> {
+// Hands control to the main thread for one idle frame
+fn tick(
+    build_tx: &std::sync::mpsc::Sender<BuildMessage>,
+    tick_rx: &std::sync::mpsc::Receiver<BuildCommand>,
+) -> bool {
+    match build_tx.send(BuildMessage::Tick) {
+        Ok(_) => (),
+        Err(err) => {
+            eprintln!("Error sending message to main thread: {:?}", err);
+            return false;
+        }
+    }
+
+    match tick_rx.recv() {
+        Ok(_delta) => true,
+        Err(_err) => false,
+    }
+}
+
+// Flattens a node and its descendants, tagging the node itself with its fingerprint
+fn flatten_scene_tree_node(node: &SceneTreeNode, fingerprint: Option<u64>) -> Vec<FlatSceneTree> {
     let mut scene_tree: Vec<FlatSceneTree> = Vec::new();
 
-    scene_tree.push(FlatSceneTree::Node(node));
-                        
-    if let quarchitect::scene_tree::SceneTreeType::Actor(
-        _actor,
-        children,
-    ) = &node.data
-    {
-        println!("Processing {:?} children...", children.len());
+    scene_tree.push(FlatSceneTree::Node(node, fingerprint));
+
+    if let quarchitect::scene_tree::SceneTreeType::Actor(_actor, children) = &node.data {
         scene_tree.push(FlatSceneTree::PushParent);
         for child in children {
-            println!("Child...");
-            let mut child_scene_tree = flatten_scene_tree_node(child);
+            let mut child_scene_tree = flatten_scene_tree_node(child, None);
             scene_tree.append(&mut child_scene_tree);
         }
         scene_tree.push(FlatSceneTree::PopParent);
@@ -0,0 +1,191 @@
+use gdnative::{
+    godot_error, godot_print, godot_warn, Dictionary, GodotString, Node, PackedScene, Resource,
+    ResourceSaver, Spatial, StringArray, Variant,
+};
+
+use super::export::json::JsonValue;
+use super::QodotMap;
+
+// Output path and diagnostics for a compile in progress
+pub struct CompileJob {
+    output_path: GodotString,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl CompileJob {
+    pub fn new(output_path: GodotString) -> CompileJob {
+        CompileJob {
+            output_path,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn report(&self, map_file: &str) -> Dictionary {
+        let to_array = |strings: &[String]| {
+            let mut array = StringArray::new();
+            for string in strings {
+                array.push(&GodotString::from_str(string));
+            }
+            Variant::from_string_array(&array)
+        };
+
+        let mut report = Dictionary::new();
+        report.set(&Variant::from_str("map"), &Variant::from_str(map_file));
+        report.set(
+            &Variant::from_str("output"),
+            &Variant::from_godot_string(&self.output_path),
+        );
+        report.set(
+            &Variant::from_str("success"),
+            &Variant::from_bool(self.errors.is_empty()),
+        );
+        report.set(&Variant::from_str("errors"), &to_array(&self.errors));
+        report.set(&Variant::from_str("warnings"), &to_array(&self.warnings));
+        report
+    }
+
+    fn report_json(&self, map_file: &str) -> String {
+        let to_array = |strings: &[String]| {
+            JsonValue::Array(strings.iter().cloned().map(JsonValue::String).collect())
+        };
+
+        let report = JsonValue::object()
+            .with("map", JsonValue::String(map_file.to_string()))
+            .with("output", JsonValue::String(self.output_path.to_string()))
+            .with("success", JsonValue::Bool(self.errors.is_empty()))
+            .with("errors", to_array(&self.errors))
+            .with("warnings", to_array(&self.warnings));
+
+        let mut out = String::new();
+        report.write_pretty(&mut out, 0);
+        out.push('\n');
+        out
+    }
+}
+
+impl QodotMap {
+    // Reports an error that stops the build, ending any compile in progress
+    pub fn build_failed(&mut self, owner: Spatial, message: String) {
+        godot_error!("{}", message);
+        if let Some(compile_job) = &mut self.compile_job {
+            compile_job.errors.push(message);
+        }
+
+        // Hand back anything detached before the build started
+        self.apply_overrides(owner);
+        self.finish_compile(owner);
+    }
+
+    pub fn build_warning(&mut self, message: String) {
+        godot_warn!("{}", message);
+        if let Some(compile_job) = &mut self.compile_job {
+            compile_job.warnings.push(message);
+        }
+    }
+
+    // Saves the output of a successful compile, and reports the outcome
+    pub fn finish_compile(&mut self, mut owner: Spatial) {
+        let mut compile_job = match self.compile_job.take() {
+            Some(compile_job) => compile_job,
+            None => return,
+        };
+
+        if compile_job.errors.is_empty() {
+            if let Err(err) = self.save_compile_output(owner, &compile_job.output_path) {
+                godot_error!("{}", err);
+                compile_job.errors.push(err);
+            }
+        }
+
+        let map_file = match self.map_type {
+            super::MapType::File => self.map_file.to_string(),
+            super::MapType::Resource => self
+                .get_map_resource(owner)
+                .map(|resource| resource.get_path().to_string())
+                .unwrap_or_default(),
+        };
+
+        // The report sits alongside the output, so pipelines can collect both
+        let output_path = gdnative::ProjectSettings::godot_singleton()
+            .globalize_path(compile_job.output_path.clone())
+            .to_string();
+        let report_path = std::path::Path::new(&output_path).with_extension("report.json");
+        if let Err(err) = std::fs::write(&report_path, compile_job.report_json(&map_file)) {
+            godot_error!("Failed to write {:?}: {}", report_path, err);
+        }
+
+        if compile_job.errors.is_empty() {
+            godot_print!("Compiled {} to {}", map_file, output_path);
+        }
+
+        // Deferred, so listeners are free to call back into the map
+        let report = compile_job.report(&map_file);
+        unsafe {
+            owner.call_deferred(
+                "emit_signal".into(),
+                &[
+                    Variant::from_str("compiled"),
+                    Variant::from_dictionary(&report),
+                ],
+            );
+        }
+    }
+
+    fn save_compile_output(
+        &mut self,
+        owner: Spatial,
+        output_path: &GodotString,
+    ) -> Result<(), String> {
+        let path = output_path.to_string();
+        let extension = std::path::Path::new(&path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            // Exports the tree that was just built, with its pivots, layers and overrides
+            "gltf" | "glb" => {
+                let global_path = gdnative::ProjectSettings::godot_singleton()
+                    .globalize_path(output_path.clone())
+                    .to_string();
+                let root = unsafe { owner.cast::<Node>().unwrap() };
+                super::export::gltf::write_gltf_from_nodes(root, &global_path)
+            }
+            "tscn" | "scn" => save_packed_scene(owner, output_path.clone()),
+            _ => Err(format!("Unsupported output format: {}", path)),
+        }
+    }
+}
+
+// Packs copies of the map's children under a plain Spatial, so the saved scene
+// doesn't depend on the plugin to load
+fn save_packed_scene(owner: Spatial, path: GodotString) -> Result<(), String> {
+    unsafe {
+        let mut root = Spatial::new().cast::<Node>().unwrap();
+        root.set_name(owner.get_name());
+
+        for i in 0..owner.get_child_count() {
+            let child = match owner.get_child(i) {
+                // Skips the finished build worker
+                Some(child) if !child.is_queued_for_deletion() => child,
+                _ => continue,
+            };
+
+            if let Some(mut duplicate) = child.duplicate(15) {
+                root.add_child(Some(duplicate), true);
+                super::overrides::set_owner_recursive(&mut duplicate, root);
+            }
+        }
+
+        let mut packed_scene = PackedScene::new();
+        let result = packed_scene.pack(Some(root));
+        root.free();
+        result.map_err(|err| format!("Failed to pack scene: {:?}", err))?;
+
+        ResourceSaver::godot_singleton()
+            .save(path.clone(), packed_scene.cast::<Resource>(), 0)
+            .map_err(|err| format!("Failed to save {}: {:?}", path.to_string(), err))
+    }
+}
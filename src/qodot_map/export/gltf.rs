@@ -0,0 +1,545 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use gdnative::{
+    ArrayMesh, CollisionShape, Mesh, MeshInstance, Node, Spatial, Variant, VariantType,
+};
+
+use super::json::JsonValue;
+use quarchitect::Vector3;
+
+#[cfg(test)]
+mod tests;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+
+const COMPONENT_FLOAT: usize = 5126;
+const COMPONENT_UNSIGNED_INT: usize = 5125;
+
+const TARGET_ARRAY_BUFFER: usize = 34962;
+const TARGET_ELEMENT_ARRAY_BUFFER: usize = 34963;
+
+// Writes the children of a built map, as they stand in the scene, to a .gltf or .glb
+pub fn write_gltf_from_nodes(root: Node, path: &str) -> Result<(), String> {
+    let mut builder = GltfBuilder::new();
+    let root_nodes: Vec<JsonValue> = unsafe {
+        (0..root.get_child_count())
+            .filter_map(|i| root.get_child(i))
+            .flat_map(|child| builder.add_godot_node(child))
+            .map(JsonValue::from)
+            .collect()
+    };
+
+    write_document(builder, root_nodes, path)
+}
+
+fn write_document(
+    builder: GltfBuilder,
+    root_nodes: Vec<JsonValue>,
+    path: &str,
+) -> Result<(), String> {
+    let path = Path::new(path);
+    let scene_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Map".to_string());
+
+    let binary = path
+        .extension()
+        .map(|extension| extension.eq_ignore_ascii_case("glb"))
+        .unwrap_or(false);
+
+    let buffer_uri = if binary {
+        None
+    } else {
+        Some(format!("{}.bin", scene_name))
+    };
+
+    let document = builder.into_document(&scene_name, root_nodes, buffer_uri.as_deref());
+
+    let mut json = String::new();
+    document.json.write(&mut json);
+
+    if binary {
+        std::fs::write(path, glb_bytes(json.into_bytes(), document.buffer))
+            .map_err(|err| format!("Failed to write {:?}: {}", path, err))
+    } else {
+        let buffer_path = path.with_file_name(buffer_uri.unwrap());
+        std::fs::write(&buffer_path, document.buffer)
+            .map_err(|err| format!("Failed to write {:?}: {}", buffer_path, err))?;
+        std::fs::write(path, json).map_err(|err| format!("Failed to write {:?}: {}", path, err))
+    }
+}
+
+struct GltfDocument {
+    json: JsonValue,
+    buffer: Vec<u8>,
+}
+
+// A mesh surface in glTF space, already wound counter-clockwise
+struct Primitive {
+    positions: Vec<Vector3>,
+    normals: Vec<Vector3>,
+    tangents: Vec<f32>,
+    uvs: Option<Vec<f32>>,
+    colors: Option<Vec<f32>>,
+    indices: Vec<u32>,
+    material: Option<String>,
+}
+
+struct GltfBuilder {
+    nodes: Vec<Option<JsonValue>>,
+    meshes: Vec<JsonValue>,
+    materials: Vec<JsonValue>,
+    material_indices: HashMap<String, usize>,
+    accessors: Vec<JsonValue>,
+    buffer_views: Vec<JsonValue>,
+    buffer: Vec<u8>,
+}
+
+impl GltfBuilder {
+    fn new() -> GltfBuilder {
+        GltfBuilder {
+            nodes: Vec::new(),
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            material_indices: HashMap::new(),
+            accessors: Vec::new(),
+            buffer_views: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+
+    fn add_godot_node(&mut self, node: Node) -> Option<usize> {
+        unsafe {
+            // Skips the finished build worker, along with collision, which glTF can't carry
+            if node.is_queued_for_deletion() || node.cast::<CollisionShape>().is_some() {
+                return None;
+            }
+            let spatial = node.cast::<Spatial>()?;
+
+            let index = self.nodes.len();
+            self.nodes.push(None);
+
+            let children: Vec<JsonValue> = (0..node.get_child_count())
+                .filter_map(|i| node.get_child(i))
+                .flat_map(|child| self.add_godot_node(child))
+                .map(JsonValue::from)
+                .collect();
+
+            let mut json = JsonValue::object()
+                .with("name", JsonValue::String(node.get_name().to_string()))
+                .with("matrix", transform_to_json(spatial.get_transform()));
+
+            let mesh = node
+                .cast::<MeshInstance>()
+                .and_then(|mesh_instance| mesh_instance.get_mesh())
+                .and_then(|mesh| mesh.cast::<ArrayMesh>())
+                .and_then(|mesh| self.add_mesh(mesh_primitives(mesh)));
+            if let Some(mesh) = mesh {
+                json = json.with("mesh", mesh.into());
+            }
+
+            if let Some(extras) = metadata_extras(node) {
+                json = json.with("extras", extras);
+            }
+
+            if !children.is_empty() {
+                json = json.with("children", JsonValue::Array(children));
+            }
+
+            self.nodes[index] = Some(json);
+            Some(index)
+        }
+    }
+
+    fn add_mesh(&mut self, primitives: Vec<Primitive>) -> Option<usize> {
+        let mut primitive_json: Vec<JsonValue> = Vec::new();
+
+        for primitive in primitives {
+            if primitive.indices.is_empty() {
+                continue;
+            }
+
+            let mut attributes = JsonValue::object()
+                .with(
+                    "POSITION",
+                    self.push_vector3_accessor(&primitive.positions, true).into(),
+                )
+                .with(
+                    "NORMAL",
+                    self.push_vector3_accessor(&primitive.normals, false).into(),
+                );
+
+            if !primitive.tangents.is_empty() {
+                attributes = attributes.with(
+                    "TANGENT",
+                    self.push_float_accessor(&primitive.tangents, 4, "VEC4").into(),
+                );
+            }
+
+            if let Some(uvs) = &primitive.uvs {
+                attributes = attributes.with(
+                    "TEXCOORD_0",
+                    self.push_float_accessor(uvs, 2, "VEC2").into(),
+                );
+            }
+
+            if let Some(colors) = &primitive.colors {
+                attributes = attributes.with(
+                    "COLOR_0",
+                    self.push_float_accessor(colors, 3, "VEC3").into(),
+                );
+            }
+
+            let mut json = JsonValue::object()
+                .with("attributes", attributes)
+                .with("indices", self.push_index_accessor(&primitive.indices).into());
+
+            if let Some(material) = &primitive.material {
+                json = json.with("material", self.material(material).into());
+            }
+
+            primitive_json.push(json);
+        }
+
+        if primitive_json.is_empty() {
+            return None;
+        }
+
+        self.meshes
+            .push(JsonValue::object().with("primitives", JsonValue::Array(primitive_json)));
+        Some(self.meshes.len() - 1)
+    }
+
+    fn material(&mut self, texture: &str) -> usize {
+        if let Some(index) = self.material_indices.get(texture) {
+            return *index;
+        }
+
+        self.materials
+            .push(JsonValue::object().with("name", texture.into()));
+        let index = self.materials.len() - 1;
+        self.material_indices.insert(texture.to_string(), index);
+        index
+    }
+
+    fn push_buffer_view(&mut self, bytes: &[u8], target: usize) -> usize {
+        while self.buffer.len() % 4 != 0 {
+            self.buffer.push(0);
+        }
+
+        let offset = self.buffer.len();
+        self.buffer.extend_from_slice(bytes);
+
+        self.buffer_views.push(
+            JsonValue::object()
+                .with("buffer", 0.into())
+                .with("byteOffset", offset.into())
+                .with("byteLength", bytes.len().into())
+                .with("target", target.into()),
+        );
+        self.buffer_views.len() - 1
+    }
+
+    fn push_vector3_accessor(&mut self, values: &[Vector3], bounds: bool) -> usize {
+        let flat: Vec<f32> = values
+            .iter()
+            .flat_map(|value| vec![value.x(), value.y(), value.z()])
+            .collect();
+        let index = self.push_float_accessor(&flat, 3, "VEC3");
+
+        // POSITION accessors are required to declare their bounds
+        if bounds && !values.is_empty() {
+            let (min, max) = values
+                .iter()
+                .skip(1)
+                .fold((values[0], values[0]), |(min, max), value| {
+                    (min.min(*value), max.max(*value))
+                });
+
+            let accessor = std::mem::replace(&mut self.accessors[index], JsonValue::Null);
+            self.accessors[index] = accessor
+                .with("min", vector3_to_json(min))
+                .with("max", vector3_to_json(max));
+        }
+
+        index
+    }
+
+    fn push_float_accessor(&mut self, values: &[f32], components: usize, kind: &str) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes().to_vec())
+            .collect();
+        let buffer_view = self.push_buffer_view(&bytes, TARGET_ARRAY_BUFFER);
+
+        self.accessors.push(
+            JsonValue::object()
+                .with("bufferView", buffer_view.into())
+                .with("componentType", COMPONENT_FLOAT.into())
+                .with("count", (values.len() / components).into())
+                .with("type", kind.into()),
+        );
+        self.accessors.len() - 1
+    }
+
+    fn push_index_accessor(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices
+            .iter()
+            .flat_map(|index| index.to_le_bytes().to_vec())
+            .collect();
+        let buffer_view = self.push_buffer_view(&bytes, TARGET_ELEMENT_ARRAY_BUFFER);
+
+        self.accessors.push(
+            JsonValue::object()
+                .with("bufferView", buffer_view.into())
+                .with("componentType", COMPONENT_UNSIGNED_INT.into())
+                .with("count", indices.len().into())
+                .with("type", "SCALAR".into()),
+        );
+        self.accessors.len() - 1
+    }
+
+    fn into_document(
+        mut self,
+        scene_name: &str,
+        root_nodes: Vec<JsonValue>,
+        buffer_uri: Option<&str>,
+    ) -> GltfDocument {
+        while self.buffer.len() % 4 != 0 {
+            self.buffer.push(0);
+        }
+
+        let mut buffer = JsonValue::object().with("byteLength", self.buffer.len().into());
+        if let Some(buffer_uri) = buffer_uri {
+            buffer = buffer.with("uri", buffer_uri.into());
+        }
+
+        let mut json = JsonValue::object()
+            .with(
+                "asset",
+                JsonValue::object()
+                    .with("version", "2.0".into())
+                    .with("generator", "Qodot".into()),
+            )
+            .with("scene", 0.into())
+            .with(
+                "scenes",
+                JsonValue::Array(vec![JsonValue::object()
+                    .with("name", scene_name.into())
+                    .with("nodes", JsonValue::Array(root_nodes))]),
+            )
+            .with(
+                "nodes",
+                JsonValue::Array(
+                    self.nodes
+                        .into_iter()
+                        .map(|node| node.unwrap_or_else(JsonValue::object))
+                        .collect(),
+                ),
+            );
+
+        if !self.meshes.is_empty() {
+            json = json
+                .with("meshes", JsonValue::Array(self.meshes))
+                .with("accessors", JsonValue::Array(self.accessors))
+                .with("bufferViews", JsonValue::Array(self.buffer_views))
+                .with("buffers", JsonValue::Array(vec![buffer]));
+        }
+
+        if !self.materials.is_empty() {
+            json = json.with("materials", JsonValue::Array(self.materials));
+        }
+
+        GltfDocument {
+            json,
+            buffer: self.buffer,
+        }
+    }
+}
+
+// Reads back the surfaces populate_mesh built, which are named after their textures
+unsafe fn mesh_primitives(mesh: ArrayMesh) -> Vec<Primitive> {
+    (0..mesh.get_surface_count())
+        .map(|surface| {
+            let arrays = mesh.surface_get_arrays(surface);
+            let array = |index: i64| arrays.get_val(index as i32);
+
+            let vertices = array(Mesh::ARRAY_VERTEX).to_vector3_array();
+            let normals = array(Mesh::ARRAY_NORMAL).to_vector3_array();
+            let tangents = array(Mesh::ARRAY_TANGENT).to_float32_array();
+            let colors = array(Mesh::ARRAY_COLOR);
+            let uvs = array(Mesh::ARRAY_TEX_UV);
+            let indices = array(Mesh::ARRAY_INDEX).to_int32_array();
+
+            let material = mesh.surface_get_name(surface).to_string();
+
+            Primitive {
+                positions: (0..vertices.len())
+                    .map(|i| quarchitect_vector3(vertices.get(i)))
+                    .collect(),
+                normals: (0..normals.len())
+                    .map(|i| quarchitect_vector3(normals.get(i)))
+                    .collect(),
+                tangents: (0..tangents.len()).map(|i| tangents.get(i)).collect(),
+                uvs: if uvs.is_nil() {
+                    None
+                } else {
+                    let uvs = uvs.to_vector2_array();
+                    Some(
+                        (0..uvs.len())
+                            .flat_map(|i| {
+                                let uv = uvs.get(i);
+                                vec![uv.x, uv.y]
+                            })
+                            .collect(),
+                    )
+                },
+                colors: if colors.is_nil() {
+                    None
+                } else {
+                    let colors = colors.to_color_array();
+                    Some(
+                        (0..colors.len())
+                            .flat_map(|i| {
+                                let color = colors.get(i);
+                                vec![color.r, color.g, color.b]
+                            })
+                            .collect(),
+                    )
+                },
+                // Godot's meshes are always clockwise, whatever the coordinate system
+                indices: (0..indices.len() / 3)
+                    .flat_map(|triangle| {
+                        let i = triangle * 3;
+                        vec![
+                            indices.get(i) as u32,
+                            indices.get(i + 2) as u32,
+                            indices.get(i + 1) as u32,
+                        ]
+                    })
+                    .collect(),
+                material: if material.is_empty() {
+                    None
+                } else {
+                    Some(material)
+                },
+            }
+        })
+        .collect()
+}
+
+// Node metadata the build attaches for scripts, leaving out Qodot's own bookkeeping
+unsafe fn metadata_extras(node: Node) -> Option<JsonValue> {
+    let keys = node.get_meta_list();
+
+    let mut names: Vec<String> = (0..keys.len())
+        .map(|i| keys.get(i).to_string())
+        .filter(|name| !name.starts_with("qodot_"))
+        .collect();
+    names.sort();
+
+    let extras: Vec<(String, JsonValue)> = names
+        .into_iter()
+        .filter_map(|name| {
+            variant_to_json(&node.get_meta(name.as_str().into())).map(|value| (name, value))
+        })
+        .collect();
+
+    if extras.is_empty() {
+        None
+    } else {
+        Some(JsonValue::Object(extras))
+    }
+}
+
+fn variant_to_json(variant: &Variant) -> Option<JsonValue> {
+    match variant.get_type() {
+        VariantType::Bool => Some(JsonValue::Bool(variant.to_bool())),
+        VariantType::I64 => Some(JsonValue::Number(variant.to_i64() as f64)),
+        VariantType::F64 => Some(JsonValue::Number(variant.to_f64())),
+        VariantType::GodotString => Some(JsonValue::String(variant.to_string())),
+        _ => None,
+    }
+}
+
+fn quarchitect_vector3(vector: gdnative::Vector3) -> Vector3 {
+    Vector3::new(vector.x, vector.y, vector.z)
+}
+
+// Basis elements are rows, where glTF wants its columns
+fn transform_to_json(transform: gdnative::Transform) -> JsonValue {
+    let [x, y, z] = transform.basis.elements;
+    matrix_to_json(
+        [
+            Vector3::new(x.x, y.x, z.x),
+            Vector3::new(x.y, y.y, z.y),
+            Vector3::new(x.z, y.z, z.z),
+        ],
+        quarchitect_vector3(transform.origin),
+    )
+}
+
+fn vector3_to_json(vector: Vector3) -> JsonValue {
+    JsonValue::Array(vec![
+        vector.x().into(),
+        vector.y().into(),
+        vector.z().into(),
+    ])
+}
+
+// Column-major, as glTF expects
+fn matrix_to_json(rotation: [Vector3; 3], translation: Vector3) -> JsonValue {
+    let mut elements: Vec<JsonValue> = Vec::new();
+    for column in &rotation {
+        elements.extend(vec![
+            column.x().into(),
+            column.y().into(),
+            column.z().into(),
+            0.0_f32.into(),
+        ]);
+    }
+    elements.extend(vec![
+        translation.x().into(),
+        translation.y().into(),
+        translation.z().into(),
+        1.0_f32.into(),
+    ]);
+    JsonValue::Array(elements)
+}
+
+fn glb_bytes(mut json: Vec<u8>, mut buffer: Vec<u8>) -> Vec<u8> {
+    while json.len() % 4 != 0 {
+        json.push(b' ');
+    }
+
+    while buffer.len() % 4 != 0 {
+        buffer.push(0);
+    }
+
+    let mut total_length = 12 + 8 + json.len();
+    if !buffer.is_empty() {
+        total_length += 8 + buffer.len();
+    }
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(total_length);
+    bytes.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+    bytes.extend_from_slice(&GLB_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(total_length as u32).to_le_bytes());
+
+    bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&GLB_CHUNK_JSON.to_le_bytes());
+    bytes.extend_from_slice(&json);
+
+    if !buffer.is_empty() {
+        bytes.extend_from_slice(&(buffer.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&GLB_CHUNK_BIN.to_le_bytes());
+        bytes.extend_from_slice(&buffer);
+    }
+
+    bytes
+}
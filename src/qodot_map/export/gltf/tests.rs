@@ -0,0 +1,118 @@
+use super::{glb_bytes, GltfBuilder, JsonValue, Primitive};
+use quarchitect::Vector3;
+
+fn triangle(material: Option<&str>) -> Primitive {
+    Primitive {
+        positions: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, -1.0),
+        ],
+        normals: vec![Vector3::new(0.0, 0.0, 1.0); 3],
+        tangents: Vec::new(),
+        uvs: None,
+        colors: None,
+        indices: vec![0, 1, 2],
+        material: material.map(String::from),
+    }
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(word)
+}
+
+#[test]
+fn document_references_its_buffer() {
+    let mut builder = GltfBuilder::new();
+    let mesh = builder.add_mesh(vec![triangle(Some("wall"))]).unwrap();
+    builder.nodes.push(Some(
+        JsonValue::object()
+            .with("name", "Mesh".into())
+            .with("mesh", mesh.into()),
+    ));
+
+    let document = builder.into_document("Map", vec![0_usize.into()], Some("Map.bin"));
+    let mut json = String::new();
+    document.json.write(&mut json);
+
+    assert_eq!(
+        json,
+        concat!(
+            r#"{"asset":{"version":"2.0","generator":"Qodot"},"scene":0,"#,
+            r#""scenes":[{"name":"Map","nodes":[0]}],"nodes":[{"name":"Mesh","mesh":0}],"#,
+            r#""meshes":[{"primitives":[{"attributes":{"POSITION":0,"NORMAL":1},"#,
+            r#""indices":2,"material":0}]}],"#,
+            r#""accessors":[{"bufferView":0,"componentType":5126,"count":3,"type":"VEC3","#,
+            r#""min":[0,0,-1],"max":[2,1,0]},"#,
+            r#"{"bufferView":1,"componentType":5126,"count":3,"type":"VEC3"},"#,
+            r#"{"bufferView":2,"componentType":5125,"count":3,"type":"SCALAR"}],"#,
+            r#""bufferViews":[{"buffer":0,"byteOffset":0,"byteLength":36,"target":34962},"#,
+            r#"{"buffer":0,"byteOffset":36,"byteLength":36,"target":34962},"#,
+            r#"{"buffer":0,"byteOffset":72,"byteLength":12,"target":34963}],"#,
+            r#""buffers":[{"byteLength":84,"uri":"Map.bin"}],"materials":[{"name":"wall"}]}"#
+        )
+    );
+    assert_eq!(document.buffer.len(), 84);
+    assert_eq!(u32_at(&document.buffer, 80), 2);
+}
+
+#[test]
+fn materials_are_shared_between_primitives() {
+    let mut builder = GltfBuilder::new();
+    builder.add_mesh(vec![triangle(Some("wall")), triangle(Some("floor"))]);
+    builder.add_mesh(vec![triangle(Some("wall")), triangle(None)]);
+
+    assert_eq!(builder.meshes.len(), 2);
+    assert_eq!(builder.materials.len(), 2);
+    assert_eq!(builder.material_indices["wall"], 0);
+    assert_eq!(builder.material_indices["floor"], 1);
+}
+
+#[test]
+fn empty_meshes_are_skipped() {
+    let mut empty = triangle(None);
+    empty.indices.clear();
+
+    let mut builder = GltfBuilder::new();
+    assert!(builder.add_mesh(vec![empty]).is_none());
+
+    let document = builder.into_document("Map", Vec::new(), None);
+    let mut json = String::new();
+    document.json.write(&mut json);
+
+    assert!(!json.contains("\"meshes\""));
+    assert!(!json.contains("\"buffers\""));
+    assert!(document.buffer.is_empty());
+}
+
+#[test]
+fn glb_chunks_are_aligned() {
+    let bytes = glb_bytes(b"{\"a\":1}".to_vec(), vec![1, 2, 3, 4, 5]);
+
+    // Header
+    assert_eq!(&bytes[0..4], b"glTF");
+    assert_eq!(u32_at(&bytes, 4), 2);
+    assert_eq!(u32_at(&bytes, 8) as usize, bytes.len());
+
+    // JSON chunk, padded with spaces
+    assert_eq!(u32_at(&bytes, 12), 8);
+    assert_eq!(&bytes[16..20], b"JSON");
+    assert_eq!(&bytes[20..28], b"{\"a\":1} ");
+
+    // Binary chunk, padded with zeroes
+    assert_eq!(u32_at(&bytes, 28), 8);
+    assert_eq!(&bytes[32..36], b"BIN\0");
+    assert_eq!(&bytes[36..], &[1, 2, 3, 4, 5, 0, 0, 0]);
+}
+
+#[test]
+fn glb_without_a_buffer_has_no_binary_chunk() {
+    let bytes = glb_bytes(b"{}".to_vec(), Vec::new());
+
+    assert_eq!(bytes.len(), 24);
+    assert_eq!(u32_at(&bytes, 8), 24);
+    assert_eq!(u32_at(&bytes, 12), 4);
+    assert_eq!(&bytes[20..], b"{}  ");
+}
@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod tests;
+
+// Minimal JSON document model, sufficient for writing glTF and TrenchBroom configs
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub fn object() -> JsonValue {
+        JsonValue::Object(Vec::new())
+    }
+
+    // Appends a key to an object value, ignoring calls on other variants
+    pub fn with(mut self, key: &str, value: JsonValue) -> JsonValue {
+        if let JsonValue::Object(entries) = &mut self {
+            entries.push((key.to_string(), value));
+        }
+        self
+    }
+
+    pub fn write(&self, out: &mut String) {
+        match self {
+            JsonValue::Null => out.push_str("null"),
+            JsonValue::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+            JsonValue::Number(value) => {
+                if !value.is_finite() {
+                    out.push_str("null");
+                } else if value.fract() == 0.0 && value.abs() < 1e15 {
+                    out.push_str(&format!("{}", *value as i64));
+                } else {
+                    out.push_str(&format!("{}", value));
+                }
+            }
+            JsonValue::String(value) => write_string(value, out),
+            JsonValue::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    value.write(out);
+                }
+                out.push(']');
+            }
+            JsonValue::Object(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_string(key, out);
+                    out.push(':');
+                    value.write(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+
+    // Indented output for files intended to be read and edited by hand
+    pub fn write_pretty(&self, out: &mut String, depth: usize) {
+        let indent = |out: &mut String, depth: usize| {
+            for _ in 0..depth {
+                out.push_str("    ");
+            }
+        };
+
+        match self {
+            JsonValue::Array(values) if values.iter().any(JsonValue::is_container) => {
+                out.push_str("[\n");
+                for (i, value) in values.iter().enumerate() {
+                    indent(out, depth + 1);
+                    value.write_pretty(out, depth + 1);
+                    out.push_str(if i + 1 < values.len() { ",\n" } else { "\n" });
+                }
+                indent(out, depth);
+                out.push(']');
+            }
+            JsonValue::Object(entries) if !entries.is_empty() => {
+                out.push_str("{\n");
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    indent(out, depth + 1);
+                    write_string(key, out);
+                    out.push_str(": ");
+                    value.write_pretty(out, depth + 1);
+                    out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+                }
+                indent(out, depth);
+                out.push('}');
+            }
+            JsonValue::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    value.write(out);
+                }
+                out.push(']');
+            }
+            _ => self.write(out),
+        }
+    }
+
+    fn is_container(&self) -> bool {
+        match self {
+            JsonValue::Array(_) | JsonValue::Object(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<f32> for JsonValue {
+    fn from(value: f32) -> JsonValue {
+        JsonValue::Number(f64::from(value))
+    }
+}
+
+impl From<usize> for JsonValue {
+    fn from(value: usize) -> JsonValue {
+        JsonValue::Number(value as f64)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> JsonValue {
+        JsonValue::String(value.to_string())
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> JsonValue {
+        JsonValue::Bool(value)
+    }
+}
+
+fn write_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
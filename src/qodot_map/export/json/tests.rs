@@ -0,0 +1,78 @@
+use super::JsonValue;
+
+fn compact(value: &JsonValue) -> String {
+    let mut out = String::new();
+    value.write(&mut out);
+    out
+}
+
+fn pretty(value: &JsonValue) -> String {
+    let mut out = String::new();
+    value.write_pretty(&mut out, 0);
+    out
+}
+
+#[test]
+fn strings_are_escaped() {
+    let value = JsonValue::from("say \"hi\"\\\n\r\t\u{1}é");
+
+    assert_eq!(compact(&value), r#""say \"hi\"\\\n\r\t\u0001é""#);
+}
+
+#[test]
+fn keys_are_escaped() {
+    let value = JsonValue::object().with("a\"b", JsonValue::Null);
+
+    assert_eq!(compact(&value), r#"{"a\"b":null}"#);
+}
+
+#[test]
+fn whole_numbers_are_written_without_a_fraction() {
+    let value = JsonValue::Array(vec![
+        3_usize.into(),
+        (-2.0_f32).into(),
+        0.5_f32.into(),
+        JsonValue::Number(std::f64::NAN),
+        JsonValue::Number(std::f64::INFINITY),
+    ]);
+
+    assert_eq!(compact(&value), "[3,-2,0.5,null,null]");
+}
+
+#[test]
+fn objects_keep_their_key_order() {
+    let value = JsonValue::object()
+        .with("z", true.into())
+        .with("a", false.into())
+        .with("m", JsonValue::object());
+
+    assert_eq!(compact(&value), r#"{"z":true,"a":false,"m":{}}"#);
+}
+
+#[test]
+fn with_ignores_non_objects() {
+    let value = JsonValue::Array(Vec::new()).with("key", true.into());
+
+    assert_eq!(compact(&value), "[]");
+}
+
+#[test]
+fn pretty_output_keeps_flat_arrays_on_one_line() {
+    let value = JsonValue::object()
+        .with("name", "Qodot".into())
+        .with(
+            "size",
+            JsonValue::Array(vec![1_usize.into(), 2_usize.into()]),
+        )
+        .with(
+            "items",
+            JsonValue::Array(vec![JsonValue::object().with("id", 1_usize.into())]),
+        )
+        .with("empty", JsonValue::object());
+
+    assert_eq!(
+        pretty(&value),
+        "{\n    \"name\": \"Qodot\",\n    \"size\": [1, 2],\n    \"items\": [\n        {\n            \
+         \"id\": 1\n        }\n    ],\n    \"empty\": {}\n}"
+    );
+}
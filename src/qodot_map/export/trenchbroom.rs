@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+
+use super::json::JsonValue;
+
+const CONFIG_VERSION: usize = 4;
+
+// Map units per Godot unit at which one texel per map unit looks right, Qodot's default
+const REFERENCE_INVERSE_SCALE_FACTOR: f32 = 16.0;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "tga", "bmp", "webp"];
+
+// Texture names TrenchBroom uses internally, which shouldn't become tags
+const RESERVED_TEXTURES: &[&str] = &["__TB_empty"];
+
+pub enum TexturePackage {
+    // Loose image files under a project directory, relative to res://
+    Directory { root: String },
+    // Quake WAD2 files referenced by the worldspawn "wad" key
+    Wad { palette: Option<String> },
+}
+
+pub struct GameConfig {
+    pub name: String,
+    pub icon: Option<String>,
+    pub textures: TexturePackage,
+    pub brush_texture_blacklist: Vec<String>,
+    pub plane_texture_blacklist: Vec<String>,
+    // The map's scale settings, which set the texture scale new faces start with
+    pub inverse_scale_factor: f32,
+    pub axis_scale: [f32; 3],
+}
+
+impl GameConfig {
+    // Keeps the reference texel density in Godot units whatever the map's scale. Faces can lie
+    // along any axis, so the axis scales are averaged.
+    fn default_texture_scale(&self) -> f32 {
+        let axis_scale = self.axis_scale.iter().map(|scale| scale.abs()).sum::<f32>() / 3.0;
+        let scale = self.inverse_scale_factor.abs() / (REFERENCE_INVERSE_SCALE_FACTOR * axis_scale);
+
+        if scale.is_finite() && scale > 0.0 {
+            scale
+        } else {
+            1.0
+        }
+    }
+}
+
+// Writes GameConfig.cfg and the game's FGD into a folder named after the game,
+// returning the path of that folder
+pub fn write_game_folder(
+    directory: &str,
+    config: &GameConfig,
+    fgd: &str,
+) -> Result<PathBuf, String> {
+    let folder = Path::new(directory).join(&config.name);
+    std::fs::create_dir_all(&folder)
+        .map_err(|err| format!("Failed to create {:?}: {}", folder, err))?;
+
+    let fgd_name = format!("{}.fgd", config.name);
+    let fgd_path = folder.join(&fgd_name);
+    std::fs::write(&fgd_path, fgd)
+        .map_err(|err| format!("Failed to write {:?}: {}", fgd_path, err))?;
+
+    let mut cfg = String::new();
+    game_config(config, &fgd_name).write_pretty(&mut cfg, 0);
+    cfg.push('\n');
+
+    let cfg_path = folder.join("GameConfig.cfg");
+    std::fs::write(&cfg_path, cfg)
+        .map_err(|err| format!("Failed to write {:?}: {}", cfg_path, err))?;
+
+    Ok(folder)
+}
+
+fn game_config(config: &GameConfig, fgd_name: &str) -> JsonValue {
+    let texture_scale = config.default_texture_scale();
+
+    let mut json = JsonValue::object()
+        .with("version", CONFIG_VERSION.into())
+        .with("name", config.name.as_str().into());
+
+    if let Some(icon) = &config.icon {
+        json = json.with("icon", icon.as_str().into());
+    }
+
+    json.with(
+        "fileformats",
+        JsonValue::Array(vec![
+            JsonValue::object().with("format", "Valve".into()),
+            JsonValue::object().with("format", "Standard".into()),
+        ]),
+    )
+    .with(
+        "filesystem",
+        JsonValue::object().with("searchpath", ".".into()).with(
+            "packageformat",
+            JsonValue::object()
+                .with("extension", "pak".into())
+                .with("format", "idpak".into()),
+        ),
+    )
+    .with("textures", textures(&config.textures))
+    .with(
+        "entities",
+        JsonValue::object()
+            .with("definitions", JsonValue::Array(vec![fgd_name.into()]))
+            .with("defaultcolor", "0.6 0.6 0.6 1.0".into())
+            .with(
+                "modelformats",
+                JsonValue::Array(vec!["mdl".into(), "bsp".into(), "obj".into()]),
+            ),
+    )
+    .with(
+        "tags",
+        JsonValue::object()
+            .with("brush", JsonValue::Array(Vec::new()))
+            .with("brushface", brushface_tags(config)),
+    )
+    .with(
+        "faceattribs",
+        JsonValue::object()
+            .with(
+                "defaults",
+                JsonValue::object().with(
+                    "scale",
+                    JsonValue::Array(vec![texture_scale.into(), texture_scale.into()]),
+                ),
+            )
+            .with("surfaceflags", JsonValue::Array(Vec::new()))
+            .with("contentflags", JsonValue::Array(Vec::new())),
+    )
+}
+
+fn textures(package: &TexturePackage) -> JsonValue {
+    match package {
+        TexturePackage::Directory { root } => JsonValue::object()
+            .with(
+                "package",
+                JsonValue::object()
+                    .with("type", "directory".into())
+                    .with("root", root.as_str().into()),
+            )
+            .with(
+                "format",
+                JsonValue::object()
+                    .with(
+                        "extensions",
+                        JsonValue::Array(
+                            IMAGE_EXTENSIONS
+                                .iter()
+                                .map(|extension| (*extension).into())
+                                .collect(),
+                        ),
+                    )
+                    .with("format", "image".into()),
+            )
+            .with("attribute", "_tb_textures".into()),
+        TexturePackage::Wad { palette } => {
+            let json = JsonValue::object()
+                .with(
+                    "package",
+                    JsonValue::object().with("type", "file".into()).with(
+                        "format",
+                        JsonValue::object()
+                            .with("extension", "wad".into())
+                            .with("format", "wad2".into()),
+                    ),
+                )
+                .with(
+                    "format",
+                    JsonValue::object()
+                        .with("extension", "D".into())
+                        .with("format", "idmip".into()),
+                );
+
+            match palette {
+                Some(palette) => json
+                    .with("palette", palette.as_str().into())
+                    .with("attribute", "wad".into()),
+                None => json.with("attribute", "wad".into()),
+            }
+        }
+    }
+}
+
+// Blacklisted textures are tagged as transparent so mappers can hide and see through them
+fn brushface_tags(config: &GameConfig) -> JsonValue {
+    let mut textures: Vec<&String> = config
+        .brush_texture_blacklist
+        .iter()
+        .chain(config.plane_texture_blacklist.iter())
+        .filter(|texture| !RESERVED_TEXTURES.contains(&texture.as_str()))
+        .collect();
+    textures.dedup();
+
+    JsonValue::Array(
+        textures
+            .into_iter()
+            .map(|texture| {
+                JsonValue::object()
+                    .with("name", tag_name(texture).as_str().into())
+                    .with("attribs", JsonValue::Array(vec!["transparent".into()]))
+                    .with("match", "texture".into())
+                    .with("pattern", texture.as_str().into())
+            })
+            .collect(),
+    )
+}
+
+// "special/clip" -> "Clip"
+fn tag_name(texture: &str) -> String {
+    let name = texture.rsplit('/').next().unwrap_or(texture);
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
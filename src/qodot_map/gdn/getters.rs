@@ -1,4 +1,4 @@
-use gdnative::{GodotString, Resource, Spatial, StringArray};
+use gdnative::{Dictionary, GodotString, Resource, Spatial, StringArray, Vector3};
 
 use crate::QodotMap;
 
@@ -103,4 +103,76 @@ impl QodotMap {
     pub fn get_chunk_size(&self, _: Spatial) -> i32 {
         self.chunk_size
     }
+
+    pub fn get_weld_vertices(&self, _: Spatial) -> bool {
+        self.weld_vertices
+    }
+
+    pub fn get_up_axis(&self, _: Spatial) -> i32 {
+        self.up_axis.into()
+    }
+
+    pub fn get_forward_axis(&self, _: Spatial) -> i32 {
+        self.forward_axis.into()
+    }
+
+    pub fn get_handedness(&self, _: Spatial) -> i32 {
+        self.handedness.into()
+    }
+
+    pub fn get_axis_scale(&self, _: Spatial) -> Vector3 {
+        self.axis_scale
+    }
+
+    pub fn get_batch_meshes(&self, _: Spatial) -> bool {
+        self.batch_meshes
+    }
+
+    pub fn get_batch_brush_entities(&self, _: Spatial) -> bool {
+        self.batch_brush_entities
+    }
+
+    pub fn get_batch_cell_size(&self, _: Spatial) -> f32 {
+        self.batch_cell_size
+    }
+
+    pub fn get_collision_cell_size(&self, _: Spatial) -> f32 {
+        self.collision_cell_size
+    }
+
+    pub fn get_max_convex_shapes_per_body(&self, _: Spatial) -> i32 {
+        self.max_convex_shapes_per_body
+    }
+
+    pub fn get_cache_resources(&self, _: Spatial) -> bool {
+        self.cache_resources
+    }
+
+    pub fn get_resource_cache_directory(&self, _: Spatial) -> GodotString {
+        self.resource_cache_directory.clone()
+    }
+
+    pub fn get_choices_as_names(&self, _: Spatial) -> bool {
+        self.choices_as_names
+    }
+
+    pub fn get_expand_flags(&self, _: Spatial) -> bool {
+        self.expand_flags
+    }
+
+    pub fn get_rotate_point_entities(&self, _: Spatial) -> bool {
+        self.rotate_point_entities
+    }
+
+    pub fn get_trenchbroom_hierarchy(&self, _: Spatial) -> bool {
+        self.trenchbroom_hierarchy
+    }
+
+    pub fn get_entity_overrides(&self, _: Spatial) -> Dictionary {
+        self.entity_overrides.clone()
+    }
+
+    pub fn get_export_trenchbroom_game(&self, _: Spatial) -> GodotString {
+        GodotString::new()
+    }
 }
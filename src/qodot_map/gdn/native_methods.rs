@@ -1,9 +1,15 @@
 use gdnative::{
     godot_error, godot_print, godot_wrap_method_inner, godot_wrap_method_parameter_count, methods,
-    FromVariant, Instance, Map, Spatial, Variant, VariantArray,
+    Dictionary, FromVariant, GodotString, Instance, Map, Node, Spatial, StringArray, Variant,
+    VariantArray,
 };
 
-use crate::{map::QuakeMap, qodot_map::MapType, texture_loader::TextureType, QodotMap};
+use crate::{
+    map::QuakeMap,
+    qodot_map::{watcher::FileWatcher, MapType},
+    texture_loader::TextureType,
+    QodotMap,
+};
 
 #[methods]
 impl QodotMap {
@@ -11,16 +17,177 @@ impl QodotMap {
     #[export]
     fn map_resource_changed(&mut self, mut owner: Spatial) {
         godot_print!("Map resource changed");
+        // The resource may have been saved to a new backing file
+        self.reset_file_watcher();
         if self.map_type == MapType::Resource && self.automatic_rebuild {
+            unsafe {
+                owner.call_deferred("rebuild_changed".into(), &[]);
+            }
+        }
+    }
+
+    // Called by the build worker once it's done populating the tree, with any error it hit
+    // and the problems it worked around
+    #[export]
+    pub fn build_complete(
+        &mut self,
+        owner: Spatial,
+        error: GodotString,
+        warnings: StringArray,
+    ) {
+        for i in 0..warnings.len() {
+            self.build_warning(warnings.get(i).to_string());
+        }
+
+        if !error.is_empty() {
+            self.build_failed(owner, error.to_string());
+            return;
+        }
+
+        godot_print!("Applying overrides");
+        self.apply_overrides(owner);
+        self.finish_compile(owner);
+    }
+
+    // Builds the map and saves the result to a .tscn, .scn, .gltf or .glb file, then emits
+    // "compiled" with a diagnostics report. Returns false if a compile is already running.
+    #[export]
+    pub fn compile(&mut self, owner: Spatial, output_path: GodotString) -> bool {
+        if self.compile_job.is_some() {
+            godot_error!("A compile is already in progress");
+            return false;
+        }
+
+        godot_print!("Compiling map to {}", output_path.to_string());
+        self.compile_job = Some(crate::qodot_map::compile::CompileJob::new(output_path));
+        self.build(owner, false);
+        true
+    }
+
+    // Records a node's current property value, to be re-applied after every rebuild
+    #[export]
+    pub fn store_override(&mut self, owner: Spatial, node: Node, property: GodotString) -> bool {
+        let entities =
+            crate::qodot_map::overrides::entity_nodes(unsafe { owner.cast::<Node>().unwrap() });
+
+        let (entity_key, property_key) = match crate::qodot_map::overrides::override_key(
+            &entities,
+            node,
+            &property.to_string(),
+        ) {
+            Ok(keys) => keys,
+            Err(err) => {
+                godot_error!("{}", err);
+                return false;
+            }
+        };
+
+        let entity_key = Variant::from_str(entity_key);
+        let mut properties = self
+            .entity_overrides
+            .get(&entity_key)
+            .try_to_dictionary()
+            .unwrap_or_else(Dictionary::new);
+
+        let value = unsafe { node.get(property) };
+        properties.set(&Variant::from_str(property_key), &value);
+        self.entity_overrides
+            .set(&entity_key, &Variant::from_dictionary(&properties));
+        true
+    }
+
+    // Rebuilds only the entities that differ from the previous build
+    #[export]
+    pub fn rebuild_changed(&mut self, owner: Spatial) {
+        godot_print!("Rebuild changed");
+        self.build(owner, true);
+    }
+
+    // Rebuilds maps when they, or their textures, change on disk
+    #[export]
+    pub fn _process(&mut self, mut owner: Spatial, _delta: f64) {
+        if !self.automatic_rebuild {
+            self.file_watcher = None;
+            return;
+        }
+
+        // Paths are only gathered when the watcher is created, since globalizing them every frame
+        // adds up. Anything that changes them drops the watcher through reset_file_watcher.
+        let changed = match &mut self.file_watcher {
+            Some(file_watcher) => file_watcher.poll(),
+            None => {
+                self.file_watcher = Some(FileWatcher::new(self.watched_paths()));
+                Vec::new()
+            }
+        };
+
+        if changed.is_empty() {
+            return;
+        }
+
+        // Texture changes can affect every entity, so only map edits rebuild incrementally
+        let map_path = self.watched_map_path();
+        let is_map_source = |path: &std::path::PathBuf| {
+            Some(path) == map_path.as_ref() || self.instance_paths.contains(path)
+        };
+        if !changed.iter().all(is_map_source) {
+            godot_print!("Map textures changed");
             unsafe {
                 owner.call_deferred("set_rebuild".into(), &[Variant::from_bool(true)]);
             }
+            return;
+        }
+
+        godot_print!("Map file changed");
+        let map_changed = changed.iter().any(|path| Some(path) == map_path.as_ref());
+        match Instance::<QuakeMap>::from_variant(&self.map_resource) {
+            // Resources track their own revision, and rebuild through map_resource_changed.
+            // Instanced maps aren't part of the resource, so their edits rebuild directly.
+            Ok(quake_map) if self.map_type == MapType::Resource && map_changed => unsafe {
+                quake_map.into_base().call_deferred("reload".into(), &[]);
+            },
+            _ => unsafe {
+                owner.call_deferred("rebuild_changed".into(), &[]);
+            },
+        }
+    }
+
+    // Export
+    // Writes the map as it's currently built, including its pivots, layers and overrides
+    #[export]
+    pub fn export_gltf(&mut self, owner: Spatial, path: GodotString) -> bool {
+        godot_print!("Exporting map to {}", path.to_string());
+
+        let path = gdnative::ProjectSettings::godot_singleton()
+            .globalize_path(path)
+            .to_string();
+        let root = unsafe { owner.cast::<Node>().unwrap() };
+
+        match crate::qodot_map::export::gltf::write_gltf_from_nodes(root, &path) {
+            Ok(()) => {
+                godot_print!("Export complete");
+                true
+            }
+            Err(err) => {
+                godot_error!("Failed to export map: {}", err);
+                false
+            }
         }
     }
 
     // Overrides
     #[export]
-    pub fn _ready(&mut self, owner: Spatial) {
+    pub fn _ready(&mut self, mut owner: Spatial) {
+        // Watching is an editor convenience, and shouldn't cost anything in-game
+        let is_editor = gdnative::Engine::godot_singleton().is_editor_hint();
+        unsafe {
+            owner.set_process(is_editor);
+        }
+
+        if is_editor {
+            crate::qodot_map::build::smoothing::register_project_settings();
+        }
+
         if self.map_type == MapType::Resource {
             let quake_map = Instance::<QuakeMap>::from_variant(&self.map_resource);
             match quake_map {
@@ -148,6 +315,15 @@ impl QodotMap {
                         None,
                     ),
                 ));
+                property_list.push(&Variant::from_dictionary(
+                    &crate::util::build_property_dictionary(
+                        "automatic_rebuild",
+                        gdnative::GlobalConstants::TYPE_BOOL,
+                        None,
+                        None,
+                        None,
+                    ),
+                ));
             }
         }
 
@@ -304,6 +480,250 @@ impl QodotMap {
             ),
         ));
 
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "weld_vertices",
+                gdnative::GlobalConstants::TYPE_BOOL,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "Coordinates",
+                gdnative::GlobalConstants::TYPE_STRING,
+                None,
+                None,
+                Some(gdnative::GlobalConstants::PROPERTY_USAGE_GROUP),
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "up_axis",
+                gdnative::GlobalConstants::TYPE_INT,
+                Some(gdnative::GlobalConstants::PROPERTY_HINT_ENUM),
+                Some("+X,+Y,+Z,-X,-Y,-Z"),
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "forward_axis",
+                gdnative::GlobalConstants::TYPE_INT,
+                Some(gdnative::GlobalConstants::PROPERTY_HINT_ENUM),
+                Some("+X,+Y,+Z,-X,-Y,-Z"),
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "handedness",
+                gdnative::GlobalConstants::TYPE_INT,
+                Some(gdnative::GlobalConstants::PROPERTY_HINT_ENUM),
+                Some("Right,Left"),
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "axis_scale",
+                gdnative::GlobalConstants::TYPE_VECTOR3,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "Batching",
+                gdnative::GlobalConstants::TYPE_STRING,
+                None,
+                None,
+                Some(gdnative::GlobalConstants::PROPERTY_USAGE_GROUP),
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "batch_meshes",
+                gdnative::GlobalConstants::TYPE_BOOL,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        if self.batch_meshes {
+            property_list.push(&Variant::from_dictionary(
+                &crate::util::build_property_dictionary(
+                    "batch_brush_entities",
+                    gdnative::GlobalConstants::TYPE_BOOL,
+                    None,
+                    None,
+                    None,
+                ),
+            ));
+
+            property_list.push(&Variant::from_dictionary(
+                &crate::util::build_property_dictionary(
+                    "batch_cell_size",
+                    gdnative::GlobalConstants::TYPE_REAL,
+                    None,
+                    None,
+                    None,
+                ),
+            ));
+        }
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "Collision",
+                gdnative::GlobalConstants::TYPE_STRING,
+                None,
+                None,
+                Some(gdnative::GlobalConstants::PROPERTY_USAGE_GROUP),
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "collision_cell_size",
+                gdnative::GlobalConstants::TYPE_REAL,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "max_convex_shapes_per_body",
+                gdnative::GlobalConstants::TYPE_INT,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "Resources",
+                gdnative::GlobalConstants::TYPE_STRING,
+                None,
+                None,
+                Some(gdnative::GlobalConstants::PROPERTY_USAGE_GROUP),
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "cache_resources",
+                gdnative::GlobalConstants::TYPE_BOOL,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        if self.cache_resources {
+            property_list.push(&Variant::from_dictionary(
+                &crate::util::build_property_dictionary(
+                    "resource_cache_directory",
+                    gdnative::GlobalConstants::TYPE_STRING,
+                    Some(gdnative::GlobalConstants::PROPERTY_HINT_DIR),
+                    None,
+                    None,
+                ),
+            ));
+        }
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "Entities",
+                gdnative::GlobalConstants::TYPE_STRING,
+                None,
+                None,
+                Some(gdnative::GlobalConstants::PROPERTY_USAGE_GROUP),
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "choices_as_names",
+                gdnative::GlobalConstants::TYPE_BOOL,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "expand_flags",
+                gdnative::GlobalConstants::TYPE_BOOL,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "rotate_point_entities",
+                gdnative::GlobalConstants::TYPE_BOOL,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "trenchbroom_hierarchy",
+                gdnative::GlobalConstants::TYPE_BOOL,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "entity_overrides",
+                gdnative::GlobalConstants::TYPE_DICTIONARY,
+                None,
+                None,
+                None,
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "TrenchBroom",
+                gdnative::GlobalConstants::TYPE_STRING,
+                None,
+                None,
+                Some(gdnative::GlobalConstants::PROPERTY_USAGE_GROUP),
+            ),
+        ));
+
+        property_list.push(&Variant::from_dictionary(
+            &crate::util::build_property_dictionary(
+                "export_trenchbroom_game",
+                gdnative::GlobalConstants::TYPE_STRING,
+                Some(gdnative::GlobalConstants::PROPERTY_HINT_GLOBAL_DIR),
+                None,
+                None,
+            ),
+        ));
+
         property_list
     }
 }
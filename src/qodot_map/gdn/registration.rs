@@ -1,6 +1,7 @@
 use crate::QodotMap;
 use gdnative::{
-    init::ClassBuilder, GodotString, Resource, StringArray,
+    init::{ClassBuilder, ExportInfo, PropertyUsage, Signal, SignalArgument},
+    Dictionary, GodotString, Resource, StringArray, Variant, VariantType, Vector3,
 };
 
 pub fn register_qodot_map(builder: &ClassBuilder<QodotMap>) {
@@ -178,4 +179,158 @@ pub fn register_qodot_map(builder: &ClassBuilder<QodotMap>) {
         .with_setter(QodotMap::set_chunk_size)
         .with_usage(gdnative::init::property::Usage::NOEDITOR)
         .done();
+
+    builder
+        .add_property::<bool>("weld_vertices")
+        .with_default(false)
+        .with_getter(QodotMap::get_weld_vertices)
+        .with_setter(QodotMap::set_weld_vertices)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<i32>("up_axis")
+        .with_default(2)
+        .with_getter(QodotMap::get_up_axis)
+        .with_setter(QodotMap::set_up_axis)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<i32>("forward_axis")
+        .with_default(0)
+        .with_getter(QodotMap::get_forward_axis)
+        .with_setter(QodotMap::set_forward_axis)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<i32>("handedness")
+        .with_default(0)
+        .with_getter(QodotMap::get_handedness)
+        .with_setter(QodotMap::set_handedness)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<Vector3>("axis_scale")
+        .with_default(Vector3::new(1.0, 1.0, 1.0))
+        .with_getter(QodotMap::get_axis_scale)
+        .with_setter(QodotMap::set_axis_scale)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<bool>("batch_meshes")
+        .with_default(false)
+        .with_getter(QodotMap::get_batch_meshes)
+        .with_setter(QodotMap::set_batch_meshes)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<bool>("batch_brush_entities")
+        .with_default(false)
+        .with_getter(QodotMap::get_batch_brush_entities)
+        .with_setter(QodotMap::set_batch_brush_entities)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<f32>("batch_cell_size")
+        .with_default(1024.0)
+        .with_getter(QodotMap::get_batch_cell_size)
+        .with_setter(QodotMap::set_batch_cell_size)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<f32>("collision_cell_size")
+        .with_default(0.0)
+        .with_getter(QodotMap::get_collision_cell_size)
+        .with_setter(QodotMap::set_collision_cell_size)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<i32>("max_convex_shapes_per_body")
+        .with_default(0)
+        .with_getter(QodotMap::get_max_convex_shapes_per_body)
+        .with_setter(QodotMap::set_max_convex_shapes_per_body)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<bool>("cache_resources")
+        .with_default(false)
+        .with_getter(QodotMap::get_cache_resources)
+        .with_setter(QodotMap::set_cache_resources)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<GodotString>("resource_cache_directory")
+        .with_default("res://qodot_cache".into())
+        .with_getter(QodotMap::get_resource_cache_directory)
+        .with_setter(QodotMap::set_resource_cache_directory)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<bool>("choices_as_names")
+        .with_default(false)
+        .with_getter(QodotMap::get_choices_as_names)
+        .with_setter(QodotMap::set_choices_as_names)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<bool>("expand_flags")
+        .with_default(false)
+        .with_getter(QodotMap::get_expand_flags)
+        .with_setter(QodotMap::set_expand_flags)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<bool>("rotate_point_entities")
+        .with_default(false)
+        .with_getter(QodotMap::get_rotate_point_entities)
+        .with_setter(QodotMap::set_rotate_point_entities)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<bool>("trenchbroom_hierarchy")
+        .with_default(false)
+        .with_getter(QodotMap::get_trenchbroom_hierarchy)
+        .with_setter(QodotMap::set_trenchbroom_hierarchy)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<Dictionary>("entity_overrides")
+        .with_default(Dictionary::new())
+        .with_getter(QodotMap::get_entity_overrides)
+        .with_setter(QodotMap::set_entity_overrides)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder
+        .add_property::<GodotString>("export_trenchbroom_game")
+        .with_default(GodotString::new())
+        .with_getter(QodotMap::get_export_trenchbroom_game)
+        .with_setter(QodotMap::set_export_trenchbroom_game)
+        .with_usage(gdnative::init::property::Usage::NOEDITOR)
+        .done();
+
+    builder.add_signal(Signal {
+        name: "compiled",
+        args: &[SignalArgument {
+            name: "report",
+            default: Variant::new(),
+            export_info: ExportInfo::new(VariantType::Dictionary),
+            usage: PropertyUsage::DEFAULT,
+        }],
+    });
 }
@@ -5,15 +5,15 @@ use crate::{
 };
 use crate::{ForgeGameData, QodotGameData, QodotMap, QuakeWad, game_data::QodotMaterialData};
 use gdnative::{
-    godot_error, godot_print, FromVariant, GodotString, Instance, Object, Resource,
-    Spatial, StringArray, Variant, VariantArray,
+    godot_error, godot_print, Dictionary, FromVariant, GodotString, Instance, Object, Resource,
+    Spatial, StringArray, Variant, VariantArray, Vector3,
 };
 
 impl QodotMap {
     // Setters
     pub fn set_rebuild(&mut self, owner: Spatial, _: bool) {
         godot_print!("Rebuild");
-        self.build(owner);
+        self.build(owner, false);
     }
 
     pub fn set_forge_game_data(&mut self, _: Spatial, new_forge_game_data: Option<Resource>) {
@@ -45,6 +45,7 @@ impl QodotMap {
     }
 
     pub fn set_map_type(&mut self, mut owner: Spatial, new_map_type: i32) {
+        self.reset_file_watcher();
         let new_map_type: MapType = new_map_type.into();
         if self.map_type != new_map_type {
             self.map_type = new_map_type;
@@ -56,6 +57,7 @@ impl QodotMap {
     }
 
     pub fn set_map_resource(&mut self, owner: Spatial, new_map_resource: Option<Resource>) {
+        self.reset_file_watcher();
         if let Ok(map_resource) = Instance::<QuakeMap>::from_variant(&self.map_resource) {
             unsafe {
                 map_resource.into_base().disconnect(
@@ -112,10 +114,12 @@ impl QodotMap {
     }
 
     pub fn set_map_file(&mut self, _: Spatial, new_map_file: GodotString) {
-        self.map_file = new_map_file
+        self.reset_file_watcher();
+        self.map_file = new_map_file;
     }
 
     pub fn set_texture_type(&mut self, mut owner: Spatial, new_texture_type: i32) {
+        self.reset_file_watcher();
         let new_texture_type: TextureType = new_texture_type.into();
         if self.texture_type != new_texture_type {
             self.texture_type = new_texture_type;
@@ -127,10 +131,12 @@ impl QodotMap {
     }
 
     pub fn set_base_search_path(&mut self, _: Spatial, new_base_search_path: GodotString) {
-        self.texture_type = TextureType::TextureResources(new_base_search_path)
+        self.reset_file_watcher();
+        self.texture_type = TextureType::TextureResources(new_base_search_path);
     }
 
     pub fn set_wad_resource(&mut self, _: Spatial, new_wad_resource: Option<Resource>) {
+        self.reset_file_watcher();
         if let Some(resource) = new_wad_resource {
             let quake_wad_result =
                 Instance::<QuakeWad>::from_variant(&Variant::from_object(&resource));
@@ -147,10 +153,12 @@ impl QodotMap {
     }
 
     pub fn set_wad_file(&mut self, _: Spatial, new_wad_file: GodotString) {
-        self.texture_type = TextureType::WadFile(new_wad_file)
+        self.reset_file_watcher();
+        self.texture_type = TextureType::WadFile(new_wad_file);
     }
 
     pub fn set_wad_palette_type(&mut self, mut owner: Spatial, new_wad_palette_type: i32) {
+        self.reset_file_watcher();
         self.wad_palette_type = new_wad_palette_type.into();
 
         unsafe {
@@ -163,6 +171,7 @@ impl QodotMap {
         _: Spatial,
         new_wad_palette_resource: Option<Resource>,
     ) {
+        self.reset_file_watcher();
         let new_wad_palette_resource = match new_wad_palette_resource {
             Some(new_wad_palette_resource) => Variant::from_object(&new_wad_palette_resource),
             None => Variant::new(),
@@ -176,7 +185,8 @@ impl QodotMap {
     }
 
     pub fn set_wad_palette_file(&mut self, _: Spatial, new_wad_palette_file: GodotString) {
-        self.wad_palette_type = PaletteType::File(new_wad_palette_file)
+        self.reset_file_watcher();
+        self.wad_palette_type = PaletteType::File(new_wad_palette_file);
     }
 
     pub fn set_brush_texture_blacklist(
@@ -217,4 +227,101 @@ impl QodotMap {
     pub fn set_chunk_size(&mut self, _owner: Spatial, new_chunk_size: i32) {
         self.chunk_size = new_chunk_size;
     }
+
+    pub fn set_weld_vertices(&mut self, _owner: Spatial, new_weld_vertices: bool) {
+        self.weld_vertices = new_weld_vertices;
+    }
+
+    pub fn set_up_axis(&mut self, _owner: Spatial, new_up_axis: i32) {
+        self.up_axis = new_up_axis.into();
+    }
+
+    pub fn set_forward_axis(&mut self, _owner: Spatial, new_forward_axis: i32) {
+        self.forward_axis = new_forward_axis.into();
+    }
+
+    pub fn set_handedness(&mut self, _owner: Spatial, new_handedness: i32) {
+        self.handedness = new_handedness.into();
+    }
+
+    pub fn set_axis_scale(&mut self, _owner: Spatial, new_axis_scale: Vector3) {
+        self.axis_scale = new_axis_scale;
+    }
+
+    pub fn set_batch_meshes(&mut self, mut owner: Spatial, new_batch_meshes: bool) {
+        if self.batch_meshes != new_batch_meshes {
+            self.batch_meshes = new_batch_meshes;
+            unsafe {
+                owner.property_list_changed_notify();
+            }
+        }
+    }
+
+    pub fn set_batch_brush_entities(&mut self, _owner: Spatial, new_batch_brush_entities: bool) {
+        self.batch_brush_entities = new_batch_brush_entities;
+    }
+
+    pub fn set_batch_cell_size(&mut self, _owner: Spatial, new_batch_cell_size: f32) {
+        self.batch_cell_size = new_batch_cell_size;
+    }
+
+    pub fn set_collision_cell_size(&mut self, _owner: Spatial, new_collision_cell_size: f32) {
+        self.collision_cell_size = new_collision_cell_size;
+    }
+
+    pub fn set_max_convex_shapes_per_body(
+        &mut self,
+        _owner: Spatial,
+        new_max_convex_shapes_per_body: i32,
+    ) {
+        self.max_convex_shapes_per_body = new_max_convex_shapes_per_body;
+    }
+
+    pub fn set_cache_resources(&mut self, mut owner: Spatial, new_cache_resources: bool) {
+        if self.cache_resources != new_cache_resources {
+            self.cache_resources = new_cache_resources;
+            unsafe {
+                owner.property_list_changed_notify();
+            }
+        }
+    }
+
+    pub fn set_resource_cache_directory(
+        &mut self,
+        _owner: Spatial,
+        new_resource_cache_directory: GodotString,
+    ) {
+        self.resource_cache_directory = new_resource_cache_directory;
+    }
+
+    pub fn set_choices_as_names(&mut self, _owner: Spatial, new_choices_as_names: bool) {
+        self.choices_as_names = new_choices_as_names;
+    }
+
+    pub fn set_expand_flags(&mut self, _owner: Spatial, new_expand_flags: bool) {
+        self.expand_flags = new_expand_flags;
+    }
+
+    pub fn set_rotate_point_entities(&mut self, _owner: Spatial, new_rotate_point_entities: bool) {
+        self.rotate_point_entities = new_rotate_point_entities;
+    }
+
+    pub fn set_trenchbroom_hierarchy(&mut self, _owner: Spatial, new_trenchbroom_hierarchy: bool) {
+        self.trenchbroom_hierarchy = new_trenchbroom_hierarchy;
+    }
+
+    pub fn set_entity_overrides(&mut self, _owner: Spatial, new_entity_overrides: Dictionary) {
+        self.entity_overrides = new_entity_overrides;
+    }
+
+    pub fn set_export_trenchbroom_game(&mut self, owner: Spatial, directory: GodotString) {
+        if directory.is_empty() {
+            return;
+        }
+
+        match self.write_trenchbroom_game(owner, directory) {
+            Ok(folder) => godot_print!("Wrote TrenchBroom game configuration to {:?}", folder),
+            Err(err) => godot_error!("Failed to write TrenchBroom game configuration: {}", err),
+        }
+    }
 }
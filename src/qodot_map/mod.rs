@@ -1,12 +1,12 @@
 #![allow(clippy::transmute_ptr_to_ptr)] // Suppress gdnative clippy warnings
 
 use gdnative::{
-    godot_error, godot_print,
+    godot_error, godot_print, godot_warn,
     user_data::{LocalCellData, RwLockData},
-    FromVariant, GodotString, Instance, Map, MapMut, NativeClass, Node, Spatial, StringArray,
-    Variant,
+    Dictionary, FromVariant, GodotString, Instance, Map, MapMut, NativeClass, Node, Spatial,
+    StringArray, Variant,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     game_data::{DefaultMaterialType, QodotGameData, QodotMaterialData},
@@ -15,7 +15,11 @@ use crate::{
 };
 
 mod build;
+mod compile;
+pub mod export;
 mod gdn;
+mod overrides;
+mod watcher;
 
 pub use build::worker::QodotBuildWorker;
 use texture_loader::PaletteType;
@@ -71,6 +75,34 @@ pub struct QodotMap {
 
     inverse_scale_factor: f32,
     chunk_size: i32,
+    weld_vertices: bool,
+
+    up_axis: build::coordinates::Axis,
+    forward_axis: build::coordinates::Axis,
+    handedness: build::coordinates::Handedness,
+    axis_scale: gdnative::Vector3,
+
+    batch_meshes: bool,
+    batch_brush_entities: bool,
+    batch_cell_size: f32,
+
+    collision_cell_size: f32,
+    max_convex_shapes_per_body: i32,
+
+    cache_resources: bool,
+    resource_cache_directory: GodotString,
+
+    choices_as_names: bool,
+    expand_flags: bool,
+    rotate_point_entities: bool,
+    trenchbroom_hierarchy: bool,
+    entity_overrides: Dictionary,
+
+    file_watcher: Option<watcher::FileWatcher>,
+    detached_nodes: Vec<overrides::DetachedNode>,
+    instance_paths: Vec<std::path::PathBuf>,
+    texture_paths: Vec<std::path::PathBuf>,
+    compile_job: Option<compile::CompileJob>,
 }
 
 impl QodotMap {
@@ -99,6 +131,34 @@ impl QodotMap {
 
         let inverse_scale_factor = 16.0;
         let chunk_size = 64;
+        let weld_vertices = false;
+
+        let up_axis = build::coordinates::Axis::PositiveZ;
+        let forward_axis = build::coordinates::Axis::PositiveX;
+        let handedness = build::coordinates::Handedness::Right;
+        let axis_scale = gdnative::Vector3::new(1.0, 1.0, 1.0);
+
+        let batch_meshes = false;
+        let batch_brush_entities = false;
+        let batch_cell_size = 1024.0;
+
+        let collision_cell_size = 0.0;
+        let max_convex_shapes_per_body = 0;
+
+        let cache_resources = false;
+        let resource_cache_directory = GodotString::from_str("res://qodot_cache");
+
+        let choices_as_names = false;
+        let expand_flags = false;
+        let rotate_point_entities = false;
+        let trenchbroom_hierarchy = false;
+        let entity_overrides = Dictionary::new();
+
+        let file_watcher = None;
+        let detached_nodes = Vec::new();
+        let instance_paths = Vec::new();
+        let texture_paths = Vec::new();
+        let compile_job = None;
 
         QodotMap {
             forge_game_data,
@@ -121,19 +181,53 @@ impl QodotMap {
 
             inverse_scale_factor,
             chunk_size,
+            weld_vertices,
+
+            up_axis,
+            forward_axis,
+            handedness,
+            axis_scale,
+
+            batch_meshes,
+            batch_brush_entities,
+            batch_cell_size,
+
+            collision_cell_size,
+            max_convex_shapes_per_body,
+
+            cache_resources,
+            resource_cache_directory,
+
+            choices_as_names,
+            expand_flags,
+            rotate_point_entities,
+            trenchbroom_hierarchy,
+            entity_overrides,
+
+            file_watcher,
+            detached_nodes,
+            instance_paths,
+            texture_paths,
+            compile_job,
         }
     }
 
     // Business Logic
-    fn build(&mut self, mut owner: Spatial) {
-        godot_print!("Clearing entities");
-        self.clear_entities(owner);
-
+    fn build(&mut self, mut owner: Spatial, incremental: bool) {
         godot_print!("Getting map file path");
         let map_file = match self.get_map_path(owner) {
             Ok(map_file) => map_file,
             Err(err) => {
-                godot_error!("Failed to get map file path: {}", err);
+                self.build_failed(owner, format!("Failed to get map file path: {}", err));
+                return;
+            }
+        };
+
+        godot_print!("Getting coordinate system");
+        let coordinates = match self.get_coordinate_system() {
+            Ok(coordinates) => coordinates,
+            Err(err) => {
+                self.build_failed(owner, format!("Invalid coordinate system: {}", err));
                 return;
             }
         };
@@ -142,16 +236,41 @@ impl QodotMap {
         let quarchitect_forge_game_data = match self.get_quarchitect_forge_game_data(owner) {
             Ok(quarchitect_forge_game_data) => quarchitect_forge_game_data,
             Err(err) => {
-                godot_error!("Failed to load quarchitect forge game data: {}", err);
+                self.build_failed(
+                    owner,
+                    format!("Failed to load quarchitect forge game data: {}", err),
+                );
                 return;
             }
         };
 
+        let color255_properties = match self.get_color255_properties(owner) {
+            Ok(color255_properties) => color255_properties,
+            Err(err) => {
+                self.build_failed(owner, format!("Failed to read colour ranges: {}", err));
+                return;
+            }
+        };
+
+        if let (Some(qodot_game_data), Some(forge_game_data)) = (
+            self.get_qodot_game_data(owner),
+            self.get_forge_game_data(owner),
+        ) {
+            match crate::game_data::validate(&qodot_game_data, &forge_game_data) {
+                Ok(warnings) => {
+                    for warning in warnings {
+                        self.build_warning(warning);
+                    }
+                }
+                Err(err) => self.build_warning(format!("Failed to validate game data: {}", err)),
+            }
+        }
+
         godot_print!("Getting quarchitect game data");
         let quarchitect_game_data = match self.get_quarchitect_game_data(owner) {
             Ok(quarchitect_game_data) => quarchitect_game_data,
             Err(err) => {
-                godot_error!("Failed to load game data: {}", err);
+                self.build_failed(owner, format!("Failed to load game data: {}", err));
                 return;
             }
         };
@@ -163,6 +282,12 @@ impl QodotMap {
                 Err(_) => HashMap::new(),
             };
 
+        let texture_paths = self.loaded_texture_paths(&texture_info);
+        if texture_paths != self.texture_paths {
+            self.texture_paths = texture_paths;
+            self.reset_file_watcher();
+        }
+
         godot_print!("Assembling texture blacklist");
         let texture_blacklist = self.get_texture_blacklist();
 
@@ -191,12 +316,49 @@ impl QodotMap {
             }) {
                 Ok(()) => (),
                 Err(err) => {
-                    godot_error!("Error fetching default material data: {:?}", err);
+                    self.build_failed(
+                        owner,
+                        format!("Error fetching default material data: {:?}", err),
+                    );
                     return;
                 }
             };
         }
 
+        let settings_fingerprint = self.settings_fingerprint(
+            &coordinates,
+            &quarchitect_forge_game_data,
+            &color255_properties,
+            &quarchitect_game_data,
+            &default_material,
+            default_spatial_material_texture_param,
+            &default_shader_material_texture_param,
+        );
+
+        // Batches span entities and cached resources are named by entity index,
+        // so neither can be patched in place
+        let incremental = if incremental && (self.batch_meshes || self.cache_resources) {
+            godot_print!("Mesh batching or resource caching enabled, rebuilding all entities");
+            false
+        } else {
+            incremental
+        };
+
+        // Hand-added nodes are held aside until the build completes
+        godot_print!("Detaching user nodes");
+        let entities = overrides::entity_nodes(unsafe { owner.cast::<Node>().unwrap() });
+        let mut detached_nodes = overrides::detach_user_nodes(&entities);
+        self.detached_nodes.append(&mut detached_nodes);
+
+        godot_print!("Clearing entities");
+        let previous_entities = self.clear_entities(owner, incremental);
+
+        let resource_cache_directory = if self.cache_resources {
+            Some(self.resource_cache_namespace(owner))
+        } else {
+            None
+        };
+
         godot_print!("Spawning build worker");
         let build_worker = Instance::<build::worker::QodotBuildWorker>::new();
         let (mut base, script) = build_worker.decouple();
@@ -211,6 +373,7 @@ impl QodotMap {
                 owner,
                 build::worker::Config::new(
                     quarchitect_forge_game_data,
+                    color255_properties,
                     quarchitect_game_data,
                     map_file,
                     texture_info,
@@ -219,60 +382,387 @@ impl QodotMap {
                     default_spatial_material_texture_param,
                     default_shader_material_texture_param,
                     self.chunk_size,
+                    coordinates,
+                    self.trenchbroom_hierarchy,
+                    settings_fingerprint,
+                    resource_cache_directory,
+                    previous_entities,
                 ),
             )
         }) {
             Ok(_) => (),
             Err(err) => {
-                godot_error!("Error running build worker: {:?}", err);
+                self.build_failed(owner, format!("Error running build worker: {:?}", err));
+            }
+        }
+    }
+
+    // Everything besides the map itself that shapes spawned entities. Entity fingerprints
+    // include it, so incremental builds don't keep nodes built with old settings. Game data and
+    // materials are hashed by content, so editing them in place counts as a change.
+    fn settings_fingerprint(
+        &self,
+        coordinates: &build::coordinates::CoordinateSystem,
+        forge_game_data: &quarchitect::game_data::forge::GameData,
+        color255_properties: &HashMap<String, HashSet<String>>,
+        game_data: &quarchitect::game_data::GameData,
+        default_material: &Variant,
+        default_spatial_material_texture_param: i32,
+        default_shader_material_texture_param: &GodotString,
+    ) -> u64 {
+        let string_array = |array: &StringArray| -> Vec<String> {
+            (0..array.len()).map(|i| array.get(i).to_string()).collect()
+        };
+
+        // Sorted, since hash map order varies between runs
+        let mut color255_properties: Vec<(&String, Vec<&String>)> = color255_properties
+            .iter()
+            .map(|(class_name, properties)| {
+                let mut properties: Vec<&String> = properties.iter().collect();
+                properties.sort();
+                (class_name, properties)
+            })
+            .collect();
+        color255_properties.sort();
+
+        build::fingerprint::settings_fingerprint(&[
+            format!("{:?}", coordinates),
+            format!("{:?}", forge_game_data.definitions),
+            format!("{:?}", color255_properties),
+            format!("{:?}", game_data),
+            format!("{:?}", self.texture_type),
+            format!("{:?}", self.wad_palette_type),
+            format!("{:?}", string_array(&self.brush_texture_blacklist)),
+            format!("{:?}", string_array(&self.plane_texture_blacklist)),
+            resource_fingerprint(default_material),
+            default_spatial_material_texture_param.to_string(),
+            default_shader_material_texture_param.to_string(),
+            self.weld_vertices.to_string(),
+            format!("{:?}", build::smoothing::default_phong_angle()),
+            format!(
+                "{} {}",
+                self.collision_cell_size, self.max_convex_shapes_per_body
+            ),
+            self.choices_as_names.to_string(),
+            self.expand_flags.to_string(),
+            self.rotate_point_entities.to_string(),
+            self.trenchbroom_hierarchy.to_string(),
+        ])
+    }
+
+    // This map's own directory inside the resource cache
+    fn resource_cache_namespace(&self, owner: Spatial) -> GodotString {
+        let directory = self.resource_cache_directory.to_string();
+        if directory.is_empty() {
+            return GodotString::new();
+        }
+
+        format!(
+            "{}/{}",
+            directory.trim_end_matches('/'),
+            self.scene_namespace(owner)
+        )
+        .into()
+    }
+
+    // A name unique to this map, made from the scene it's saved in and its path within that
+    // scene, for files generated on its behalf
+    fn scene_namespace(&self, owner: Spatial) -> String {
+        let (scene_path, node_path) = unsafe {
+            match owner.get_owner() {
+                Some(scene_root) => (
+                    scene_root.get_filename().to_string(),
+                    scene_root.get_path_to(owner.cast::<Node>()).to_string(),
+                ),
+                None => (owner.get_filename().to_string(), ".".to_string()),
+            }
+        };
+
+        build::resource_cache::namespace(&scene_path, &node_path)
+    }
+
+    // Restores user edits once the build worker has repopulated the tree
+    fn apply_overrides(&mut self, owner: Spatial) {
+        let root = unsafe { owner.cast::<Node>().unwrap() };
+
+        let entities = overrides::entity_nodes(root);
+        let detached_nodes = std::mem::replace(&mut self.detached_nodes, Vec::new());
+        overrides::reattach_user_nodes(root, &entities, detached_nodes);
+        overrides::apply_property_overrides(&entities, &self.entity_overrides);
+    }
+
+    // Writes a TrenchBroom game folder describing this map's game data and textures
+    fn write_trenchbroom_game(
+        &mut self,
+        owner: Spatial,
+        directory: GodotString,
+    ) -> Result<std::path::PathBuf, String> {
+        let forge_game_data = match self.get_forge_game_data(owner) {
+            Some(forge_game_data) => forge_game_data,
+            None => return Err("No forge game data present".into()),
+        };
+
+        let forge_game_data =
+            Instance::<super::ForgeGameData>::from_variant(&Variant::from_object(&forge_game_data))
+                .map_err(|err| err.to_string())?;
+        let forge_game_data: RwLockData<super::ForgeGameData> = forge_game_data.into_script();
+        let (name, fgd) = forge_game_data
+            .map(|forge_game_data| (forge_game_data.inner().name, forge_game_data.fgd()))
+            .map_err(|err| format!("{:?}", err))?;
+
+        let name = if name.is_empty() {
+            "Qodot".to_string()
+        } else {
+            name
+        };
+
+        let textures = match &self.texture_type {
+            texture_loader::TextureType::TextureResources(base_path) => {
+                export::trenchbroom::TexturePackage::Directory {
+                    root: texture_root(base_path),
+                }
+            }
+            _ => export::trenchbroom::TexturePackage::Wad {
+                palette: match &self.wad_palette_type {
+                    PaletteType::File(palette_file) => Some(project_relative_path(palette_file)),
+                    PaletteType::Resource(palette_resource) => palette_resource
+                        .try_to_object::<gdnative::Resource>()
+                        .map(|resource| project_relative_path(&resource.get_path())),
+                },
+            },
+        };
+
+        let config = export::trenchbroom::GameConfig {
+            name,
+            icon: Some("Icon.png".into()),
+            textures,
+            brush_texture_blacklist: self.get_texture_blacklist_strings(true),
+            plane_texture_blacklist: self.get_texture_blacklist_strings(false),
+            inverse_scale_factor: self.inverse_scale_factor,
+            axis_scale: [self.axis_scale.x, self.axis_scale.y, self.axis_scale.z],
+        };
+
+        let directory = gdnative::ProjectSettings::godot_singleton()
+            .globalize_path(directory)
+            .to_string();
+
+        let folder = export::trenchbroom::write_game_folder(&directory, &config, &fgd)?;
+
+        let icon_path = folder.join("Icon.png");
+        if let Err(err) = save_project_icon(&icon_path.to_string_lossy()) {
+            godot_warn!("Failed to write TrenchBroom icon: {}", err);
+        }
+
+        Ok(folder)
+    }
+
+    // Drops the file watcher, so it's recreated with fresh paths on the next frame
+    fn reset_file_watcher(&mut self) {
+        self.file_watcher = None;
+    }
+
+    // The map file on disk, if there is one
+    fn watched_map_path(&self) -> Option<std::path::PathBuf> {
+        let map_file = match self.map_type {
+            MapType::File => self.map_file.clone(),
+            MapType::Resource => match Instance::<QuakeMap>::from_variant(&self.map_resource) {
+                Ok(quake_map) => {
+                    let (base, script) = quake_map.decouple();
+                    script
+                        .map(|script| script.backing_file(base))
+                        .unwrap_or_default()
+                }
+                Err(_) => GodotString::new(),
+            },
+        };
+
+        if map_file.is_empty() {
+            return None;
+        }
+
+        let map_file = gdnative::ProjectSettings::godot_singleton().globalize_path(map_file);
+        Some(std::path::PathBuf::from(map_file.to_string()))
+    }
+
+    // Files a map is built from: the map itself and any maps it instances, plus the texture
+    // files its last build loaded
+    fn watched_paths(&self) -> Vec<std::path::PathBuf> {
+        let mut paths: Vec<std::path::PathBuf> = self.watched_map_path().into_iter().collect();
+        paths.extend(self.instance_paths.iter().cloned());
+        paths.extend(self.texture_paths.iter().cloned());
+        paths
+    }
+
+    // The individual texture, WAD and palette files behind loaded textures. Only regular files
+    // are kept, so a directory setting like res:// never puts the whole project under watch,
+    // and anything in the resource cache is skipped, since builds write there themselves.
+    fn loaded_texture_paths(
+        &self,
+        texture_info: &HashMap<String, texture_loader::TextureInfo>,
+    ) -> Vec<std::path::PathBuf> {
+        let mut paths: Vec<GodotString> = Vec::new();
+
+        match &self.texture_type {
+            texture_loader::TextureType::TextureResources(_) => {
+                for texture in texture_info.values() {
+                    paths.push(texture.gdnative_data.get_path());
+                    paths.extend(texture.gdnative_extra.values().map(|extra| extra.get_path()));
+                }
+            }
+            texture_loader::TextureType::WadFile(wad_file) => paths.push(wad_file.clone()),
+            texture_loader::TextureType::WadResource(wad_resource) => {
+                if let Some(wad_resource) = wad_resource.try_to_object::<gdnative::Resource>() {
+                    paths.push(wad_resource.get_path());
+                }
             }
         }
+
+        match &self.wad_palette_type {
+            PaletteType::File(palette_file) => paths.push(palette_file.clone()),
+            PaletteType::Resource(palette_resource) => {
+                if let Some(palette_resource) =
+                    palette_resource.try_to_object::<gdnative::Resource>()
+                {
+                    paths.push(palette_resource.get_path());
+                }
+            }
+        }
+
+        let project_settings = gdnative::ProjectSettings::godot_singleton();
+        let globalize = |path: GodotString| {
+            std::path::PathBuf::from(project_settings.globalize_path(path).to_string())
+        };
+        let cache_directory = if self.resource_cache_directory.is_empty() {
+            None
+        } else {
+            Some(globalize(self.resource_cache_directory.clone()))
+        };
+        let is_cached = |path: &std::path::PathBuf| match &cache_directory {
+            Some(cache_directory) => path.starts_with(cache_directory),
+            None => false,
+        };
+
+        let mut paths: Vec<std::path::PathBuf> = paths
+            .into_iter()
+            .filter(|path| !path.is_empty())
+            .map(globalize)
+            .filter(|path| path.is_file() && !is_cached(path))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
     }
 
-    fn clear_entities(&self, mut owner: Spatial) {
+    // Removes spawned children. When keeping fingerprinted entities, they're left in place
+    // and returned by fingerprint so the build can reuse any that haven't changed.
+    fn clear_entities(
+        &self,
+        mut owner: Spatial,
+        keep_fingerprinted: bool,
+    ) -> HashMap<u64, Vec<Variant>> {
+        let mut previous_entities: HashMap<u64, Vec<Variant>> = HashMap::new();
+
         unsafe {
             for i in (0..owner.get_child_count()).rev() {
                 if let Some(mut child) = owner.get_child(i) {
-                    if child.has_meta(CHILD_META.into()) {
-                        owner.remove_child(Some(child));
-                        child.queue_free();
+                    if !child.has_meta(CHILD_META.into()) {
+                        continue;
+                    }
+
+                    let fingerprint = child
+                        .get_meta(build::fingerprint::META.into())
+                        .try_to_string()
+                        .and_then(|fingerprint| build::fingerprint::from_string(&fingerprint));
+
+                    match fingerprint {
+                        Some(fingerprint) if keep_fingerprinted => previous_entities
+                            .entry(fingerprint)
+                            .or_insert_with(Vec::new)
+                            .push(Variant::from_object(&child)),
+                        _ => {
+                            owner.remove_child(Some(child));
+                            child.queue_free();
+                        }
                     }
                 }
             }
         }
+
+        previous_entities
     }
 
     fn get_map_path(&mut self, owner: Spatial) -> Result<GodotString, String> {
-        match self.map_type {
+        let (map_path, base_path) = match self.map_type {
             MapType::Resource => match self.get_map_resource(owner) {
                 Some(resource) => {
                     let quake_map: Instance<QuakeMap> = Instance::try_from_base(resource).unwrap();
-                    let (base, _script) = quake_map.decouple();
-                    let path = (&base).get_path();
-                    let new_map_revision;
-                    unsafe {
-                        new_map_revision = base.get("revision".into()).try_to_i64();
-                    }
-                    match new_map_revision {
-                        Some(new_map_revision) => {
-                            self.set_map_revision(owner, Some(new_map_revision as i32))
-                        }
-                        None => {
-                            return Err("Failed to set new map revision".into());
-                        }
-                    }
-                    Ok(path)
+                    let (base, script) = quake_map.decouple();
+                    let (path, base_path, new_map_revision) = match script.map(|script| {
+                        (
+                            script.build_path(base),
+                            script.instance_base_path(base),
+                            script.get_revision(base),
+                        )
+                    }) {
+                        Ok(result) => result,
+                        Err(err) => return Err(format!("Failed to read map resource: {:?}", err)),
+                    };
+                    self.set_map_revision(owner, Some(new_map_revision));
+                    path.map(|path| (path, base_path))
                 }
                 None => Err("No map resource".into()),
             },
-            MapType::File => Ok(self.map_file.clone()),
+            MapType::File => Ok((self.map_file.clone(), self.map_file.clone())),
+        }?;
+
+        self.expand_instances(owner, map_path, base_path)
+    }
+
+    // Builds any maps instanced by misc_external_map or func_instance into a generated copy of
+    // the map, remembering their files so edits to them are watched too. Relative instance paths
+    // are resolved from base_path, which differs from map_path for embedded maps.
+    fn expand_instances(
+        &mut self,
+        owner: Spatial,
+        map_path: GodotString,
+        base_path: GodotString,
+    ) -> Result<GodotString, String> {
+        let project_settings = gdnative::ProjectSettings::godot_singleton();
+        let global_path = project_settings.globalize_path(map_path.clone()).to_string();
+        let global_base_path = if base_path.is_empty() {
+            global_path.clone()
+        } else {
+            project_settings.globalize_path(base_path).to_string()
+        };
+
+        match crate::map::instances::expand_instances(
+            std::path::Path::new(&global_path),
+            std::path::Path::new(&global_base_path),
+        ) {
+            Ok(Some(expanded)) => {
+                if self.instance_paths != expanded.instance_paths {
+                    self.instance_paths = expanded.instance_paths;
+                    self.reset_file_watcher();
+                }
+                let name = format!("instances_{}", self.scene_namespace(owner));
+                let path = crate::map::write_map_source(&name, &expanded.source)?;
+                Ok(path.to_string_lossy().as_ref().into())
+            }
+            Ok(None) => {
+                if !self.instance_paths.is_empty() {
+                    self.instance_paths.clear();
+                    self.reset_file_watcher();
+                }
+                Ok(map_path)
+            }
+            Err(err) => Err(format!("Failed to expand instances: {}", err)),
         }
     }
 
-    fn get_quarchitect_forge_game_data(
+    fn get_forge_game_data_script(
         &self,
         owner: Spatial,
-    ) -> Result<quarchitect::game_data::forge::GameData, String> {
+    ) -> Result<RwLockData<super::ForgeGameData>, String> {
         let forge_game_data = match self.get_forge_game_data(owner) {
             Some(forge_game_data) => forge_game_data,
             None => {
@@ -280,18 +770,49 @@ impl QodotMap {
             }
         };
 
-        // Extract QodotGameData instance
+        // Extract ForgeGameData instance
         let forge_game_data = Variant::from_object(&forge_game_data);
         let forge_game_data = Instance::<super::ForgeGameData>::from_variant(&forge_game_data);
-        let forge_game_data = match forge_game_data {
-            Ok(game_data) => game_data,
-            Err(err) => return Err(err.to_string()),
-        };
+        match forge_game_data {
+            Ok(game_data) => Ok(game_data.into_script()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
 
+    fn get_quarchitect_forge_game_data(
+        &self,
+        owner: Spatial,
+    ) -> Result<quarchitect::game_data::forge::GameData, String> {
         // Convert into quarchitect game data
-        let forge_game_data: RwLockData<super::ForgeGameData> = forge_game_data.into_script();
-        match forge_game_data.map(super::ForgeGameData::inner) {
-            Ok(quarchitect_forge_game_data) => Ok(quarchitect_forge_game_data),
+        let forge_game_data = self.get_forge_game_data_script(owner)?;
+        let quarchitect_forge_game_data = match forge_game_data.map(super::ForgeGameData::inner) {
+            Ok(quarchitect_forge_game_data) => quarchitect_forge_game_data,
+            Err(err) => return Err(format!("{:?}", err)),
+        };
+
+        // Resolve base classes so each definition carries its inherited properties
+        let (definitions, warnings) = crate::game_data::forge::flatten_definitions(
+            &quarchitect_forge_game_data.definitions,
+        )?;
+
+        for warning in warnings {
+            godot_warn!("{}", warning);
+        }
+
+        Ok(quarchitect::game_data::forge::GameData {
+            definitions,
+            ..quarchitect_forge_game_data
+        })
+    }
+
+    // Quarchitect stores every colour alike, so the FGD's declared ranges travel separately
+    fn get_color255_properties(
+        &self,
+        owner: Spatial,
+    ) -> Result<HashMap<String, HashSet<String>>, String> {
+        let forge_game_data = self.get_forge_game_data_script(owner)?;
+        match forge_game_data.map(super::ForgeGameData::color255_properties) {
+            Ok(color255_properties) => color255_properties,
             Err(err) => Err(format!("{:?}", err)),
         }
     }
@@ -323,29 +844,44 @@ impl QodotMap {
         }
     }
 
+    fn get_coordinate_system(&self) -> Result<build::coordinates::CoordinateSystem, String> {
+        build::coordinates::CoordinateSystem::new(
+            self.up_axis,
+            self.forward_axis,
+            self.handedness,
+            quarchitect::Vector3::new(self.axis_scale.x, self.axis_scale.y, self.axis_scale.z),
+            self.inverse_scale_factor,
+        )
+    }
+
     fn get_texture_blacklist(&self) -> quarchitect::TextureBlacklist {
         quarchitect::TextureBlacklist::new(
-            {
-                let mut brush_textures: Vec<String> = Vec::new();
-                for i in 0..self.brush_texture_blacklist.len() {
-                    let brush_texture = self.brush_texture_blacklist.get(i);
-                    brush_textures.push(brush_texture.to_string());
-                }
-                brush_textures
-            },
-            {
-                let mut plane_textures: Vec<String> = Vec::new();
-                for i in 0..self.plane_texture_blacklist.len() {
-                    let plane_texture = self.plane_texture_blacklist.get(i);
-                    plane_textures.push(plane_texture.to_string());
-                }
-                plane_textures
-            },
+            self.get_texture_blacklist_strings(true),
+            self.get_texture_blacklist_strings(false),
         )
     }
 
+    fn get_texture_blacklist_strings(&self, brush: bool) -> Vec<String> {
+        let blacklist = if brush {
+            &self.brush_texture_blacklist
+        } else {
+            &self.plane_texture_blacklist
+        };
+
+        let mut textures: Vec<String> = Vec::new();
+        for i in 0..blacklist.len() {
+            textures.push(blacklist.get(i).to_string());
+        }
+        textures
+    }
+
     fn add_child_editor(owner: Spatial, parent: &mut Node, child: Option<Node>) {
         if let Some(mut child) = child {
+            // Tag generated nodes, so hand-added ones can be told apart
+            unsafe {
+                child.set_meta(overrides::GENERATED_META.into(), Variant::from_bool(true));
+            }
+
             // Tag direct children with metadata identifier
             if Variant::from_object(parent) == Variant::from_object(&owner) {
                 unsafe {
@@ -383,3 +919,114 @@ impl QodotMap {
         }
     }
 }
+
+// A resource's path and saved property values, recursing into embedded sub-resources
+fn resource_fingerprint(resource: &Variant) -> String {
+    // Godot's PROPERTY_USAGE_STORAGE
+    const USAGE_STORAGE: i64 = 1;
+    // Far deeper than materials nest, but guards against resources that reference each other
+    const MAX_DEPTH: usize = 4;
+
+    fn fingerprint(resource: gdnative::Resource, depth: usize, out: &mut String) {
+        out.push_str(&resource.get_path().to_string());
+        if depth >= MAX_DEPTH {
+            return;
+        }
+
+        let properties = resource.get_property_list();
+        for i in 0..properties.len() {
+            let property = properties.get_val(i).to_dictionary();
+            if property.get(&Variant::from_str("usage")).to_i64() & USAGE_STORAGE == 0 {
+                continue;
+            }
+
+            let name = property.get(&Variant::from_str("name")).to_godot_string();
+            let value = resource.get(name.clone());
+            out.push_str(&format!(";{}=", name.to_string()));
+
+            match value.try_to_object::<gdnative::Resource>() {
+                // Resources saved to their own file are identified by path. Built-in ones,
+                // addressed as scene.tscn::id, can be edited in place and are hashed instead.
+                Some(nested) if is_resource_file(&nested.get_path().to_string()) => {
+                    out.push_str(&nested.get_path().to_string())
+                }
+                Some(nested) => {
+                    out.push('{');
+                    fingerprint(nested, depth + 1, out);
+                    out.push('}');
+                }
+                None => out.push_str(&value.to_string()),
+            }
+        }
+    }
+
+    fn is_resource_file(path: &str) -> bool {
+        !path.is_empty() && !path.contains("::")
+    }
+
+    let mut out = String::new();
+    if let Some(resource) = resource.try_to_object::<gdnative::Resource>() {
+        fingerprint(resource, 0, &mut out);
+    }
+    out
+}
+
+fn project_relative_path(path: &GodotString) -> String {
+    let path = path.to_string();
+    path.trim_start_matches("res://").trim_matches('/').to_string()
+}
+
+// TrenchBroom treats each subdirectory of the root as a texture collection, so point it at
+// the first directory under the search path, mirroring the layout the texture loader expects
+fn texture_root(base_path: &GodotString) -> String {
+    let relative = project_relative_path(base_path);
+
+    let mut dir = gdnative::Directory::new();
+    let mut directories: Vec<String> = Vec::new();
+    if dir.open(base_path.clone()).is_ok() && dir.list_dir_begin(true, true).is_ok() {
+        loop {
+            let file = dir.get_next();
+            if file.is_empty() {
+                break;
+            }
+
+            if dir.current_is_dir() {
+                directories.push(file.to_string());
+            }
+        }
+        dir.list_dir_end();
+    }
+    directories.sort();
+
+    match directories.first() {
+        Some(directory) if relative.is_empty() => directory.clone(),
+        Some(directory) => format!("{}/{}", relative, directory),
+        None => relative,
+    }
+}
+
+fn save_project_icon(path: &str) -> Result<(), String> {
+    let project_settings = gdnative::ProjectSettings::godot_singleton();
+    let icon = project_settings
+        .get_setting("application/config/icon".into())
+        .to_string();
+
+    if icon.is_empty() {
+        return Err("No project icon set".into());
+    }
+
+    let texture = gdnative::ResourceLoader::godot_singleton()
+        .load(icon.clone().into(), "Texture".into(), false)
+        .and_then(|resource| resource.cast::<gdnative::Texture>());
+
+    let mut image = match texture.and_then(|texture| texture.get_data()) {
+        Some(image) => image,
+        None => return Err(format!("Failed to load {}", icon)),
+    };
+
+    image.decompress();
+    image.resize(32, 32, gdnative::Image::INTERPOLATE_BILINEAR);
+    image
+        .save_png(path.into())
+        .map_err(|err| format!("Failed to save {}: {:?}", path, err))
+}
@@ -0,0 +1,271 @@
+use gdnative::{godot_warn, Dictionary, GodotString, Node, NodePath, Variant};
+use quarchitect::game_data::Property;
+use std::collections::HashMap;
+
+// Marks nodes spawned by a build. Anything without it under a generated node was added by hand.
+pub const GENERATED_META: &str = "qodot_generated";
+
+// The targetname or _tb_name of the map entity a node was spawned from
+pub const ENTITY_KEY_META: &str = "qodot_entity_key";
+
+// A hand-added node, held outside the tree while the entity it belongs to is rebuilt
+pub struct DetachedNode {
+    entity_key: String,
+    parent_path: GodotString,
+    node: Variant,
+}
+
+// Entities are identified by name, falling back to TrenchBroom's object name
+pub fn entity_key(properties: &HashMap<String, Property>) -> Option<String> {
+    ["targetname", "_tb_name"]
+        .iter()
+        .filter_map(|key| match properties.get(*key) {
+            Some(Property::String(value)) if !value.is_empty() => Some(value.clone()),
+            _ => None,
+        })
+        .next()
+}
+
+// Keyed entity nodes under the map. Keys shared by several entities are ambiguous, since
+// nothing tells their entities apart from one build to the next, so no edits are stored
+// against them.
+pub struct EntityNodes {
+    pub unique: HashMap<String, Node>,
+    pub ambiguous: HashMap<String, Vec<Node>>,
+}
+
+pub fn entity_nodes(root: Node) -> EntityNodes {
+    let mut entities: HashMap<String, Vec<Node>> = HashMap::new();
+    collect_entity_nodes(root, &mut entities);
+
+    let (unique, ambiguous): (HashMap<String, Vec<Node>>, HashMap<String, Vec<Node>>) = entities
+        .into_iter()
+        .partition(|(_, nodes)| nodes.len() == 1);
+
+    EntityNodes {
+        unique: unique
+            .into_iter()
+            .map(|(entity_key, nodes)| (entity_key, nodes[0]))
+            .collect(),
+        ambiguous,
+    }
+}
+
+fn collect_entity_nodes(node: Node, entities: &mut HashMap<String, Vec<Node>>) {
+    unsafe {
+        for i in 0..node.get_child_count() {
+            let child = match node.get_child(i) {
+                Some(child) if child.has_meta(GENERATED_META.into()) => child,
+                _ => continue,
+            };
+
+            if let Some(key) = child.get_meta(ENTITY_KEY_META.into()).try_to_string() {
+                entities.entry(key).or_insert_with(Vec::new).push(child);
+            }
+
+            collect_entity_nodes(child, entities);
+        }
+    }
+}
+
+// Removes hand-added nodes from beneath each entity, so they survive the entity being freed.
+// Those under ambiguous entities are detached too, and end up under the map root.
+pub fn detach_user_nodes(entities: &EntityNodes) -> Vec<DetachedNode> {
+    let ambiguous = entities
+        .ambiguous
+        .iter()
+        .flat_map(|(entity_key, nodes)| nodes.iter().map(move |node| (entity_key, node)));
+
+    let mut detached: Vec<DetachedNode> = Vec::new();
+    for (entity_key, entity) in entities.unique.iter().chain(ambiguous) {
+        detach_from(entity_key, *entity, *entity, &mut detached);
+    }
+    detached
+}
+
+fn detach_from(entity_key: &str, entity: Node, mut node: Node, detached: &mut Vec<DetachedNode>) {
+    unsafe {
+        for i in (0..node.get_child_count()).rev() {
+            let child = match node.get_child(i) {
+                Some(child) => child,
+                None => continue,
+            };
+
+            if child.has_meta(GENERATED_META.into()) {
+                // Nested entities hold their own hand-added nodes
+                if !child.has_meta(ENTITY_KEY_META.into()) {
+                    detach_from(entity_key, entity, child, detached);
+                }
+                continue;
+            }
+
+            detached.push(DetachedNode {
+                entity_key: entity_key.to_string(),
+                parent_path: entity.get_path_to(Some(node)).to_godot_string(),
+                node: Variant::from_object(&child),
+            });
+            node.remove_child(Some(child));
+        }
+    }
+}
+
+// Returns detached nodes to their entities. Nodes whose entity no longer exists are
+// placed under the map root rather than discarded.
+pub fn reattach_user_nodes(mut root: Node, entities: &EntityNodes, detached: Vec<DetachedNode>) {
+    let edited_scene_root = unsafe {
+        root.get_tree()
+            .and_then(|tree| tree.get_edited_scene_root())
+    };
+
+    for detached_node in detached {
+        let mut node = match detached_node.node.try_to_object::<Node>() {
+            Some(node) => node,
+            None => continue,
+        };
+
+        let parent = entities
+            .unique
+            .get(&detached_node.entity_key)
+            .and_then(|entity| unsafe {
+                entity.get_node(NodePath::new(&detached_node.parent_path))
+            });
+
+        unsafe {
+            match parent {
+                Some(mut parent) => parent.add_child(Some(node), true),
+                None if entities.ambiguous.contains_key(&detached_node.entity_key) => {
+                    godot_warn!(
+                        "Several entities are named {}, moving {} to the map root",
+                        detached_node.entity_key,
+                        node.get_name().to_string()
+                    );
+                    root.add_child(Some(node), true);
+                }
+                None => {
+                    godot_warn!(
+                        "Entity {} no longer exists, moving {} to the map root",
+                        detached_node.entity_key,
+                        node.get_name().to_string()
+                    );
+                    root.add_child(Some(node), true);
+                }
+            }
+
+            if let Some(edited_scene_root) = edited_scene_root {
+                set_owner_recursive(&mut node, edited_scene_root);
+            }
+        }
+    }
+}
+
+// Removing a node from the tree clears the owner of it and its descendants,
+// which would stop them being saved with the scene
+pub unsafe fn set_owner_recursive(node: &mut Node, owner: Node) {
+    node.set_owner(Some(owner));
+    for i in 0..node.get_child_count() {
+        if let Some(mut child) = node.get_child(i) {
+            set_owner_recursive(&mut child, owner);
+        }
+    }
+}
+
+// Applies stored property values. Overrides are keyed by entity, then by property name,
+// or by a path relative to the entity and a property name, as in "Light:light_energy".
+pub fn apply_property_overrides(entities: &EntityNodes, overrides: &Dictionary) {
+    let entity_keys = overrides.keys();
+    for i in 0..entity_keys.len() {
+        let entity_key = entity_keys.get_val(i);
+
+        let entity = match entities.unique.get(&entity_key.to_string()) {
+            Some(entity) => *entity,
+            None if entities.ambiguous.contains_key(&entity_key.to_string()) => {
+                godot_warn!(
+                    "Several entities are named {}, not applying its overrides",
+                    entity_key.to_string()
+                );
+                continue;
+            }
+            None => {
+                godot_warn!(
+                    "No entity named {} to apply overrides to",
+                    entity_key.to_string()
+                );
+                continue;
+            }
+        };
+
+        let properties = match overrides.get(&entity_key).try_to_dictionary() {
+            Some(properties) => properties,
+            None => continue,
+        };
+
+        let property_keys = properties.keys();
+        for j in 0..property_keys.len() {
+            let property_key = property_keys.get_val(j);
+            let value = properties.get(&property_key);
+            let property_key = property_key.to_string();
+
+            let (path, property) = match property_key.rfind(':') {
+                Some(index) => (&property_key[..index], &property_key[index + 1..]),
+                None => (".", property_key.as_str()),
+            };
+
+            let node = unsafe { entity.get_node(NodePath::from_str(path)) };
+            match node {
+                Some(mut node) => unsafe { node.set(property.into(), value) },
+                None => godot_warn!(
+                    "No node at {} under entity {} to apply overrides to",
+                    path,
+                    entity_key.to_string()
+                ),
+            }
+        }
+    }
+}
+
+// Where an override for the given node and property is stored: its entity's key,
+// and the property key within that entity's overrides
+pub fn override_key(
+    entities: &EntityNodes,
+    node: Node,
+    property: &str,
+) -> Result<(String, String), String> {
+    let node_variant = Variant::from_object(&node);
+
+    // Walk up to the nearest ancestor that was spawned from a keyed entity
+    let mut current = Some(node);
+    while let Some(candidate) = current {
+        let candidate_variant = Variant::from_object(&candidate);
+
+        if let Some((entity_key, _)) = entities.ambiguous.iter().find(|(_, nodes)| {
+            nodes
+                .iter()
+                .any(|entity| Variant::from_object(entity) == candidate_variant)
+        }) {
+            return Err(format!(
+                "Several entities are named {}, give this one a unique targetname or _tb_name",
+                entity_key
+            ));
+        }
+
+        let entity_key = entities
+            .unique
+            .iter()
+            .find(|(_, entity)| Variant::from_object(*entity) == candidate_variant)
+            .map(|(entity_key, _)| entity_key.clone());
+
+        if let Some(entity_key) = entity_key {
+            let property_key = if candidate_variant == node_variant {
+                property.to_string()
+            } else {
+                let path = unsafe { candidate.get_path_to(Some(node)) };
+                format!("{}:{}", path.to_godot_string().to_string(), property)
+            };
+            return Ok((entity_key, property_key));
+        }
+
+        current = unsafe { candidate.get_parent() };
+    }
+
+    Err("Node is not part of an entity with a targetname or _tb_name".into())
+}
@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// How often the watched paths are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Editors often write a file in several steps, so wait for changes to settle before reporting
+const DEBOUNCE: Duration = Duration::from_millis(750);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+// Polls a set of files for modifications. Directories are never walked, so callers pass the
+// individual files a build read.
+pub struct FileWatcher {
+    paths: Vec<PathBuf>,
+    stamps: Vec<Option<FileStamp>>,
+    last_poll: Instant,
+    changed_at: Option<Instant>,
+    changed: HashSet<usize>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> FileWatcher {
+        let stamps = paths.iter().map(|path| stamp(path)).collect();
+        FileWatcher {
+            paths,
+            stamps,
+            last_poll: Instant::now(),
+            changed_at: None,
+            changed: HashSet::new(),
+        }
+    }
+
+    // Returns the watched paths that changed, once they've gone quiet for the debounce period
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        if now.duration_since(self.last_poll) < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = now;
+
+        let mut settled = true;
+        for (index, path) in self.paths.iter().enumerate() {
+            let stamp = stamp(path);
+            if stamp != self.stamps[index] {
+                self.stamps[index] = stamp;
+                self.changed.insert(index);
+                settled = false;
+            }
+        }
+
+        if !settled {
+            self.changed_at = Some(now);
+            return Vec::new();
+        }
+
+        match self.changed_at {
+            Some(changed_at) if now.duration_since(changed_at) >= DEBOUNCE => {
+                self.changed_at = None;
+                let paths = &self.paths;
+                self.changed
+                    .drain()
+                    .map(|index| paths[index].clone())
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+
+    Some(FileStamp {
+        modified: metadata.modified().ok(),
+        len: metadata.len(),
+    })
+}